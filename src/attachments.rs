@@ -0,0 +1,78 @@
+use crate::progress::{ProgressPhase, ProgressTracker};
+use crate::{first_error, Exporter, FileExportError, Result};
+use rayon::prelude::*;
+use snafu::ResultExt;
+use std::path::{Path, PathBuf};
+
+impl<'a> Exporter<'a> {
+    // Remember that the attachment `src` should be copied to `destinations` (usually just one, see
+    // Exporter::colocate_attachments). Attachments are copied in a single pass once all notes have
+    // been processed (see copy_pending_attachments), which copies each attachment only once,
+    // however often it's queued.
+    pub(crate) fn queue_attachment(&self, src: &Path, destinations: Vec<PathBuf>) {
+        self.pending_attachments
+            .lock()
+            .expect("pending attachments lock poisoned")
+            .entry(src.to_path_buf())
+            .or_insert(destinations);
+    }
+
+    // Whether the attachment `src` is waiting to be copied.
+    pub(crate) fn is_pending_attachment(&self, src: &Path) -> bool {
+        self.pending_attachments
+            .lock()
+            .expect("pending attachments lock poisoned")
+            .contains_key(src)
+    }
+
+    // Export the attachment `path`, which is embedded by one of the notes set through
+    // Exporter::export_files and therefore needs to be exported along with it.
+    pub(crate) fn export_dependency(&self, path: &Path) -> Result<()> {
+        if self.is_pending_attachment(path) {
+            return Ok(());
+        }
+        let relative_path = match path.strip_prefix(self.output_root()) {
+            Ok(relative_path) => relative_path,
+            Err(_) => return Ok(()),
+        };
+        let destination = self.output_path(path, &self.destination.join(relative_path));
+        self.export_note(path, &destination)
+    }
+
+    // Return the number of queued attachments which aren't among `files`, as these were queued as
+    // dependencies of the notes set through Exporter::export_files.
+    pub(crate) fn pending_dependencies(&self, files: &[PathBuf]) -> usize {
+        self.pending_attachments
+            .lock()
+            .expect("pending attachments lock poisoned")
+            .keys()
+            .filter(|src| !files.contains(src))
+            .count()
+    }
+
+    // Copy all queued attachments in parallel, reporting each of them to `progress`.
+    pub(crate) fn copy_pending_attachments(
+        &self,
+        progress: Option<&ProgressTracker>,
+    ) -> Result<()> {
+        let mut attachments: Vec<(PathBuf, Vec<PathBuf>)> = std::mem::take(
+            &mut *self
+                .pending_attachments
+                .lock()
+                .expect("pending attachments lock poisoned"),
+        )
+        .into_iter()
+        .collect();
+        attachments.sort();
+        first_error(attachments.into_par_iter().map(|(src, destinations)| {
+            for dest in destinations {
+                self.timed("copy", || self.export_attachment(&src, &dest))
+                    .context(FileExportError { path: &src })?;
+            }
+            if let Some(progress) = progress {
+                progress.advance(ProgressPhase::Attachments);
+            }
+            Ok(())
+        }))
+    }
+}
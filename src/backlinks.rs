@@ -0,0 +1,191 @@
+use crate::references::ObsidianNoteReference;
+use crate::{
+    is_absolute_url, is_markdown_file, normalize_path, parser_options, Context, Exporter,
+    MarkdownEvents,
+};
+use percent_encoding::percent_decode_str;
+use pulldown_cmark::{CowStr, Event, HeadingLevel, LinkType, Parser, Tag};
+use rayon::prelude::*;
+use regex::Regex;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where [Exporter::inject_backlinks][crate::Exporter::inject_backlinks] lists the notes which
+/// link to a note.
+pub enum BacklinkStrategy {
+    /// Add a "Backlinks" section with a list of links to the end of the note.
+    Footer,
+    /// Add a list of links to the frontmatter of the note, under the given key.
+    FrontmatterKey(String),
+}
+
+// The ways in which a note can refer to another file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReferenceKind {
+    // A wikilink (`[[file]]`) or markdown link (`[text](file)`).
+    Link,
+    // A wikilink embed (`![[file]]`).
+    Embed,
+    // A markdown image (`![alt](file)`).
+    Image,
+}
+
+impl<'a> Exporter<'a> {
+    // Map every note under start_at to the exported notes which link to it, sorted by path.
+    //
+    // This reads all notes before any of them are exported. Notes which can't be read are left
+    // out here, the error is reported when the note itself is exported.
+    pub(crate) fn build_backlinks(&self) -> HashMap<PathBuf, Vec<PathBuf>> {
+        let notes: Vec<&PathBuf> = self.exported_notes.iter().collect();
+        let links: Vec<(PathBuf, PathBuf)> = notes
+            .par_iter()
+            .flat_map(|note| {
+                self.note_links(note)
+                    .into_iter()
+                    .map(|target| (target, note.to_path_buf()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let mut backlinks: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for (target, source) in links {
+            backlinks.entry(target).or_default().push(source);
+        }
+        for sources in backlinks.values_mut() {
+            sources.sort();
+            sources.dedup();
+        }
+        backlinks
+    }
+
+    // Return the other notes which the note at `path` links to. Embeds are not counted as links.
+    fn note_links(&self, path: &Path) -> Vec<PathBuf> {
+        self.note_references(path)
+            .into_iter()
+            .filter(|(target, kind)| {
+                *kind == ReferenceKind::Link && target != path && is_markdown_file(target)
+            })
+            .map(|(target, _)| target)
+            .collect()
+    }
+
+    // Return the files in the vault which the note at `path` links to or embeds, along with the
+    // kind of each reference.
+    pub(crate) fn note_references(&self, path: &Path) -> Vec<(PathBuf, ReferenceKind)> {
+        lazy_static! {
+            static ref WIKILINK_RE: Regex = Regex::new(r"(!?)\[\[(?P<ref>[^\]]+)\]\]").unwrap();
+        }
+        let content = match self.read_note(path, false) {
+            Ok(Some((_, content))) => content,
+            _ => return vec![],
+        };
+        let note_dir = path
+            .parent()
+            .expect("obsidian content files should always have a parent");
+
+        let mut targets = vec![];
+        let mut text = String::new();
+        let mut in_code_block = false;
+        for event in Parser::new_ext(&content, parser_options()) {
+            match event {
+                Event::Text(content) if !in_code_block => {
+                    text.push_str(&content);
+                    continue;
+                }
+                Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+                Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+                Event::Start(Tag::Link(_, url, _)) if !is_absolute_url(&url) => {
+                    targets.extend(self.markdown_link_target(note_dir, &url, ReferenceKind::Link));
+                }
+                Event::Start(Tag::Image(_, url, _)) if !is_absolute_url(&url) => {
+                    targets.extend(self.markdown_link_target(note_dir, &url, ReferenceKind::Image));
+                }
+                _ => {}
+            }
+            // Wikilinks may be split across several text events.
+            for captures in WIKILINK_RE.captures_iter(&text) {
+                let kind = match captures[1].is_empty() {
+                    true => ReferenceKind::Link,
+                    false => ReferenceKind::Embed,
+                };
+                let file = match ObsidianNoteReference::from_str(&captures["ref"]).file {
+                    Some(file) => file,
+                    None => continue,
+                };
+                targets.extend(
+                    self.lookup_filename(file, path)
+                        .map(|target| (target.clone(), kind)),
+                );
+            }
+            text.clear();
+        }
+        targets
+    }
+
+    // Return the file in the vault which the markdown link `url` in a note in `note_dir` points
+    // to, along with `kind`.
+    fn markdown_link_target(
+        &self,
+        note_dir: &Path,
+        url: &str,
+        kind: ReferenceKind,
+    ) -> Option<(PathBuf, ReferenceKind)> {
+        let path = url.split('#').next().unwrap_or_default();
+        let path = percent_decode_str(path).decode_utf8_lossy();
+        let target = normalize_path(&note_dir.join(path.as_ref()));
+        self.vault_contents
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|file| normalize_path(file) == target)
+            .map(|file| (file.clone(), kind))
+    }
+
+    // Add the notes which link to the note described by `context` to its frontmatter or events,
+    // according to `strategy`.
+    pub(crate) fn add_backlinks(
+        &self,
+        strategy: &BacklinkStrategy,
+        context: &mut Context,
+        events: &mut MarkdownEvents,
+    ) {
+        let sources = match self.backlinks.get(context.current_file()) {
+            Some(sources) => sources,
+            None => return,
+        };
+        match strategy {
+            BacklinkStrategy::FrontmatterKey(key) => {
+                let links = sources
+                    .iter()
+                    .map(|source| Value::String(self.link_to_path(source, context)))
+                    .collect();
+                context
+                    .frontmatter
+                    .insert(Value::String(key.to_string()), Value::Sequence(links));
+            }
+            BacklinkStrategy::Footer => {
+                let heading = Tag::Heading(HeadingLevel::H2, None, vec![]);
+                events.push(Event::Start(heading.clone()));
+                events.push(Event::Text(CowStr::Borrowed("Backlinks")));
+                events.push(Event::End(heading));
+                events.push(Event::Start(Tag::List(None)));
+                for source in sources {
+                    let link = Tag::Link(
+                        LinkType::Inline,
+                        CowStr::from(self.link_to_path(source, context)),
+                        CowStr::Borrowed(""),
+                    );
+                    let name = source.file_stem().unwrap_or_default().to_string_lossy();
+                    events.push(Event::Start(Tag::Item));
+                    events.push(Event::Start(link.clone()));
+                    events.push(Event::Text(CowStr::from(name.into_owned())));
+                    events.push(Event::End(link));
+                    events.push(Event::End(Tag::Item));
+                }
+                events.push(Event::End(Tag::List(None)));
+            }
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::tags::{frontmatter_tags, tag_matches};
+use crate::Frontmatter;
+use regex::Regex;
+use serde_yaml::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    static ref COMPARISON_RE: Regex =
+        Regex::new(r"^(?P<property>[\w.]+)\s*(?P<op>==|!=|>=|<=|>|<)\s*(?P<value>.+)$").unwrap();
+    static ref FUNCTION_RE: Regex =
+        Regex::new(r"^(?P<negate>!)?\s*file\.(?P<func>hasTag|inFolder)\((?P<args>.*)\)$").unwrap();
+    static ref PROPERTY_RE: Regex = Regex::new(r"^(?P<negate>!)?\s*(?P<property>[\w.]+)$").unwrap();
+}
+
+/// The rows and columns of a table view of an Obsidian Base (`.base` file).
+pub struct BaseTable<'a> {
+    /// The properties shown by the view, such as `file.name` or `note.author`.
+    pub columns: Vec<String>,
+    /// The header to display for each column. This is the `displayName` configured for the
+    /// property, or the name of the property without its `note.` or `file.` prefix.
+    pub headers: Vec<String>,
+    /// The notes matching the filters of the view, along with their frontmatter.
+    pub rows: Vec<(&'a PathBuf, &'a Frontmatter)>,
+}
+
+/// Evaluate the first table view of the Base defined by `definition` against the notes in
+/// `index`.
+///
+/// Only a subset of the filter syntax of Bases is supported: `and`, `or` and `not` groups,
+/// comparisons of a property against a literal (`status == "done"`, `rating >= 3`), the
+/// `file.hasTag()` and `file.inFolder()` functions and bare properties, which match when the
+/// property is set to a non-empty value. Unsupported expressions never match.
+pub fn evaluate_base<'a>(
+    definition: &str,
+    index: &'a HashMap<PathBuf, Frontmatter>,
+    root: &Path,
+) -> serde_yaml::Result<BaseTable<'a>> {
+    let base: Value = match definition.trim().is_empty() {
+        true => Value::Mapping(Default::default()),
+        false => serde_yaml::from_str(definition)?,
+    };
+    let view = base
+        .get("views")
+        .and_then(Value::as_sequence)
+        .and_then(|views| {
+            views
+                .iter()
+                .find(|view| view.get("type").and_then(Value::as_str) == Some("table"))
+        });
+
+    let mut rows: Vec<(&PathBuf, &Frontmatter)> = index
+        .iter()
+        .filter(|(path, frontmatter)| {
+            let note = Note {
+                path,
+                frontmatter,
+                root,
+            };
+            [
+                base.get("filters"),
+                view.and_then(|view| view.get("filters")),
+            ]
+            .iter()
+            .flatten()
+            .all(|filter| note.matches(filter))
+        })
+        .collect();
+    rows.sort_by(|a, b| a.0.cmp(b.0));
+
+    if let Some(sort) = view
+        .and_then(|view| view.get("sort"))
+        .and_then(Value::as_sequence)
+    {
+        for rule in sort.iter().rev() {
+            let property = match rule.get("property").and_then(Value::as_str) {
+                Some(property) => property,
+                None => continue,
+            };
+            let descending = rule
+                .get("direction")
+                .and_then(Value::as_str)
+                .map(|direction| direction.eq_ignore_ascii_case("desc"))
+                .unwrap_or(false);
+            rows.sort_by(|a, b| {
+                let a = Note::new(a, root).property(property);
+                let b = Note::new(b, root).property(property);
+                let ordering = compare_values(&a, &b).unwrap_or(Ordering::Equal);
+                match descending {
+                    true => ordering.reverse(),
+                    false => ordering,
+                }
+            });
+        }
+    }
+    if let Some(limit) = view
+        .and_then(|view| view.get("limit"))
+        .and_then(Value::as_u64)
+    {
+        rows.truncate(limit as usize);
+    }
+
+    let columns: Vec<String> = view
+        .and_then(|view| view.get("order"))
+        .and_then(Value::as_sequence)
+        .map(|order| {
+            order
+                .iter()
+                .filter_map(|property| property.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_else(|| vec!["file.name".to_string()]);
+    let headers = columns
+        .iter()
+        .map(|column| {
+            base.get("properties")
+                .and_then(|properties| properties.get(column.as_str()))
+                .and_then(|property| property.get("displayName"))
+                .and_then(Value::as_str)
+                .unwrap_or_else(|| {
+                    column
+                        .strip_prefix("note.")
+                        .or_else(|| column.strip_prefix("file."))
+                        .unwrap_or(column)
+                })
+                .to_string()
+        })
+        .collect();
+
+    Ok(BaseTable {
+        columns,
+        headers,
+        rows,
+    })
+}
+
+/// Return the value of `property` for the note at `path`, or `Value::Null` when it isn't set.
+///
+/// Properties may refer to frontmatter (`author` or `note.author`) or to information about the
+/// file itself (`file.name`, `file.path`, `file.folder`, `file.ext` and `file.tags`).
+pub fn note_property(path: &Path, frontmatter: &Frontmatter, root: &Path, property: &str) -> Value {
+    Note {
+        path,
+        frontmatter,
+        root,
+    }
+    .property(property)
+}
+
+struct Note<'a> {
+    path: &'a Path,
+    frontmatter: &'a Frontmatter,
+    root: &'a Path,
+}
+
+impl<'a> Note<'a> {
+    fn new(row: &(&'a PathBuf, &'a Frontmatter), root: &'a Path) -> Note<'a> {
+        Note {
+            path: row.0,
+            frontmatter: row.1,
+            root,
+        }
+    }
+
+    fn relative_path(&self) -> &Path {
+        self.path.strip_prefix(self.root).unwrap_or(self.path)
+    }
+
+    fn property(&self, property: &str) -> Value {
+        let string = |value: Option<&std::ffi::OsStr>| {
+            Value::String(value.unwrap_or_default().to_string_lossy().into_owned())
+        };
+        match property {
+            "file.name" => string(self.path.file_stem()),
+            "file.ext" => string(self.path.extension()),
+            "file.path" => string(Some(self.relative_path().as_os_str())),
+            "file.folder" => string(self.relative_path().parent().map(Path::as_os_str)),
+            "file.tags" => Value::Sequence(
+                frontmatter_tags(self.frontmatter)
+                    .into_iter()
+                    .map(Value::String)
+                    .collect(),
+            ),
+            _ => self
+                .frontmatter
+                .get(&Value::String(
+                    property
+                        .strip_prefix("note.")
+                        .unwrap_or(property)
+                        .to_string(),
+                ))
+                .cloned()
+                .unwrap_or(Value::Null),
+        }
+    }
+
+    fn matches(&self, filter: &Value) -> bool {
+        match filter {
+            Value::String(expression) => self.matches_expression(expression.trim()),
+            Value::Mapping(_) => {
+                let filters = |key: &str| {
+                    filter
+                        .get(key)
+                        .and_then(Value::as_sequence)
+                        .map(|filters| filters.iter())
+                };
+                filters("and").is_none_or(|mut f| f.all(|filter| self.matches(filter)))
+                    && filters("or").is_none_or(|mut f| f.any(|filter| self.matches(filter)))
+                    && filters("not").is_none_or(|mut f| !f.any(|filter| self.matches(filter)))
+            }
+            _ => false,
+        }
+    }
+
+    fn matches_expression(&self, expression: &str) -> bool {
+        if let Some(captures) = FUNCTION_RE.captures(expression) {
+            let args: Vec<String> = captures["args"]
+                .split(',')
+                .map(|arg| {
+                    arg.trim()
+                        .trim_matches(|c| c == '"' || c == '\'')
+                        .to_string()
+                })
+                .filter(|arg| !arg.is_empty())
+                .collect();
+            let matches = match &captures["func"] {
+                "hasTag" => {
+                    let tags = frontmatter_tags(self.frontmatter);
+                    args.iter()
+                        .any(|arg| tags.iter().any(|tag| tag_matches(tag, arg)))
+                }
+                _ => args.iter().any(|folder| {
+                    self.relative_path()
+                        .parent()
+                        .is_some_and(|parent| parent.starts_with(folder))
+                }),
+            };
+            return matches != captures.name("negate").is_some();
+        }
+        if let Some(captures) = COMPARISON_RE.captures(expression) {
+            let value = self.property(&captures["property"]);
+            let literal: Value = match serde_yaml::from_str(&captures["value"]) {
+                Ok(literal) => literal,
+                Err(_) => return false,
+            };
+            let ordering = compare_values(&value, &literal);
+            return match &captures["op"] {
+                "==" => ordering == Some(Ordering::Equal),
+                "!=" => ordering != Some(Ordering::Equal),
+                ">" => ordering == Some(Ordering::Greater),
+                "<" => ordering == Some(Ordering::Less),
+                ">=" => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                _ => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            };
+        }
+        if let Some(captures) = PROPERTY_RE.captures(expression) {
+            let truthy = match self.property(&captures["property"]) {
+                Value::Null | Value::Bool(false) => false,
+                Value::String(value) => !value.is_empty(),
+                Value::Sequence(values) => !values.is_empty(),
+                _ => true,
+            };
+            return truthy != captures.name("negate").is_some();
+        }
+        false
+    }
+}
+
+/// Compare two frontmatter values. Numbers are compared numerically and strings lexically, other
+/// combinations only compare equal when the values are identical.
+pub fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Null, _) => None,
+        (_, Value::Null) => None,
+        (a, b) if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn index() -> HashMap<PathBuf, Frontmatter> {
+        let mut index = HashMap::new();
+        for (path, frontmatter) in [
+            ("/vault/books/a.md", "status: done\nrating: 4\ntags: [book]"),
+            ("/vault/books/b.md", "status: todo\nrating: 2\ntags: [book]"),
+            ("/vault/c.md", "status: done\nrating: 5"),
+        ] {
+            index.insert(
+                PathBuf::from(path),
+                serde_yaml::from_str(frontmatter).unwrap(),
+            );
+        }
+        index
+    }
+
+    fn names(table: &BaseTable) -> Vec<String> {
+        table
+            .rows
+            .iter()
+            .map(|(path, _)| path.file_stem().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn filters_and_columns() {
+        let index = index();
+        let table = evaluate_base(
+            r#"
+filters:
+  and:
+    - file.hasTag("book")
+    - 'rating >= 3'
+views:
+  - type: table
+    order: [file.name, note.status]
+"#,
+            &index,
+            Path::new("/vault"),
+        )
+        .unwrap();
+        assert_eq!(names(&table), vec!["a"]);
+        assert_eq!(table.headers, vec!["name", "status"]);
+    }
+
+    #[test]
+    fn view_filters_and_sorting() {
+        let index = index();
+        let table = evaluate_base(
+            r#"
+views:
+  - type: table
+    filters:
+      or:
+        - 'status == "done"'
+        - file.inFolder("books")
+      not:
+        - 'rating < 3'
+    sort:
+      - property: rating
+        direction: DESC
+"#,
+            &index,
+            Path::new("/vault"),
+        )
+        .unwrap();
+        assert_eq!(names(&table), vec!["c", "a"]);
+        assert_eq!(table.columns, vec!["file.name"]);
+    }
+}
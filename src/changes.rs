@@ -0,0 +1,88 @@
+use crate::frontmatter::frontmatter_from_str;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Describes how an exported note differs from the same note in a previous export.
+pub enum ChangeKind {
+    /// The note didn't exist in the previous export.
+    Added,
+    /// The note is identical to the previous export.
+    Unchanged,
+    /// Only the body of the note changed.
+    Body,
+    /// Only the frontmatter of the note changed.
+    Frontmatter,
+    /// Both the frontmatter and the body of the note changed.
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The change of a single note compared to a previous export, as reported by
+/// [Exporter::changes][crate::Exporter::changes].
+pub struct NoteChange {
+    /// The path of the exported note, relative to the destination.
+    pub path: PathBuf,
+    /// How the note changed.
+    pub kind: ChangeKind,
+}
+
+/// Classify the change between the `previous` and `current` contents of an exported note.
+///
+/// Frontmatter is compared by value, so changes in formatting or key order alone don't count as
+/// a change. Bodies are compared ignoring leading and trailing whitespace.
+pub fn classify_change(previous: Option<&str>, current: &str) -> ChangeKind {
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return ChangeKind::Added,
+    };
+    let split = |content: &str| {
+        matter::matter(content).unwrap_or_else(|| (String::new(), content.to_string()))
+    };
+    let (previous_frontmatter, previous_body) = split(previous);
+    let (current_frontmatter, current_body) = split(current);
+
+    let frontmatter_changed = match (
+        frontmatter_from_str(&previous_frontmatter),
+        frontmatter_from_str(&current_frontmatter),
+    ) {
+        (Ok(previous), Ok(current)) => previous != current,
+        _ => previous_frontmatter != current_frontmatter,
+    };
+    let body_changed = previous_body.trim() != current_body.trim();
+
+    match (frontmatter_changed, body_changed) {
+        (false, false) => ChangeKind::Unchanged,
+        (false, true) => ChangeKind::Body,
+        (true, false) => ChangeKind::Frontmatter,
+        (true, true) => ChangeKind::Both,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn classify_changes() {
+        let note = "---\na: 1\nb: 2\n---\n\nBody\n";
+        assert_eq!(classify_change(None, note), ChangeKind::Added);
+        assert_eq!(
+            classify_change(Some(note), "---\nb: 2\na: 1\n---\n\nBody\n"),
+            ChangeKind::Unchanged
+        );
+        assert_eq!(
+            classify_change(Some(note), "---\na: 1\nb: 3\n---\n\nBody\n"),
+            ChangeKind::Frontmatter
+        );
+        assert_eq!(
+            classify_change(Some(note), "---\na: 1\nb: 2\n---\n\nNew body\n"),
+            ChangeKind::Body
+        );
+        assert_eq!(classify_change(Some(note), "New body\n"), ChangeKind::Both);
+        assert_eq!(
+            classify_change(Some("Body\n"), "Body\n"),
+            ChangeKind::Unchanged
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use crate::schema::schema_violations;
+use crate::{
+    first_error, is_empty_note, is_markdown_file, Context, Exporter, FileExportError, Result,
+};
+use rayon::prelude::*;
+use snafu::ResultExt;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The kinds of problems reported by [Exporter::check][crate::Exporter::check].
+pub enum IssueKind {
+    /// A link to a note or file which doesn't exist in the vault.
+    BrokenLink,
+    /// An embed of a note or file which doesn't exist in the vault.
+    MissingEmbed,
+    /// Several files in the vault have the same filename, which makes references to them by name
+    /// ambiguous.
+    DuplicateBasename,
+    /// The frontmatter of a note doesn't match the schema set through
+    /// [Exporter::frontmatter_schema][crate::Exporter::frontmatter_schema].
+    SchemaViolation,
+    /// A note without any content.
+    EmptyNote,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A problem found in the vault by [Exporter::check][crate::Exporter::check].
+pub struct Issue {
+    /// The file the problem was found in.
+    pub path: PathBuf,
+    /// The kind of problem.
+    pub kind: IssueKind,
+    /// The details of the problem: the reference which couldn't be resolved, the other files with
+    /// the same name or the schema violation. Empty for empty notes.
+    pub detail: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path.display())?;
+        match self.kind {
+            IssueKind::BrokenLink => write!(f, "broken link to '{}'", self.detail),
+            IssueKind::MissingEmbed => write!(f, "missing embed '{}'", self.detail),
+            IssueKind::DuplicateBasename => write!(f, "filename shared with {}", self.detail),
+            IssueKind::SchemaViolation => {
+                write!(f, "frontmatter does not match the schema: {}", self.detail)
+            }
+            IssueKind::EmptyNote => write!(f, "empty note"),
+        }
+    }
+}
+
+impl<'a> Exporter<'a> {
+    /// Check the vault for problems without exporting it, returning every problem found.
+    ///
+    /// All notes which would be exported are parsed, resolving their links and embeds the same way
+    /// an export does, but nothing is written to the destination. This reports links and embeds
+    /// which can't be resolved, files sharing the same filename, frontmatter which doesn't match
+    /// the schema set through [Exporter::frontmatter_schema] and empty notes. Issues are sorted
+    /// by path.
+    ///
+    /// Warnings are printed as they would be during an export, except that schema violations are
+    /// only returned as issues, regardless of whether the schema is strict.
+    pub fn check(&mut self) -> Result<Vec<Issue>> {
+        self.in_thread_pool(Self::check_vault)
+    }
+
+    fn check_vault(&mut self) -> Result<Vec<Issue>> {
+        self.prepare_run()?;
+        let notes: Vec<PathBuf> = self
+            .exported_files()
+            .into_iter()
+            .filter(|file| is_markdown_file(file) && !self.is_verbatim_file(file))
+            .collect();
+        first_error(notes.par_iter().map(|note| self.check_note(note)))?;
+
+        let mut issues = std::mem::take(&mut *self.issues.lock().expect("issues lock poisoned"));
+        issues.extend(self.duplicate_basenames());
+        issues.sort();
+        issues.dedup();
+        Ok(issues)
+    }
+
+    // Remember a problem found while parsing notes, to be returned by Exporter::check.
+    pub(crate) fn record_issue(&self, kind: IssueKind, path: &Path, detail: &str) {
+        self.issues
+            .lock()
+            .expect("issues lock poisoned")
+            .push(Issue {
+                path: path.to_path_buf(),
+                kind,
+                detail: detail.to_string(),
+            });
+    }
+
+    // Parse the note at `path`, recording the problems found in it.
+    fn check_note(&self, path: &Path) -> Result<()> {
+        let mut context = Context::new(path.to_path_buf(), path.to_path_buf());
+        context.vault_notes = self.frontmatter_index.clone();
+        let note = match self
+            .parse_obsidian_note(path, &context)
+            .context(FileExportError { path })?
+        {
+            Some(note) => note,
+            None => return Ok(()),
+        };
+        if let Some(schema) = &self.schema {
+            for violation in schema_violations(schema, &note.frontmatter) {
+                self.record_issue(IssueKind::SchemaViolation, path, &violation);
+            }
+        }
+        if is_empty_note(&note.events) {
+            self.record_issue(IssueKind::EmptyNote, path, "");
+        }
+        Ok(())
+    }
+
+    // Return an issue for every file in the vault which has the same filename as another file,
+    // ignoring case like references do.
+    fn duplicate_basenames(&self) -> Vec<Issue> {
+        let mut files: BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+        for file in self.vault_contents.as_ref().unwrap() {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            files.entry(name.to_lowercase()).or_default().push(file);
+        }
+        let mut issues = vec![];
+        for paths in files.values().filter(|paths| paths.len() > 1) {
+            for path in paths {
+                let others: Vec<String> = paths
+                    .iter()
+                    .filter(|other| other != &path)
+                    .map(|other| format!("'{}'", other.display()))
+                    .collect();
+                issues.push(Issue {
+                    path: path.to_path_buf(),
+                    kind: IssueKind::DuplicateBasename,
+                    detail: others.join(", "),
+                });
+            }
+        }
+        issues
+    }
+}
@@ -0,0 +1,161 @@
+use crate::summary::Warning;
+use crate::{is_markdown_file, Exporter};
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// What [Exporter::colocate_attachments][crate::Exporter::colocate_attachments] does with
+/// attachments which are referenced by more than one note.
+pub enum SharedAttachments {
+    /// Copy the attachment into the folder of every note which references it.
+    Duplicate,
+    /// Export the attachment to its usual location, which all notes link to.
+    Centralize,
+}
+
+impl<'a> Exporter<'a> {
+    // Map every attachment to the notes under start_at which link to or embed it, sorted by path.
+    //
+    // Like build_backlinks, this reads all notes before any of them are exported.
+    pub(crate) fn build_attachment_references(&self) -> HashMap<PathBuf, Vec<PathBuf>> {
+        let notes: Vec<&PathBuf> = self
+            .vault_contents
+            .as_ref()
+            .unwrap()
+            .iter()
+            .filter(|file| file.starts_with(&self.start_at))
+            .filter(|file| is_markdown_file(file) && !self.is_verbatim_file(file))
+            .collect();
+        let references: Vec<(PathBuf, PathBuf)> = notes
+            .par_iter()
+            .flat_map(|note| {
+                self.note_references(note)
+                    .into_iter()
+                    .filter(|(target, _)| !is_markdown_file(target))
+                    .map(|(target, _)| (target, note.to_path_buf()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let mut attachments: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for (attachment, note) in references {
+            attachments.entry(attachment).or_default().push(note);
+        }
+        for notes in attachments.values_mut() {
+            notes.sort();
+            notes.dedup();
+        }
+        for attachment in self.colliding_attachments(&attachments) {
+            attachments.remove(&attachment);
+        }
+        attachments
+    }
+
+    // Return the attachments which would be co-located at the same path as a different attachment,
+    // such as `a/img.png` and `b/img.png` embedded by the same note. These are exported to their
+    // usual location instead, with a warning.
+    fn colliding_attachments(&self, attachments: &HashMap<PathBuf, Vec<PathBuf>>) -> Vec<PathBuf> {
+        let (pattern, shared) = match &self.colocate_attachments {
+            Some(colocate) => colocate,
+            None => return vec![],
+        };
+        let mut destinations: HashMap<PathBuf, Vec<(&PathBuf, &PathBuf)>> = HashMap::new();
+        for (attachment, notes) in attachments {
+            if notes.len() > 1 && *shared == SharedAttachments::Centralize {
+                continue;
+            }
+            for note in notes {
+                destinations
+                    .entry(colocated_path(pattern, note, attachment))
+                    .or_default()
+                    .push((attachment, note));
+            }
+        }
+        let mut collisions: Vec<_> = destinations
+            .into_iter()
+            .filter(|(_, references)| {
+                references
+                    .iter()
+                    .any(|(attachment, _)| *attachment != references[0].0)
+            })
+            .collect();
+        collisions.sort();
+
+        let mut colliding = vec![];
+        for (destination, mut references) in collisions {
+            references.sort();
+            let mut warning = Warning::new(
+                "Attachments would be co-located at the same path, exporting them to their usual location",
+            )
+            .detail("Destination", destination.display());
+            for (attachment, note) in references {
+                warning = warning
+                    .detail("Attachment", attachment.display())
+                    .source(note);
+                colliding.push(attachment.to_path_buf());
+            }
+            self.warn(warning);
+        }
+        colliding
+    }
+
+    // Return the notes the attachment `path` is co-located with, along with the path in the vault
+    // it's placed at for each of them. Returns None when the attachment is exported to its usual
+    // location instead.
+    fn colocated_paths(&self, path: &Path) -> Option<Vec<(&PathBuf, PathBuf)>> {
+        let (pattern, shared) = self.colocate_attachments.as_ref()?;
+        let notes = self.attachment_references.get(path)?;
+        if notes.len() > 1 && *shared == SharedAttachments::Centralize {
+            return None;
+        }
+        Some(
+            notes
+                .iter()
+                .map(|note| (note, colocated_path(pattern, note, path)))
+                .collect(),
+        )
+    }
+
+    // Return the path in the vault which links from the note `note` to the attachment `path`
+    // should point to, or None when the attachment is exported to its usual location.
+    //
+    // Notes which embed another note don't have a copy of the attachments of the embedded note,
+    // so links from these point to the copy next to the note which references it.
+    pub(crate) fn attachment_location(&self, path: &Path, note: &Path) -> Option<PathBuf> {
+        let mut paths = self.colocated_paths(path)?;
+        let index = paths
+            .iter()
+            .position(|(colocated_note, _)| colocated_note.as_path() == note)
+            .unwrap_or(0);
+        Some(paths.swap_remove(index).1)
+    }
+
+    // Return the destinations the attachment `src` should be copied to, when its usual
+    // destination is `dest`.
+    pub(crate) fn attachment_destinations(&self, src: &Path, dest: &Path) -> Vec<PathBuf> {
+        let paths = match self.colocated_paths(src) {
+            Some(paths) => paths,
+            None => return vec![dest.to_path_buf()],
+        };
+        paths
+            .into_iter()
+            .filter_map(|(_, path)| {
+                let relative_path = path.strip_prefix(self.output_root()).ok()?;
+                Some(self.output_path(src, &self.destination.join(relative_path)))
+            })
+            .collect()
+    }
+}
+
+// Return the path in the vault of the attachment `attachment` when it's co-located with `note`,
+// according to `pattern`.
+fn colocated_path(pattern: &str, note: &Path, attachment: &Path) -> PathBuf {
+    let stem = note.file_stem().unwrap_or_default().to_string_lossy();
+    note.parent()
+        .expect("obsidian content files should always have a parent")
+        .join(pattern.replace("{note}", &stem))
+        .join(attachment.file_name().unwrap_or_default())
+}
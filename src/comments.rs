@@ -0,0 +1,157 @@
+use crate::CommentStrategy;
+use std::ops::Range;
+
+// Apply `strategy` to the Obsidian comments (`%% text %%`) within `content`, which may span
+// multiple lines. Comments within fenced code blocks and inline code are left alone, as are
+// unterminated comments.
+pub fn process_comments(content: String, strategy: CommentStrategy) -> String {
+    if strategy == CommentStrategy::Keep || !content.contains("%%") {
+        return content;
+    }
+    let mut output = String::with_capacity(content.len());
+    let mut code = code_ranges(&content).into_iter().peekable();
+    let mut pos = 0;
+    while pos < content.len() {
+        // Code which started within a comment ends up being removed along with it.
+        while code.peek().is_some_and(|range| range.start < pos) {
+            code.next();
+        }
+        if let Some(range) = code.next_if(|range| range.start == pos) {
+            output.push_str(&content[range.clone()]);
+            pos = range.end;
+            continue;
+        }
+        let rest = &content[pos..];
+        if let Some(comment) = rest.strip_prefix("%%") {
+            if let Some(len) = comment.find("%%") {
+                if strategy == CommentStrategy::ToHtmlComment {
+                    output.push_str("<!--");
+                    output.push_str(&escape_html_comment(&comment[..len]));
+                    output.push_str("-->");
+                }
+                pos += len + 4;
+                continue;
+            }
+        }
+        let c = rest.chars().next().unwrap();
+        output.push(c);
+        pos += c.len_utf8();
+    }
+    output
+}
+
+// Return the byte ranges of the fenced code blocks (including their fences) and inline code spans
+// (including their backticks) within `content`. An unterminated code block runs until the end of
+// `content`, while backticks without a closing run on the same line don't start a code span.
+pub(crate) fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut fence: Option<(&str, usize)> = None;
+    let mut pos = 0;
+    while pos < content.len() {
+        let rest = &content[pos..];
+        if pos == 0 || content[..pos].ends_with('\n') {
+            let line_end = rest.find('\n').map_or(rest.len(), |idx| idx + 1);
+            let trimmed = rest[..line_end].trim_start_matches(' ');
+            match fence {
+                Some((marker, start)) => {
+                    if trimmed.starts_with(marker) {
+                        ranges.push(start..pos + line_end);
+                        fence = None;
+                    }
+                    pos += line_end;
+                    continue;
+                }
+                None => {
+                    if let Some(marker) = fence_marker(trimmed) {
+                        if line_end - trimmed.len() < 4 {
+                            fence = Some((marker, pos));
+                            pos += line_end;
+                            continue;
+                        }
+                    }
+                }
+            }
+        }
+
+        if rest.starts_with('`') {
+            let run = &rest[..rest.len() - rest.trim_start_matches('`').len()];
+            let line_end = rest.find('\n').unwrap_or(rest.len());
+            match rest[run.len()..line_end].find(run) {
+                Some(idx) => {
+                    let end = pos + run.len() + idx + run.len();
+                    ranges.push(pos..end);
+                    pos = end;
+                }
+                None => pos += run.len(),
+            }
+            continue;
+        }
+        pos += rest.chars().next().unwrap().len_utf8();
+    }
+    if let Some((_, start)) = fence {
+        ranges.push(start..content.len());
+    }
+    ranges
+}
+
+// Make `text` safe to place inside an HTML comment. A `--` within the text would end the comment
+// early (or make it invalid), so consecutive dashes are separated by spaces. Text starting with `>`
+// or `-`, or ending with `-`, would also run into the comment delimiters and is padded.
+fn escape_html_comment(text: &str) -> String {
+    let mut escaped = text.to_string();
+    while escaped.contains("--") {
+        escaped = escaped.replace("--", "- -");
+    }
+    if escaped.starts_with('>') || escaped.starts_with('-') {
+        escaped.insert(0, ' ');
+    }
+    if escaped.ends_with('-') {
+        escaped.push(' ');
+    }
+    escaped
+}
+
+// Return the marker (such as ```` ``` ```` or `~~~`) when `line` opens a fenced code block.
+fn fence_marker(line: &str) -> Option<&str> {
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.len() - line.trim_start_matches(c).len();
+    match len >= 3 {
+        true => Some(&line[..len]),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn comments_outside_code() {
+        let content =
+            "a %%one%% b\n%%\ntwo\n%%\n`%%code%%` and\n```\n%%fenced%%\n```\n%%unterminated";
+        assert_eq!(
+            process_comments(content.to_string(), CommentStrategy::Strip),
+            "a  b\n\n`%%code%%` and\n```\n%%fenced%%\n```\n%%unterminated"
+        );
+        assert_eq!(
+            process_comments(content.to_string(), CommentStrategy::ToHtmlComment),
+            "a <!--one--> b\n<!--\ntwo\n-->\n`%%code%%` and\n```\n%%fenced%%\n```\n%%unterminated"
+        );
+        assert_eq!(
+            process_comments(content.to_string(), CommentStrategy::Keep),
+            content
+        );
+    }
+
+    #[test]
+    fn html_comments_escape_dashes() {
+        assert_eq!(
+            process_comments(
+                "%%a --> b%% %%---%% %%>x-%%".to_string(),
+                CommentStrategy::ToHtmlComment
+            ),
+            "<!--a - -> b--> <!-- - - - --> <!-- >x- -->"
+        );
+    }
+}
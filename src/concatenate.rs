@@ -0,0 +1,257 @@
+use crate::summary::Warning;
+use crate::{
+    first_error, is_absolute_url, is_markdown_file, normalize_path, path_to_link,
+    render_mdevents_to_mdtext, Context, Exporter, FileExportError, MarkdownEvents, NoteOrder,
+    OutputFormat, Result, WriteError,
+};
+use pathdiff::diff_paths;
+use percent_encoding::percent_decode_str;
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Tag};
+use rayon::prelude::*;
+use slug::slugify;
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+// A note which has been fully processed and is ready to be concatenated.
+pub(crate) struct ConcatenatedNote<'b> {
+    pub(crate) source: PathBuf,
+    pub(crate) context: Context,
+    pub(crate) events: MarkdownEvents<'b>,
+}
+
+impl<'a> Exporter<'a> {
+    // Export all notes under start_at into the single file `path`, copying attachments as usual.
+    pub(crate) fn export_concatenated(&self, path: &Path) -> Result<()> {
+        let files = self.exported_files();
+        let destination = self.destination.join(path);
+
+        first_error(
+            files
+                .par_iter()
+                .filter(|file| !is_markdown_file(file) || self.is_verbatim_file(file))
+                .map(|file| {
+                    let relative_path = file
+                        .strip_prefix(self.output_root())
+                        .expect("file should always be nested under root");
+                    let destination = self.output_path(file, &self.destination.join(relative_path));
+                    self.export_note(file, &destination)
+                }),
+        )?;
+        self.copy_pending_attachments(None)?;
+
+        let notes = files
+            .par_iter()
+            .filter(|file| is_markdown_file(file) && !self.is_verbatim_file(file))
+            .map(|file| {
+                let note = self
+                    .process_obsidian_note(file, &destination)
+                    .context(FileExportError { path: file })?;
+                Ok(note.map(|(context, events)| ConcatenatedNote {
+                    source: file.to_path_buf(),
+                    context,
+                    events,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        let note_count = notes.len();
+        self.write_concatenated(path, &self.note_order, notes)?;
+        self.summary
+            .lock()
+            .expect("summary lock poisoned")
+            .notes_exported += note_count;
+        Ok(())
+    }
+
+    // Write the notes which were exported individually during this run to the single file `path`
+    // as well (see Exporter::also_concatenate).
+    pub(crate) fn export_also_concatenated(&self, path: &Path, order: &NoteOrder) -> Result<()> {
+        let notes = std::mem::take(
+            &mut *self
+                .rendered_notes
+                .lock()
+                .expect("rendered notes lock poisoned"),
+        );
+        self.write_concatenated(path, order, notes)
+    }
+
+    // Concatenate the given notes, in the given order, into the single file `path`.
+    fn write_concatenated(
+        &self,
+        path: &Path,
+        order: &NoteOrder,
+        mut notes: Vec<ConcatenatedNote>,
+    ) -> Result<()> {
+        let destination = self.destination.join(path);
+        notes.sort_by(|a, b| order.compare(&a.context, &b.context));
+
+        // Links are resolved relative to the directory the concatenated file ends up in.
+        let base_dir = normalize_path(
+            self.output_root()
+                .join(path)
+                .parent()
+                .expect("concatenated file should have a parent directory"),
+        );
+        let anchors: HashMap<PathBuf, String> = notes
+            .iter()
+            .map(|note| (normalize_path(&note.source), self.note_anchor(&note.source)))
+            .collect();
+
+        let mut events = vec![];
+        for (idx, note) in notes.into_iter().enumerate() {
+            if idx > 0 {
+                events.push(Event::Rule);
+            }
+            let anchor = &anchors[&normalize_path(&note.source)];
+            events.push(Event::Html(CowStr::from(format!(
+                "<a id=\"{}\"></a>\n",
+                anchor
+            ))));
+            let heading = Tag::Heading(HeadingLevel::H1, None, vec![]);
+            events.push(Event::Start(heading.clone()));
+            events.push(Event::Text(CowStr::from(
+                note.source
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            )));
+            events.push(Event::End(heading));
+
+            let note_dir = note
+                .source
+                .parent()
+                .expect("obsidian content files should always have a parent")
+                .to_path_buf();
+            let rewrite = |target: &str| {
+                self.rewrite_concatenated_link(target, &note_dir, &base_dir, &anchors)
+            };
+            let source = &note.source;
+            let note_events = match self.prefix_concatenated_anchors {
+                true => prefix_heading_anchors(note.events, anchor),
+                false => note.events,
+            };
+            events.extend(note_events.into_iter().map(|event| match event {
+                Event::Start(Tag::Link(kind, target, title)) => {
+                    let (link, excluded) = rewrite(&target);
+                    if excluded {
+                        self.warn(
+                            Warning::new("Linked note is not part of the concatenated file")
+                                .reference(&target)
+                                .source(source),
+                        );
+                    }
+                    Event::Start(Tag::Link(kind, link, title))
+                }
+                Event::End(Tag::Link(kind, target, title)) => {
+                    Event::End(Tag::Link(kind, rewrite(&target).0, title))
+                }
+                Event::Start(Tag::Image(kind, target, title)) => {
+                    Event::Start(Tag::Image(kind, rewrite(&target).0, title))
+                }
+                Event::End(Tag::Image(kind, target, title)) => {
+                    Event::End(Tag::Image(kind, rewrite(&target).0, title))
+                }
+                event => event,
+            }));
+        }
+
+        let output = match self.output_format {
+            OutputFormat::Html => self.render_html(events, &Default::default(), &destination),
+            OutputFormat::Markdown => render_mdevents_to_mdtext(events),
+        };
+        self.create_output(&destination)?
+            .write_all(output.as_bytes())
+            .context(WriteError { path: &destination })
+    }
+
+    // Return the anchor which marks the start of the note at `path` in the concatenated file.
+    fn note_anchor(&self, path: &Path) -> String {
+        let relative_path = path.strip_prefix(&self.root).unwrap_or(path);
+        slugify(relative_path.with_extension("").to_string_lossy())
+    }
+
+    // Rewrite a link target of a note located in `note_dir`, so that links to other concatenated
+    // notes become anchors and other relative links are relative to `base_dir`. Also returns
+    // whether the link points to a note which isn't part of the concatenated file, as such links
+    // end up pointing to a file which isn't exported.
+    fn rewrite_concatenated_link<'b>(
+        &self,
+        target: &str,
+        note_dir: &Path,
+        base_dir: &Path,
+        anchors: &HashMap<PathBuf, String>,
+    ) -> (CowStr<'b>, bool) {
+        if is_absolute_url(target) {
+            return (CowStr::from(target.to_string()), false);
+        }
+        let (path, fragment) = match target.find('#') {
+            Some(index) => target.split_at(index),
+            None => (target, ""),
+        };
+        let path = percent_decode_str(path).decode_utf8_lossy();
+        let target_file = normalize_path(&note_dir.join(path.as_ref()));
+        // Links to notes have already been given the extension of the output format.
+        let target_note = match self.output_format {
+            OutputFormat::Html => target_file.with_extension("md"),
+            OutputFormat::Markdown => target_file.clone(),
+        };
+        if let Some(anchor) = anchors.get(&target_note) {
+            let link = match fragment {
+                "" => CowStr::from(format!("#{}", anchor)),
+                fragment if self.prefix_concatenated_anchors => CowStr::from(format!(
+                    "#{}-{}",
+                    anchor,
+                    slugify(percent_decode_str(&fragment[1..]).decode_utf8_lossy())
+                )),
+                fragment => CowStr::from(fragment.to_string()),
+            };
+            return (link, false);
+        }
+        let link = match diff_paths(&target_file, base_dir) {
+            Some(rel_link) => CowStr::from(format!("{}{}", path_to_link(&rel_link), fragment)),
+            None => CowStr::from(target.to_string()),
+        };
+        let is_note = match self.output_format {
+            OutputFormat::Html => target_file.extension().is_some_and(|ext| ext == "html"),
+            OutputFormat::Markdown => is_markdown_file(&target_file),
+        };
+        (link, is_note)
+    }
+}
+
+// Insert an anchor at the start of each heading in `events`, consisting of the slug of the heading
+// prefixed with `prefix`.
+fn prefix_heading_anchors<'b>(events: MarkdownEvents<'b>, prefix: &str) -> MarkdownEvents<'b> {
+    let mut prefixed = Vec::with_capacity(events.len());
+    let mut heading: Option<(usize, String)> = None;
+    for event in events {
+        match &event {
+            Event::Start(Tag::Heading(..)) => heading = Some((prefixed.len() + 1, String::new())),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, heading_text)) = heading.as_mut() {
+                    heading_text.push_str(text);
+                }
+            }
+            Event::End(Tag::Heading(..)) => {
+                if let Some((position, heading_text)) = heading.take() {
+                    prefixed.insert(
+                        position,
+                        Event::Html(CowStr::from(format!(
+                            "<a id=\"{}-{}\"></a>",
+                            prefix,
+                            slugify(heading_text.trim())
+                        ))),
+                    );
+                }
+            }
+            _ => {}
+        }
+        prefixed.push(event);
+    }
+    prefixed
+}
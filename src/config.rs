@@ -0,0 +1,194 @@
+use crate::postprocessors::softbreaks_to_hardbreaks;
+use crate::{
+    EncodingErrorStrategy, Exporter, FrontmatterStrategy, LogLevel, OutputFormat,
+    SharedAttachments, WalkOptions, WarningFormat,
+};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+/// A batch of settings for an [Exporter], mirroring the options of the command line program.
+///
+/// Settings which are left at `None` keep their default. This is what the `--config` file of the
+/// command line program is deserialized into, with keys named after the long command line options
+/// (using underscores instead of dashes). See [Exporter::from_config].
+pub struct Config {
+    /// Only export notes under this sub-path. See [Exporter::start_at].
+    pub start_at: Option<PathBuf>,
+    /// Keep the `start_at` sub-path in the destination. See [Exporter::start_at_as_root].
+    pub keep_start_at_path: Option<bool>,
+    /// Only export notes with one of these tags. See [Exporter::include_tags].
+    pub tag: Option<Vec<String>>,
+    /// The frontmatter strategy (`frontmatter` in a config file). See
+    /// [Exporter::frontmatter_strategy].
+    #[serde(rename = "frontmatter")]
+    pub frontmatter_strategy: Option<FrontmatterStrategy>,
+    /// See [Exporter::keep_frontmatter_comments].
+    pub keep_frontmatter_comments: Option<bool>,
+    /// See [Exporter::frontmatter_as_table].
+    pub frontmatter_as_table: Option<bool>,
+    /// See [Exporter::output_format].
+    pub output_format: Option<OutputFormat>,
+    /// See [Exporter::html_template_file].
+    pub html_template: Option<PathBuf>,
+    /// The filename of ignore files. See [WalkOptions::ignore_filename].
+    pub ignore_file: Option<String>,
+    /// How to handle notes which aren't valid UTF-8 (`encoding_errors` in a config file). See
+    /// [Exporter::on_encoding_error].
+    #[serde(rename = "encoding_errors")]
+    pub encoding_error_strategy: Option<EncodingErrorStrategy>,
+    /// Export hidden files. See [WalkOptions::ignore_hidden].
+    pub hidden: Option<bool>,
+    /// See [WalkOptions::include_obsidian_config].
+    pub include_obsidian_config: Option<bool>,
+    /// Don't honor git's ignore rules. See [WalkOptions::honor_gitignore].
+    pub no_git: Option<bool>,
+    /// See [WalkOptions::max_depth].
+    pub max_depth: Option<usize>,
+    /// See [Exporter::publish_marker].
+    pub publish_marker: Option<String>,
+    /// The maximum size of attachments in bytes. See [Exporter::max_attachment_size].
+    pub max_attachment_size: Option<u64>,
+    /// Only export notes. See [Exporter::skip_attachments].
+    pub notes_only: Option<bool>,
+    /// See [Exporter::colocate_attachments].
+    pub colocate_attachments: Option<String>,
+    /// What to do with co-located attachments used by several notes. See
+    /// [Exporter::colocate_attachments].
+    pub shared_attachments: Option<SharedAttachments>,
+    /// Don't process embeds recursively. See [Exporter::process_embeds_recursively].
+    pub no_recursive_embeds: Option<bool>,
+    /// See [Exporter::skip_empty_notes].
+    pub skip_empty_notes: Option<bool>,
+    /// Convert soft line breaks to hard line breaks, using the
+    /// [softbreaks_to_hardbreaks][crate::postprocessors::softbreaks_to_hardbreaks] postprocessor.
+    pub hard_linebreaks: Option<bool>,
+    /// See [Exporter::render_bases].
+    pub render_bases: Option<bool>,
+    /// See [Exporter::threads].
+    pub threads: Option<usize>,
+    /// See [Exporter::profile].
+    pub profile: Option<bool>,
+    /// Print warnings as GitHub Actions annotations. See [WarningFormat::GithubActions].
+    pub github_annotations: Option<bool>,
+    /// See [Exporter::log_file].
+    pub log_file: Option<PathBuf>,
+    /// The level of the log file. See [Exporter::log_file].
+    pub log_level: Option<LogLevel>,
+}
+
+impl Config {
+    /// Combine this config with `overrides`, where every setting of `overrides` which isn't
+    /// `None` replaces the setting of this config.
+    pub fn merge(self, overrides: Config) -> Config {
+        Config {
+            start_at: overrides.start_at.or(self.start_at),
+            keep_start_at_path: overrides.keep_start_at_path.or(self.keep_start_at_path),
+            tag: overrides.tag.or(self.tag),
+            frontmatter_strategy: overrides.frontmatter_strategy.or(self.frontmatter_strategy),
+            keep_frontmatter_comments: overrides
+                .keep_frontmatter_comments
+                .or(self.keep_frontmatter_comments),
+            frontmatter_as_table: overrides.frontmatter_as_table.or(self.frontmatter_as_table),
+            output_format: overrides.output_format.or(self.output_format),
+            html_template: overrides.html_template.or(self.html_template),
+            ignore_file: overrides.ignore_file.or(self.ignore_file),
+            encoding_error_strategy: overrides
+                .encoding_error_strategy
+                .or(self.encoding_error_strategy),
+            hidden: overrides.hidden.or(self.hidden),
+            include_obsidian_config: overrides
+                .include_obsidian_config
+                .or(self.include_obsidian_config),
+            no_git: overrides.no_git.or(self.no_git),
+            max_depth: overrides.max_depth.or(self.max_depth),
+            publish_marker: overrides.publish_marker.or(self.publish_marker),
+            max_attachment_size: overrides.max_attachment_size.or(self.max_attachment_size),
+            notes_only: overrides.notes_only.or(self.notes_only),
+            colocate_attachments: overrides.colocate_attachments.or(self.colocate_attachments),
+            shared_attachments: overrides.shared_attachments.or(self.shared_attachments),
+            no_recursive_embeds: overrides.no_recursive_embeds.or(self.no_recursive_embeds),
+            skip_empty_notes: overrides.skip_empty_notes.or(self.skip_empty_notes),
+            hard_linebreaks: overrides.hard_linebreaks.or(self.hard_linebreaks),
+            render_bases: overrides.render_bases.or(self.render_bases),
+            threads: overrides.threads.or(self.threads),
+            profile: overrides.profile.or(self.profile),
+            github_annotations: overrides.github_annotations.or(self.github_annotations),
+            log_file: overrides.log_file.or(self.log_file),
+            log_level: overrides.log_level.or(self.log_level),
+        }
+    }
+}
+
+impl<'a> Exporter<'a> {
+    /// Create a new exporter which reads notes from `root` and exports these to `destination`,
+    /// applying the settings of `config`.
+    ///
+    /// Settings which `config` leaves at `None` keep the defaults of [Exporter::new]. Other
+    /// settings may still be changed on the returned exporter.
+    pub fn from_config(root: PathBuf, destination: PathBuf, config: &'a Config) -> Exporter<'a> {
+        let mut exporter = Exporter::new(root, destination);
+        let flag = |value: Option<bool>| value.unwrap_or(false);
+
+        if let Some(path) = &config.start_at {
+            exporter.start_at(path.clone());
+        }
+        exporter.start_at_as_root(!flag(config.keep_start_at_path));
+        if let Some(tags) = &config.tag {
+            exporter.include_tags(tags.clone());
+        }
+        if let Some(strategy) = config.frontmatter_strategy {
+            exporter.frontmatter_strategy(strategy);
+        }
+        exporter.keep_frontmatter_comments(flag(config.keep_frontmatter_comments));
+        exporter.frontmatter_as_table(flag(config.frontmatter_as_table));
+        if let Some(format) = config.output_format {
+            exporter.output_format(format);
+        }
+        if let Some(path) = &config.html_template {
+            exporter.html_template_file(path.clone());
+        }
+        if let Some(strategy) = config.encoding_error_strategy {
+            exporter.on_encoding_error(strategy);
+        }
+        if let Some(marker) = &config.publish_marker {
+            exporter.publish_marker(marker.clone());
+        }
+        exporter.max_attachment_size(config.max_attachment_size);
+        exporter.skip_attachments(flag(config.notes_only));
+        if let Some(pattern) = &config.colocate_attachments {
+            exporter.colocate_attachments(
+                pattern.clone(),
+                config
+                    .shared_attachments
+                    .unwrap_or(SharedAttachments::Centralize),
+            );
+        }
+        exporter.process_embeds_recursively(!flag(config.no_recursive_embeds));
+        exporter.skip_empty_notes(flag(config.skip_empty_notes));
+        if flag(config.hard_linebreaks) {
+            exporter.add_postprocessor(&softbreaks_to_hardbreaks);
+        }
+        exporter.render_bases(flag(config.render_bases));
+        exporter.threads(config.threads.unwrap_or(0));
+        exporter.profile(flag(config.profile));
+        if flag(config.github_annotations) {
+            exporter.warning_format(WarningFormat::GithubActions);
+        }
+        if let Some(path) = &config.log_file {
+            exporter.log_file(path.clone(), config.log_level.unwrap_or(LogLevel::Warning));
+        }
+
+        let mut walk_options = WalkOptions::default();
+        if let Some(filename) = &config.ignore_file {
+            walk_options.ignore_filename = filename;
+        }
+        walk_options.ignore_hidden = !flag(config.hidden);
+        walk_options.honor_gitignore = !flag(config.no_git);
+        walk_options.include_obsidian_config = flag(config.include_obsidian_config);
+        walk_options.max_depth = config.max_depth;
+        exporter.walk_options(walk_options);
+        exporter
+    }
+}
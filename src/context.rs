@@ -1,5 +1,10 @@
+use crate::tags::{frontmatter_tags, tag_matches};
 use crate::Frontmatter;
+use pulldown_cmark::Event;
+use std::collections::HashMap;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 /// Context holds metadata about a note which is being parsed.
@@ -42,6 +47,11 @@ pub struct Context {
     /// );
     /// ```
     pub frontmatter: Frontmatter,
+
+    pub(crate) embed_ranges: Vec<Range<usize>>,
+    pub(crate) inline_tags: Vec<String>,
+    pub(crate) embeds: Vec<PathBuf>,
+    pub(crate) vault_notes: Option<Arc<HashMap<PathBuf, Frontmatter>>>,
 }
 
 impl Context {
@@ -51,6 +61,10 @@ impl Context {
             file_tree: vec![src],
             destination: dest,
             frontmatter: Frontmatter::new(),
+            embed_ranges: Vec::new(),
+            inline_tags: Vec::new(),
+            embeds: Vec::new(),
+            vault_notes: None,
         }
     }
 
@@ -58,9 +72,21 @@ impl Context {
     pub fn from_parent(context: &Context, child: &Path) -> Context {
         let mut context = context.clone();
         context.file_tree.push(child.to_path_buf());
+        context.embed_ranges.clear();
+        context.inline_tags.clear();
+        context.embeds.clear();
         context
     }
 
+    /// Return the frontmatter of every note in the vault, keyed by the path of the note.
+    ///
+    /// This index is built before any notes are exported, so it reflects frontmatter as it is
+    /// written in the vault, before any changes made by postprocessors. It is only available to
+    /// contexts created by an [Exporter][crate::Exporter].
+    pub fn vault_notes(&self) -> Option<&HashMap<PathBuf, Frontmatter>> {
+        self.vault_notes.as_deref()
+    }
+
     /// Return the path of the file currently being parsed.
     pub fn current_file(&self) -> &PathBuf {
         self.file_tree
@@ -90,4 +116,74 @@ impl Context {
     pub fn file_tree(&self) -> Vec<PathBuf> {
         self.file_tree.clone()
     }
+
+    /// Return the filename of [Context::destination] without its extension.
+    pub fn destination_stem(&self) -> String {
+        self.destination
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Change the extension of [Context::destination], keeping the rest of the filename intact.
+    ///
+    /// An `extension` without a leading `.` is expected, such as `mdx`. An empty `extension`
+    /// removes the extension altogether. As with changing [Context::destination] directly, links
+    /// to this note are not updated.
+    pub fn set_destination_extension(&mut self, extension: &str) {
+        self.destination.set_extension(extension);
+    }
+
+    /// Return the tags of this note.
+    ///
+    /// This includes both the tags listed in frontmatter (under `tags` or `tag`) as well as inline
+    /// `#tags` within the body of the note itself (tags in embedded notes are not included). Tags
+    /// are normalized: they're returned without a leading `#` and in lowercase.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = vec![];
+        for tag in frontmatter_tags(&self.frontmatter)
+            .into_iter()
+            .chain(self.inline_tags.iter().cloned())
+        {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Return true if this note has the given tag, or a tag nested under it (for example
+    /// `project/alpha` when checking for `project`). See also [Context::tags].
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags().iter().any(|t| tag_matches(t, tag))
+    }
+
+    /// Return the ranges of [MarkdownEvents][crate::MarkdownEvents] which were inserted by embeds
+    /// of other notes.
+    ///
+    /// These ranges refer to the events as generated by parsing the note. Postprocessors which
+    /// insert or remove events will cause these ranges to no longer line up with the events passed
+    /// to postprocessors running after them.
+    pub fn embed_ranges(&self) -> &[Range<usize>] {
+        &self.embed_ranges
+    }
+
+    /// Return the ranges of `events` which make up the note's own content, that is, excluding any
+    /// content that was inserted by embeds (see [Context::embed_ranges]).
+    pub fn content_range_excluding_embeds(&self, events: &[Event]) -> Vec<Range<usize>> {
+        let mut ranges = Vec::with_capacity(self.embed_ranges.len() + 1);
+        let mut start = 0;
+        for embed in &self.embed_ranges {
+            let end = embed.start.min(events.len());
+            if start < end {
+                ranges.push(start..end);
+            }
+            start = embed.end.max(start);
+        }
+        if start < events.len() {
+            ranges.push(start..events.len());
+        }
+        ranges
+    }
 }
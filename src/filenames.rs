@@ -0,0 +1,84 @@
+use crate::summary::Warning;
+use crate::Exporter;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+impl<'a> Exporter<'a> {
+    // Replace or strip the disallowed characters in each component of `path` below the
+    // destination. Relative paths, such as those of links, are sanitized in full.
+    pub(crate) fn sanitize_output_path(&self, path: &Path) -> PathBuf {
+        let (disallowed, replacement) = match &self.filename_sanitizer {
+            Some((disallowed, replacement)) => (disallowed, *replacement),
+            None => return path.to_path_buf(),
+        };
+        let (mut sanitized, relative_path) = match path.strip_prefix(&self.destination) {
+            Ok(relative_path) => (self.destination.clone(), relative_path),
+            Err(_) => (PathBuf::new(), path),
+        };
+        for component in relative_path.components() {
+            match component {
+                Component::Normal(name) => sanitized.push(sanitize_filename(
+                    &name.to_string_lossy(),
+                    disallowed,
+                    replacement,
+                )),
+                component => sanitized.push(component),
+            }
+        }
+        sanitized
+    }
+
+    // Warn about files under start_at which end up at the same destination once their names have
+    // been sanitized, as all but one of them would be overwritten.
+    pub(crate) fn warn_filename_collisions(&self) {
+        let mut destinations: HashMap<PathBuf, &PathBuf> = HashMap::new();
+        for file in self
+            .vault_contents
+            .as_ref()
+            .unwrap()
+            .iter()
+            .filter(|file| file.starts_with(&self.start_at))
+        {
+            let relative_path = file
+                .strip_prefix(self.output_root())
+                .expect("file should always be nested under root");
+            let destination = self.output_path(file, &self.destination.join(relative_path));
+            if let Some(other) = destinations.insert(destination.clone(), file) {
+                self.warn(
+                    Warning::new("Sanitized filenames collide")
+                        .detail("Destination", destination.display())
+                        .source(other)
+                        .source(file),
+                );
+            }
+        }
+    }
+}
+
+// Replace each character of `name` found in `disallowed` with `replacement`, or remove it when no
+// replacement is given.
+fn sanitize_filename(name: &str, disallowed: &str, replacement: Option<char>) -> String {
+    name.chars()
+        .filter_map(|c| match disallowed.contains(c) {
+            true => replacement,
+            false => Some(c),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_filename_replaces_or_strips() {
+        assert_eq!(
+            sanitize_filename("Q: What? | Answer.md", ":?|", Some('-')),
+            "Q- What- - Answer.md"
+        );
+        assert_eq!(
+            sanitize_filename("Q: What? | Answer.md", ":?|", None),
+            "Q What  Answer.md"
+        );
+    }
+}
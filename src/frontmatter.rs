@@ -1,4 +1,11 @@
-use serde_yaml::Result;
+use crate::comments::code_ranges;
+use crate::json::value_to_json;
+use crate::{frontmatter_value_to_string, MarkdownEvents};
+use pulldown_cmark::{Alignment, CowStr, Event, Tag};
+use regex::Regex;
+use serde::Deserialize;
+use serde_yaml::{Result, Value};
+use std::collections::HashMap;
 
 /// YAML front matter from an Obsidian note.
 ///
@@ -45,7 +52,220 @@ pub fn frontmatter_to_str(frontmatter: Frontmatter) -> Result<String> {
     Ok(buffer)
 }
 
-#[derive(Debug, Clone, Copy)]
+// Return the `aliases` listed in `frontmatter`, which may be a single string or a list of them.
+pub(crate) fn note_aliases(frontmatter: &Frontmatter) -> Vec<String> {
+    match frontmatter.get(&Value::String("aliases".to_string())) {
+        Some(Value::String(alias)) => vec![alias.to_string()],
+        Some(Value::Sequence(aliases)) => aliases
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|alias| alias.to_string())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// Return the rows of the table listing `frontmatter` (see Exporter::frontmatter_as_table). Nested
+// mappings are flattened into dotted keys, lists of scalars are joined with commas and lists
+// containing mappings or other lists are written as JSON.
+pub(crate) fn frontmatter_table_rows(frontmatter: &Frontmatter) -> Vec<(String, String)> {
+    let mut rows = vec![];
+    for (key, value) in frontmatter {
+        let key = match key {
+            Value::String(key) => key.to_string(),
+            key => value_to_json(key).trim_matches('"').to_string(),
+        };
+        match value {
+            Value::Mapping(mapping) => {
+                for (nested_key, value) in frontmatter_table_rows(mapping) {
+                    rows.push((format!("{}.{}", key, nested_key), value));
+                }
+            }
+            Value::Sequence(values)
+                if values
+                    .iter()
+                    .any(|value| value.is_mapping() || value.is_sequence()) =>
+            {
+                rows.push((key, value_to_json(value)))
+            }
+            value => rows.push((key, frontmatter_value_to_string(value))),
+        }
+    }
+    rows
+}
+
+// Return the events of a two-column table listing the keys and values of `frontmatter`.
+pub(crate) fn frontmatter_table<'b>(frontmatter: &Frontmatter) -> MarkdownEvents<'b> {
+    let alignments = vec![Alignment::None; 2];
+    let mut events = vec![
+        Event::Start(Tag::Table(alignments.clone())),
+        Event::Start(Tag::TableHead),
+    ];
+    for heading in ["Key", "Value"] {
+        events.push(Event::Start(Tag::TableCell));
+        events.push(Event::Text(CowStr::Borrowed(heading)));
+        events.push(Event::End(Tag::TableCell));
+    }
+    events.push(Event::End(Tag::TableHead));
+    for (key, value) in frontmatter_table_rows(frontmatter) {
+        events.push(Event::Start(Tag::TableRow));
+        for cell in [key, value] {
+            // Pipes would end the cell, and line breaks the row.
+            let cell = cell.replace('|', "\\|").replace('\n', " ");
+            events.push(Event::Start(Tag::TableCell));
+            events.push(Event::Text(CowStr::from(cell)));
+            events.push(Event::End(Tag::TableCell));
+        }
+        events.push(Event::End(Tag::TableRow));
+    }
+    events.push(Event::End(Tag::Table(alignments)));
+    events
+}
+
+/// Move text matching any of `patterns` out of `content` and into `frontmatter`.
+///
+/// Each pattern is paired with the frontmatter key its matches are stored under. See
+/// [crate::Exporter::metadata_comment_pattern] for details.
+pub fn extract_metadata_comments(
+    mut content: String,
+    frontmatter: &mut Frontmatter,
+    patterns: &[(Regex, String)],
+) -> String {
+    for (regex, key) in patterns {
+        let mut values: Vec<Value> = regex
+            .captures_iter(&content)
+            .filter_map(|captures| {
+                captures
+                    .name("value")
+                    .or_else(|| captures.get(1))
+                    .or_else(|| captures.get(0))
+            })
+            .map(|value| Value::String(value.as_str().to_string()))
+            .collect();
+        let value = match values.len() {
+            0 => continue,
+            1 => values.remove(0),
+            _ => Value::Sequence(values),
+        };
+        frontmatter.insert(Value::String(key.to_string()), value);
+        content = regex.replace_all(&content, "").into_owned();
+    }
+    content
+}
+
+/// Return the regular expression matching directive comments with the given `prefix`, for use
+/// with [extract_directive_comments].
+pub fn directive_regex(prefix: &str) -> Regex {
+    Regex::new(&format!(
+        r"[ \t]*%%[ \t]*{}(?:\.(?P<key>[^\s:%]+))?[ \t]*:[ \t]*(?P<value>[^\n%]*?)[ \t]*%%",
+        regex::escape(prefix)
+    ))
+    .expect("directive regex should be valid")
+}
+
+/// Move the directives in comments such as `%% export: false %%` or `%% export.title: Note %%`
+/// from `content` into `frontmatter`, where `export` is the given `prefix` and `regex` was built
+/// for it by [directive_regex].
+///
+/// The value of the directive named after the prefix itself is stored under the `prefix` key,
+/// while directives of the form `prefix.key` are stored under `key`. Values are parsed as YAML,
+/// so `false` becomes a boolean. Directives within fenced code blocks and inline code are left
+/// as-is. See [crate::Exporter::directive_comment] for details.
+pub fn extract_directive_comments(
+    content: String,
+    frontmatter: &mut Frontmatter,
+    regex: &Regex,
+    prefix: &str,
+) -> String {
+    let code = code_ranges(&content);
+    let mut output = String::with_capacity(content.len());
+    let mut last = 0;
+    for captures in regex.captures_iter(&content) {
+        let directive = captures.get(0).unwrap();
+        if code
+            .iter()
+            .any(|range| range.contains(&(directive.end() - 1)))
+        {
+            continue;
+        }
+        let key = captures.name("key").map_or(prefix, |key| key.as_str());
+        let value = serde_yaml::from_str(&captures["value"])
+            .unwrap_or_else(|_| Value::String(captures["value"].to_string()));
+        frontmatter.insert(Value::String(key.to_string()), value);
+        output.push_str(&content[last..directive.start()]);
+        last = directive.end();
+    }
+    output.push_str(&content[last..]);
+    output
+}
+
+/// Split a back matter block off the end of `content`, returning the remaining content and the
+/// parsed back matter.
+///
+/// A back matter block is a YAML mapping between two `---` lines at the very end of a note. The
+/// opening `---` must be preceded by a blank line (or be the first line of the note), as it would
+/// otherwise turn the line above it into a heading. A block which doesn't parse as a non-empty
+/// YAML mapping is left alone, so that regular thematic breaks are preserved.
+pub fn extract_backmatter(content: &str) -> Option<(String, Frontmatter)> {
+    let lines: Vec<&str> = content.trim_end().lines().collect();
+    let (closing, body) = lines.split_last()?;
+    if closing.trim_end() != "---" {
+        return None;
+    }
+    let opening = body.iter().rposition(|line| line.trim_end() == "---")?;
+    if opening > 0 && !body[opening - 1].trim().is_empty() {
+        return None;
+    }
+    let block = body[opening + 1..].join("\n");
+    if block.trim().is_empty() {
+        return None;
+    }
+    let backmatter: Frontmatter = serde_yaml::from_str(&block).ok()?;
+    if backmatter.is_empty() {
+        return None;
+    }
+    let mut remainder = body[..opening].join("\n").trim_end().to_string();
+    remainder.push('\n');
+    Some((remainder, backmatter))
+}
+
+/// Make sure a second YAML document directly following the frontmatter of a note (a document
+/// stream such as `---`, `a: b`, `---`, `x: y`, `---`) is kept as regular content.
+///
+/// Only the first document is parsed as frontmatter. The `---` lines around any document after
+/// it are thematic breaks, but the closing `---` would turn the line above it into a heading, so
+/// a blank line is inserted before it. Content which doesn't start with `---` is returned as-is.
+pub fn separate_yaml_documents(content: String) -> String {
+    let mut lines = content.lines();
+    let starts_with_document = lines
+        .by_ref()
+        .find(|line| !line.trim().is_empty())
+        .is_some_and(|line| line.trim_end() == "---");
+    if !starts_with_document {
+        return content;
+    }
+    let mut previous = "";
+    let mut output = vec![];
+    let mut separated = false;
+    for line in content.lines() {
+        if !separated && line.trim_end() == "---" && !previous.trim().is_empty() {
+            if previous.trim_end() != "---" {
+                output.push("");
+            }
+            separated = true;
+        }
+        output.push(line);
+        previous = line;
+    }
+    let mut output = output.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
 /// Available strategies for the inclusion of frontmatter in notes.
 pub enum FrontmatterStrategy {
     /// Copy frontmatter when a note has frontmatter defined.
@@ -57,12 +277,131 @@ pub enum FrontmatterStrategy {
     Never,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Which frontmatter wins when a key is set both in a note and in its sidecar file (see
+/// [crate::Exporter::sidecar_frontmatter]).
+pub enum SidecarPrecedence {
+    /// Keep the value from the frontmatter of the note itself (the default).
+    Inline,
+    /// Use the value from the sidecar file.
+    Sidecar,
+}
+
+/// Merge the keys of `sidecar` into `frontmatter`, resolving conflicts according to `precedence`.
+pub fn merge_sidecar_frontmatter(
+    frontmatter: &mut Frontmatter,
+    sidecar: Frontmatter,
+    precedence: SidecarPrecedence,
+) {
+    for (key, value) in sidecar {
+        if precedence == SidecarPrecedence::Sidecar || !frontmatter.contains_key(&key) {
+            frontmatter.insert(key, value);
+        }
+    }
+}
+
+/// Rename the keys of `frontmatter` according to `renames`, keeping their values and order.
+///
+/// When several keys end up with the same name, the first one is kept. The names of such keys are
+/// returned along with the renamed frontmatter.
+pub fn rename_frontmatter_keys(
+    frontmatter: Frontmatter,
+    renames: &HashMap<String, String>,
+) -> (Frontmatter, Vec<String>) {
+    let mut renamed = Frontmatter::new();
+    let mut collisions = vec![];
+    for (key, value) in frontmatter {
+        let key = match key.as_str().and_then(|name| renames.get(name)) {
+            Some(name) => Value::String(name.to_string()),
+            None => key,
+        };
+        if renamed.contains_key(&key) {
+            collisions.push(key.as_str().unwrap_or_default().to_string());
+            continue;
+        }
+        renamed.insert(key, value);
+    }
+    (renamed, collisions)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
     use serde_yaml::Value;
 
+    #[test]
+    fn yaml_documents_after_frontmatter() {
+        assert_eq!(
+            separate_yaml_documents("\n---\nstatus: stream\nmore: keys\n---\nBody.\n".to_string()),
+            "\n---\nstatus: stream\nmore: keys\n\n---\nBody.\n"
+        );
+        // Setext headings elsewhere in a note are left alone.
+        let content = "Heading\n---\n\nText.\n".to_string();
+        assert_eq!(separate_yaml_documents(content.clone()), content);
+    }
+
+    #[test]
+    fn directive_comments() {
+        let mut frontmatter = Frontmatter::new();
+        let content = extract_directive_comments(
+            "Text %% export: false %%\n%%export.title:  A title %%\n%% other: 1 %%\n".to_string(),
+            &mut frontmatter,
+            &directive_regex("export"),
+            "export",
+        );
+        assert_eq!(content, "Text\n\n%% other: 1 %%\n");
+        assert_eq!(
+            frontmatter,
+            serde_yaml::from_str::<Frontmatter>("export: false\ntitle: A title").unwrap()
+        );
+    }
+
+    #[test]
+    fn directive_comments_in_code() {
+        let mut frontmatter = Frontmatter::new();
+        let content = "`%% export: false %%`\n```\n%% export: false %%\n```\n%% export.a: 1 %%\n";
+        let content = extract_directive_comments(
+            content.to_string(),
+            &mut frontmatter,
+            &directive_regex("export"),
+            "export",
+        );
+        assert_eq!(
+            content,
+            "`%% export: false %%`\n```\n%% export: false %%\n```\n\n"
+        );
+        assert_eq!(
+            frontmatter,
+            serde_yaml::from_str::<Frontmatter>("a: 1").unwrap()
+        );
+    }
+
+    #[test]
+    fn renamed_keys() {
+        let frontmatter: Frontmatter =
+            serde_yaml::from_str("tags: [a]\ndate: 2024-01-01\npublishDate: 2023-01-01\ntitle: T")
+                .unwrap();
+        let renames: HashMap<String, String> = [("tags", "keywords"), ("date", "publishDate")]
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect();
+        let (renamed, collisions) = rename_frontmatter_keys(frontmatter, &renames);
+        assert_eq!(
+            renamed,
+            serde_yaml::from_str::<Frontmatter>("keywords: [a]\npublishDate: 2024-01-01\ntitle: T")
+                .unwrap()
+        );
+        assert_eq!(
+            renamed
+                .iter()
+                .map(|(key, _)| key.as_str())
+                .collect::<Vec<_>>(),
+            vec![Some("keywords"), Some("publishDate"), Some("title")]
+        );
+        assert_eq!(collisions, vec!["publishDate"]);
+    }
+
     #[test]
     fn empty_string_should_yield_empty_frontmatter() {
         assert_eq!(frontmatter_from_str("").unwrap(), Frontmatter::new())
@@ -89,4 +428,63 @@ mod tests {
             format!("---\nfoo: bar\n---\n")
         )
     }
+
+    #[test]
+    fn frontmatter_table_rows_flatten_nested_values() {
+        let frontmatter = frontmatter_from_str(
+            "title: Note\ntags: [a, b]\nauthor:\n  name: Jane\n  site: example.com\nlinks:\n  - {url: a}",
+        )
+        .unwrap();
+        assert_eq!(
+            frontmatter_table_rows(&frontmatter),
+            vec![
+                ("title".to_string(), "Note".to_string()),
+                ("tags".to_string(), "a, b".to_string()),
+                ("author.name".to_string(), "Jane".to_string()),
+                ("author.site".to_string(), "example.com".to_string()),
+                ("links".to_string(), "[{\"url\": \"a\"}]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn backmatter_is_extracted() {
+        let (content, backmatter) =
+            extract_backmatter("Text\n\n---\nauthor: Jane\nyear: 2021\n---\n").unwrap();
+        assert_eq!(content, "Text\n");
+        assert_eq!(
+            backmatter,
+            frontmatter_from_str("author: Jane\nyear: 2021").unwrap()
+        );
+    }
+
+    #[test]
+    fn thematic_breaks_are_not_backmatter() {
+        // Not a mapping.
+        assert_eq!(extract_backmatter("Text\n\n---\nMore text\n\n---\n"), None);
+        // The opening line is a setext heading underline.
+        assert_eq!(extract_backmatter("Heading\n---\nkey: value\n---\n"), None);
+        // Content follows the closing line.
+        assert_eq!(extract_backmatter("---\nkey: value\n---\n\nText\n"), None);
+    }
+
+    #[test]
+    fn merge_sidecar_precedence() {
+        let inline = frontmatter_from_str("a: inline\nb: inline").unwrap();
+        let sidecar = frontmatter_from_str("b: sidecar\nc: sidecar").unwrap();
+
+        let mut merged = inline.clone();
+        merge_sidecar_frontmatter(&mut merged, sidecar.clone(), SidecarPrecedence::Inline);
+        assert_eq!(
+            merged,
+            frontmatter_from_str("a: inline\nb: inline\nc: sidecar").unwrap()
+        );
+
+        let mut merged = inline;
+        merge_sidecar_frontmatter(&mut merged, sidecar, SidecarPrecedence::Sidecar);
+        assert_eq!(
+            merged,
+            frontmatter_from_str("a: inline\nb: sidecar\nc: sidecar").unwrap()
+        );
+    }
 }
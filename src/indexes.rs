@@ -0,0 +1,182 @@
+use crate::{
+    frontmatter_value_to_string, path_to_link, render_mdevents_to_mdtext, Context, Exporter,
+    Frontmatter, LinkStyle, OutputFormat, Result, WriteError,
+};
+use pulldown_cmark::{CowStr, Event, HeadingLevel, LinkType, Tag};
+use serde_yaml::Value;
+use snafu::ResultExt;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Where the titles of the notes listed in directory indexes come from.
+pub enum IndexTitle {
+    /// Use the filename of the note, without its extension.
+    Filename,
+    /// Use the value of the given frontmatter key, falling back to the filename of the note when
+    /// it isn't set.
+    FrontmatterKey(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Configuration for the index notes written by
+/// [Exporter::generate_directory_indexes][crate::Exporter::generate_directory_indexes].
+pub struct IndexConfig {
+    /// The filename of the index note written to each directory, such as `index.md`.
+    pub filename: String,
+    /// Where the titles of the listed notes come from.
+    pub title_from: IndexTitle,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        IndexConfig {
+            filename: "index.md".to_string(),
+            title_from: IndexTitle::Filename,
+        }
+    }
+}
+
+// The entries of the index of a single directory.
+#[derive(Default)]
+struct DirectoryIndex {
+    subdirectories: BTreeSet<PathBuf>,
+    // The titles and paths of the notes in the directory.
+    notes: Vec<(String, PathBuf)>,
+}
+
+impl<'a> Exporter<'a> {
+    // Remember a note which was exported during this run, so it can be listed in the index of its
+    // directory.
+    pub(crate) fn record_indexed_note(&self, config: &IndexConfig, context: &Context) {
+        let title = match &config.title_from {
+            IndexTitle::FrontmatterKey(key) => context
+                .frontmatter
+                .get(&Value::String(key.to_string()))
+                .filter(|value| !value.is_null())
+                .map(frontmatter_value_to_string),
+            IndexTitle::Filename => None,
+        };
+        let title = title.unwrap_or_else(|| {
+            context
+                .current_file()
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        });
+        self.indexed_notes
+            .lock()
+            .expect("indexed notes lock poisoned")
+            .push((context.destination.clone(), title));
+    }
+
+    // Write an index note to every directory of the destination which received notes during this
+    // run, as well as to the directories above these, unless an exported note already takes its
+    // place.
+    pub(crate) fn write_directory_indexes(&self, config: &IndexConfig) -> Result<()> {
+        let notes = self
+            .indexed_notes
+            .lock()
+            .expect("indexed notes lock poisoned")
+            .clone();
+        let mut directories: BTreeMap<PathBuf, DirectoryIndex> = BTreeMap::new();
+        for (destination, title) in &notes {
+            let relative_path = match destination.strip_prefix(&self.destination) {
+                Ok(relative_path) => relative_path,
+                Err(_) => continue,
+            };
+            let dir = relative_path.parent().unwrap_or_else(|| Path::new(""));
+            directories
+                .entry(dir.to_path_buf())
+                .or_default()
+                .notes
+                .push((title.to_string(), relative_path.to_path_buf()));
+            for (child, parent) in dir.ancestors().zip(dir.ancestors().skip(1)) {
+                directories
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .subdirectories
+                    .insert(child.to_path_buf());
+            }
+        }
+
+        for (dir, mut entries) in directories {
+            let index = dir.join(&config.filename);
+            if entries.notes.iter().any(|(_, path)| path == &index) {
+                continue;
+            }
+            entries.notes.sort_by_key(|(title, _)| title.to_lowercase());
+
+            let name = match dir.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => self
+                    .destination
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "Index".to_string()),
+            };
+            let links = entries
+                .subdirectories
+                .iter()
+                .map(|subdirectory| {
+                    (
+                        subdirectory
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .into_owned(),
+                        subdirectory.join(&config.filename),
+                    )
+                })
+                .chain(entries.notes);
+
+            let heading = Tag::Heading(HeadingLevel::H1, None, vec![]);
+            let mut events = vec![
+                Event::Start(heading.clone()),
+                Event::Text(CowStr::from(name)),
+                Event::End(heading),
+                Event::Start(Tag::List(None)),
+            ];
+            for (title, path) in links {
+                let link = Tag::Link(
+                    LinkType::Inline,
+                    CowStr::from(self.index_link(&dir, &path)),
+                    CowStr::Borrowed(""),
+                );
+                events.push(Event::Start(Tag::Item));
+                events.push(Event::Start(link.clone()));
+                events.push(Event::Text(CowStr::from(title)));
+                events.push(Event::End(link));
+                events.push(Event::End(Tag::Item));
+            }
+            events.push(Event::End(Tag::List(None)));
+
+            let destination = self.destination.join(&index);
+            let output = match self.output_format {
+                OutputFormat::Html => self.render_html(events, &Frontmatter::new(), &index),
+                OutputFormat::Markdown => render_mdevents_to_mdtext(events),
+            };
+            self.create_output(&destination)?
+                .write_all(output.as_bytes())
+                .context(WriteError { path: &destination })?;
+        }
+        Ok(())
+    }
+
+    // Return the link from the index in `dir` to `path`, both relative to the destination.
+    fn index_link(&self, dir: &Path, path: &Path) -> String {
+        match self.link_style {
+            LinkStyle::AbsoluteFromRoot => format!(
+                "{}/{}",
+                self.link_base.trim_end_matches('/'),
+                path_to_link(path)
+            ),
+            LinkStyle::Relative => path_to_link(
+                path.strip_prefix(dir)
+                    .expect("entries are nested under dir"),
+            ),
+        }
+    }
+}
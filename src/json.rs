@@ -0,0 +1,84 @@
+use crate::Frontmatter;
+use serde_yaml::Value;
+
+// Encode `s` as a JSON string literal.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len() + 2);
+    encoded.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => encoded.push_str("\\\""),
+            '\\' => encoded.push_str("\\\\"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\t' => encoded.push_str("\\t"),
+            c if (c as u32) < 0x20 => encoded.push_str(&format!("\\u{:04x}", c as u32)),
+            c => encoded.push(c),
+        }
+    }
+    encoded.push('"');
+    encoded
+}
+
+// Serialize `frontmatter` as a single line JSON object. Keys which aren't strings are converted
+// to strings, and numbers which JSON can't represent (such as `.nan`) become `null`.
+pub(crate) fn frontmatter_to_json(frontmatter: &Frontmatter) -> String {
+    let entries: Vec<String> = frontmatter
+        .iter()
+        .map(|(key, value)| {
+            let key = match key {
+                Value::String(key) => json_string(key),
+                key => json_string(value_to_json(key).trim_matches('"')),
+            };
+            format!("{}: {}", key, value_to_json(value))
+        })
+        .collect();
+    format!("{{{}}}", entries.join(", "))
+}
+
+// Serialize `value` as JSON, see frontmatter_to_json.
+pub(crate) fn value_to_json(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(number) => match number.as_f64() {
+            Some(float) if !float.is_finite() => "null".to_string(),
+            _ => number.to_string(),
+        },
+        Value::String(value) => json_string(value),
+        Value::Sequence(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(value_to_json)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Mapping(mapping) => frontmatter_to_json(mapping),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontmatter::frontmatter_from_str;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(
+            json_string("quote \" slash \\ newline \n bell \u{7}"),
+            "\"quote \\\" slash \\\\ newline \\n bell \\u0007\""
+        );
+    }
+
+    #[test]
+    fn frontmatter_to_json_converts_keys_and_special_numbers() {
+        let frontmatter = frontmatter_from_str("2022: .nan\ntrue: ~\nlist: [1, b]").unwrap();
+        assert_eq!(
+            frontmatter_to_json(&frontmatter),
+            "{\"2022\": null, \"true\": null, \"list\": [1, \"b\"]}"
+        )
+    }
+}
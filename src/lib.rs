@@ -1,35 +1,116 @@
+// Lints added to clippy and rustc after large parts of this crate were written.
+#![allow(
+    clippy::empty_line_after_doc_comments,
+    clippy::needless_borrows_for_generic_args,
+    clippy::needless_lifetimes,
+    clippy::question_mark,
+    mismatched_lifetime_syntaxes
+)]
+
 pub extern crate pulldown_cmark;
 pub extern crate serde_yaml;
 
 #[macro_use]
 extern crate lazy_static;
 
+mod attachments;
+mod backlinks;
+mod bases;
+mod changes;
+mod check;
+mod colocate;
+mod comments;
+mod concatenate;
+mod config;
 mod context;
+mod filenames;
 mod frontmatter;
+mod indexes;
+mod json;
+mod links;
+mod log;
+mod metadata;
+mod order;
+mod output;
+mod passthrough;
 pub mod postprocessors;
+mod profile;
+mod progress;
+mod provenance;
+mod redirects;
 mod references;
+mod responsive;
+mod restore;
+mod schema;
+mod search;
+mod summary;
+mod tags;
+mod unicode;
 mod walker;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod writer;
 
+pub use backlinks::BacklinkStrategy;
+pub use changes::{ChangeKind, NoteChange};
+pub use check::{Issue, IssueKind};
+pub use colocate::SharedAttachments;
+pub use config::Config;
 pub use context::Context;
-pub use frontmatter::{Frontmatter, FrontmatterStrategy};
+pub use frontmatter::{Frontmatter, FrontmatterStrategy, SidecarPrecedence};
+pub use indexes::{IndexConfig, IndexTitle};
+pub use links::{LinkCallback, LinkEvent, LinkKind};
+pub use log::LogLevel;
+pub use order::NoteOrder;
+pub use output::OutputFormat;
+pub use profile::{Profile, Timing};
+pub use progress::{Progress, ProgressCallback, ProgressPhase};
+pub use restore::restore_wikilinks;
+pub use search::SearchIndexFormat;
+pub use summary::{RunSummary, SkipReason, WarningCallback, WarningFormat};
 pub use walker::{vault_contents, WalkOptions};
+pub use writer::WriterFactory;
 
-use frontmatter::{frontmatter_from_str, frontmatter_to_str};
+use backlinks::ReferenceKind;
+use bases::{evaluate_base, note_property};
+use changes::classify_change;
+use comments::process_comments;
+use concatenate::ConcatenatedNote;
+use frontmatter::{
+    directive_regex, extract_backmatter, extract_directive_comments, extract_metadata_comments,
+    frontmatter_from_str, frontmatter_table, frontmatter_to_str, merge_sidecar_frontmatter,
+    note_aliases, rename_frontmatter_keys, separate_yaml_documents,
+};
+use json::frontmatter_to_json;
 use pathdiff::diff_paths;
-use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
-use pulldown_cmark::{CodeBlockKind, CowStr, Event, HeadingLevel, Options, Parser, Tag};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use progress::ProgressTracker;
+use pulldown_cmark::{Alignment, CodeBlockKind, CowStr, Event, HeadingLevel, Options, Parser, Tag};
 use pulldown_cmark_to_cmark::cmark_with_options;
 use rayon::prelude::*;
 use references::*;
+use regex::Regex;
+use search::SearchRecord;
+use serde::Deserialize;
+use serde_yaml::Value;
 use slug::slugify;
 use snafu::{ResultExt, Snafu};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::fmt;
 use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::ErrorKind;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
 use std::str;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use summary::Warning;
+use tags::{frontmatter_tags, inline_tags, tag_matches};
+use unicode::to_nfc;
+use walker::ignore_pattern_errors;
 
 /// A series of markdown [Event]s that are generated while traversing an Obsidian markdown note.
 pub type MarkdownEvents<'a> = Vec<Event<'a>>;
@@ -45,6 +126,7 @@ pub type MarkdownEvents<'a> = Vec<Event<'a>>;
 /// 2. Change a note's contents by altering [MarkdownEvents].
 /// 3. Prevent later postprocessors from running ([PostprocessorResult::StopHere]) or cause a note
 ///    to be skipped entirely ([PostprocessorResult::StopAndSkipNote]).
+/// 4. Abort the export as a whole ([PostprocessorResult::Abort]).
 ///
 /// # Postprocessors and embeds
 ///
@@ -141,6 +223,47 @@ pub type MarkdownEvents<'a> = Vec<Event<'a>>;
 
 pub type Postprocessor =
     dyn Fn(Context, MarkdownEvents) -> (Context, MarkdownEvents, PostprocessorResult) + Send + Sync;
+
+/// A function which is called on the contents of attachments (any file which isn't a note) before
+/// they're written to their destination.
+///
+/// Attachment processors receive the path of the attachment within the vault and its contents,
+/// which they may modify in-place. They're called in the order they've been added through
+/// [Exporter::add_attachment_processor], each one seeing the changes made by the previous one.
+/// Returning [PostprocessorResult::StopHere] prevents later attachment processors from running,
+/// while [PostprocessorResult::StopAndSkipNote] causes the attachment not to be copied at all.
+///
+/// # Example
+///
+/// ```
+/// # use obsidian_export::{Exporter, PostprocessorResult};
+/// # use std::path::{Path, PathBuf};
+/// # use tempfile::TempDir;
+/// #
+/// /// This attachment processor skips PDF files larger than 1 MiB.
+/// fn skip_large_pdfs(path: &Path, contents: &mut Vec<u8>) -> PostprocessorResult {
+///     let is_pdf = path.extension().is_some_and(|ext| ext == "pdf");
+///     match is_pdf && contents.len() > 1024 * 1024 {
+///         true => PostprocessorResult::StopAndSkipNote,
+///         false => PostprocessorResult::Continue,
+///     }
+/// }
+///
+/// # let tmp_dir = TempDir::new().expect("failed to make tempdir");
+/// # let source = PathBuf::from("tests/testdata/input/main-samples");
+/// # let destination = tmp_dir.path().to_path_buf();
+/// # let mut exporter = Exporter::new(source, destination);
+/// exporter.add_attachment_processor(&skip_large_pdfs);
+/// # exporter.run().unwrap();
+/// ```
+pub type AttachmentProcessor = dyn Fn(&Path, &mut Vec<u8>) -> PostprocessorResult + Send + Sync;
+
+/// A post-processing function which owns its state and may mutate it.
+///
+/// Unlike [Postprocessor], which is borrowed and must be [Fn], these may be `move` closures which
+/// capture and update owned state. See [Exporter::add_postprocessor_boxed] for details.
+pub type BoxedPostprocessor =
+    dyn FnMut(Context, MarkdownEvents) -> (Context, MarkdownEvents, PostprocessorResult) + Send;
 type Result<T, E = ExportError> = std::result::Result<T, E>;
 
 const PERCENTENCODE_CHARS: &AsciiSet = &CONTROLS.add(b' ').add(b'(').add(b')').add(b'%').add(b'?');
@@ -175,11 +298,13 @@ pub enum ExportError {
     /// This occurs when an operation is requested on a file or directory which does not exist.
     PathDoesNotExist { path: PathBuf },
 
-    #[snafu(display("Invalid character encoding encountered"))]
-    /// This error may occur when invalid UTF8 is encountered.
-    ///
-    /// Currently, operations which assume UTF8 perform lossy encoding however.
-    CharacterEncodingError { source: str::Utf8Error },
+    #[snafu(display("Invalid character encoding encountered in '{}'", path.display()))]
+    /// This error occurs when a note contains invalid UTF8 and [EncodingErrorStrategy::Fail] is
+    /// in effect.
+    CharacterEncodingError {
+        path: PathBuf,
+        source: str::Utf8Error,
+    },
 
     #[snafu(display("Recursion limit exceeded"))]
     /// This error occurs when embedded notes are too deeply nested or cause an infinite loop.
@@ -196,6 +321,13 @@ pub enum ExportError {
         source: Box<ExportError>,
     },
 
+    #[snafu(display("Invalid regular expression '{}'", pattern))]
+    /// This occurs when a regular expression passed to the exporter fails to compile.
+    RegexError {
+        pattern: String,
+        source: regex::Error,
+    },
+
     #[snafu(display("Failed to decode YAML frontmatter in '{}'", path.display()))]
     FrontMatterDecodeError {
         path: PathBuf,
@@ -209,9 +341,197 @@ pub enum ExportError {
         #[snafu(source(from(serde_yaml::Error, Box::new)))]
         source: Box<serde_yaml::Error>,
     },
+
+    #[snafu(display("Postprocessor aborted the export of '{}': {}", path.display(), message))]
+    /// This occurs when a postprocessor returns [PostprocessorResult::Abort].
+    PostprocessorAborted { path: PathBuf, message: String },
+
+    #[snafu(display("Failed to decode Base definition in '{}'", path.display()))]
+    /// This occurs when a `.base` file can't be parsed while [Exporter::render_bases] is enabled.
+    BaseDecodeError {
+        path: PathBuf,
+        #[snafu(source(from(serde_yaml::Error, Box::new)))]
+        source: Box<serde_yaml::Error>,
+    },
+
+    #[snafu(display("Failed to decode JSON schema in '{}'", path.display()))]
+    /// This occurs when the schema set through [Exporter::frontmatter_schema] can't be parsed.
+    SchemaDecodeError {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[snafu(display("Invalid JSON schema in '{}': {}", path.display(), message))]
+    /// This occurs when the schema set through [Exporter::frontmatter_schema] is valid JSON, but
+    /// not a valid JSON schema (or refers to schemas which can't be resolved).
+    InvalidSchema { path: PathBuf, message: String },
+
+    #[snafu(display(
+        "Frontmatter of '{}' does not match the schema: {}",
+        path.display(),
+        violations.join("; ")
+    ))]
+    /// This occurs when the frontmatter of a note doesn't conform to the schema set through
+    /// [Exporter::frontmatter_schema] in strict mode.
+    FrontmatterSchemaViolation {
+        path: PathBuf,
+        violations: Vec<String>,
+    },
+
+    #[snafu(display("Failed to start {} threads", threads))]
+    /// This occurs when the threads set through [Exporter::threads] can't be started.
+    ThreadPoolError {
+        threads: usize,
+        source: rayon::ThreadPoolBuildError,
+    },
+
+    #[cfg(feature = "wasm")]
+    #[snafu(display("Failed to load WASM postprocessor '{}': {}", path.display(), message))]
+    /// This occurs when the module given to [Exporter::add_wasm_postprocessor] can't be loaded.
+    WasmLoadError { path: PathBuf, message: String },
+}
+
+#[non_exhaustive]
+#[derive(Debug, Snafu)]
+/// ConfigError represents the configuration problems which may be reported by
+/// [Exporter::validate].
+pub enum ConfigError {
+    #[snafu(display("Source '{}' does not exist", path.display()))]
+    /// The source (vault root) does not exist.
+    SourceNotFound { path: PathBuf },
+
+    #[snafu(display("Destination '{}' does not exist", path.display()))]
+    /// The destination does not exist.
+    ///
+    /// When exporting a single file to a destination filename, this refers to the parent directory
+    /// of the destination.
+    DestinationNotFound { path: PathBuf },
+
+    #[snafu(display("Destination '{}' is the same as the source", path.display()))]
+    /// The destination is the same as the source, which would cause notes to be overwritten.
+    DestinationIsSource { path: PathBuf },
+
+    #[snafu(display("Destination '{}' is inside source '{}'", path.display(), root.display()))]
+    /// The destination is nested within the source, which would cause exported notes to be
+    /// exported again by later runs.
+    DestinationInsideSource { path: PathBuf, root: PathBuf },
+
+    #[snafu(display("Destination '{}' is not writable", path.display()))]
+    /// The destination is read-only.
+    DestinationNotWritable { path: PathBuf },
+
+    #[snafu(display("Start path '{}' does not exist", path.display()))]
+    /// The path given to [Exporter::start_at] does not exist.
+    StartAtNotFound { path: PathBuf },
+
+    #[snafu(display("Start path '{}' is not inside source '{}'", path.display(), root.display()))]
+    /// The path given to [Exporter::start_at] is not located within the source.
+    StartAtOutsideSource { path: PathBuf, root: PathBuf },
+
+    #[snafu(display("'{}' is used both by {} and by {}", key, include, exclude))]
+    /// The same key is given to an option which includes files and an option which excludes them,
+    /// such as a [publish marker][Exporter::publish_marker] which is also the
+    /// [ignore filename][WalkOptions::ignore_filename].
+    ConflictingKeys {
+        key: String,
+        include: &'static str,
+        exclude: &'static str,
+    },
+
+    #[snafu(display("Invalid glob pattern in ignore file: {}", source))]
+    /// An ignore file in the source contains a pattern which isn't a valid glob.
+    InvalidGlob { source: ignore::Error },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Available strategies for dealing with notes which aren't valid UTF-8.
+pub enum EncodingErrorStrategy {
+    /// Abort the export with [ExportError::CharacterEncodingError] (the default).
+    Fail,
+    /// Replace invalid sequences with `U+FFFD REPLACEMENT CHARACTER` and print a warning.
+    Lossy,
+    /// Skip the note and print a warning. Embeds of the note produce no content.
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Available strategies for Obsidian comments (`%% text %%`) in notes.
+pub enum CommentStrategy {
+    /// Leave comments in the exported notes as-is (the default).
+    Keep,
+    /// Remove comments from the exported notes.
+    Strip,
+    /// Convert comments into HTML comments (`<!-- text -->`), which are hidden when the note is
+    /// rendered but remain in its source. Dashes in the text which would end the HTML comment
+    /// early (such as `--`) are separated by spaces.
+    ToHtmlComment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Available strategies for embeds without a file extension (such as `![[Diagram]]`) when both a
+/// note and an attachment with that name exist (`Diagram.md` and `Diagram.png`).
+///
+/// Embeds which include an extension (`![[Diagram.png]]`) always refer to that exact file.
+pub enum EmbedAmbiguity {
+    /// Embed the note (the default).
+    PreferNote,
+    /// Embed the attachment.
+    PreferAttachment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Available strategies for finding the file a link or embed (such as `[[Note]]`) refers to.
+///
+/// These mirror the "New link format" setting of Obsidian. Whichever strategy is used, links which
+/// don't match a file this way are resolved as with [LinkResolution::ShortestPath].
+pub enum LinkResolution {
+    /// Use the file in the vault with a matching name (or path suffix) which has the shortest
+    /// path (the default). Embeds of attachments matching several files produce a warning.
+    ShortestPath,
+    /// Prefer a matching file in the directory of the note containing the link.
+    RelativeFirst,
+    /// Prefer a file at the given path from the root of the vault.
+    Absolute,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How links to other files in the vault are written, set through [Exporter::link_style].
+pub enum LinkStyle {
+    /// Paths relative to the note containing the link, such as `../notes/Note.md` (the default).
+    Relative,
+    /// Paths from the root of the destination, such as `/notes/Note.md`, prefixed with the base
+    /// set through [Exporter::link_base].
+    AbsoluteFromRoot,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+/// What to do with the `^block-id` markers which identify blocks in notes, set through
+/// [Exporter::block_ids].
+pub enum BlockIdStyle {
+    /// Keep the markers as they were written (the default).
+    Keep,
+    /// Remove the markers.
+    Strip,
+    /// Replace the markers with an anchor (`<span id="block-id"></span>`), so links to the block
+    /// (`[[Note#^block-id]]`) point to it.
+    Anchor,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// What to output in place of embeds (`![[Note]]`) which don't refer to any file in the vault.
+pub enum UnresolvedEmbedStyle {
+    /// Keep the embed as it was written (`![[Note]]`).
+    Leave,
+    /// Insert the given text, with `{target}` replaced by the name of the missing file.
+    Placeholder(String),
+    /// Insert a link to where the missing note would have been exported.
+    Link,
+    /// Leave the embed out entirely (the default).
+    Remove,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 /// Emitted by [Postprocessor]s to signal the next action to take.
 pub enum PostprocessorResult {
     /// Continue with the next post-processor (if any).
@@ -220,6 +540,50 @@ pub enum PostprocessorResult {
     StopHere,
     /// Skip this note (don't export it) and don't run any more post-processors.
     StopAndSkipNote,
+    /// Abort the export with an [ExportError::PostprocessorAborted] error carrying this message.
+    ///
+    /// Notes are exported in parallel, so notes which are already being exported by other threads
+    /// may still be written, but no new notes are started.
+    Abort(String),
+}
+
+#[derive(Clone)]
+// A postprocessor registered through either Exporter::add_postprocessor or
+// Exporter::add_postprocessor_boxed.
+enum PostprocessorRef<'a> {
+    Borrowed(&'a Postprocessor),
+    Boxed(Arc<Mutex<Box<BoxedPostprocessor>>>),
+    #[cfg(feature = "wasm")]
+    Wasm(Arc<wasm::WasmPostprocessor>),
+}
+
+impl<'a> PostprocessorRef<'a> {
+    fn call<'b>(
+        &self,
+        context: Context,
+        events: MarkdownEvents<'b>,
+    ) -> (Context, MarkdownEvents<'b>, PostprocessorResult) {
+        match self {
+            PostprocessorRef::Borrowed(func) => func(context, events),
+            PostprocessorRef::Boxed(func) => {
+                // The lock is poisoned when the postprocessor panicked on another note. When
+                // panics are caught (see Exporter::catch_postprocessor_panics), keep using it.
+                let mut func = func.lock().unwrap_or_else(|err| err.into_inner());
+                func(context, events)
+            }
+            #[cfg(feature = "wasm")]
+            PostprocessorRef::Wasm(func) => func.call(context, events),
+        }
+    }
+}
+
+// The result of parsing a single note.
+struct ParsedNote<'a> {
+    frontmatter: Frontmatter,
+    events: MarkdownEvents<'a>,
+    embed_ranges: Vec<Range<usize>>,
+    inline_tags: Vec<String>,
+    embeds: Vec<PathBuf>,
 }
 
 #[derive(Clone)]
@@ -229,16 +593,113 @@ pub enum PostprocessorResult {
 /// customization using [`Exporter::frontmatter_strategy`] and [`Exporter::walk_options`].
 ///
 /// After that, calling [`Exporter::run`] will start the export process.
+///
+/// An Exporter may be run several times, for example to export to different destinations (see
+/// [`Exporter::set_destination`]) or with different settings. The contents of the vault and the
+/// frontmatter index are only built on the first run and reused afterwards. They're rebuilt when
+/// a setting which determines what's part of the vault or how notes are read changes, or after
+/// calling [`Exporter::clear_cache`] when the vault itself has changed. Everything else, such as
+/// the [RunSummary] or the profile, is recomputed on every run.
 pub struct Exporter<'a> {
     root: PathBuf,
     destination: PathBuf,
     start_at: PathBuf,
+    start_at_as_root: bool,
     frontmatter_strategy: FrontmatterStrategy,
+    frontmatter_as_export_const: bool,
+    frontmatter_as_table: bool,
+    body_wrapper: Option<(String, String)>,
+    passthrough_unmodified: bool,
+    keep_frontmatter_comments: bool,
+    max_attachment_size: Option<u64>,
+    skip_attachments: bool,
+    reuse_index: bool,
     vault_contents: Option<Vec<PathBuf>>,
+    normalized_vault_contents: Option<Vec<PathBuf>>,
+    path_index: HashMap<PathBuf, usize>,
+    name_index: HashMap<String, Vec<usize>>,
+    frontmatter_index: Option<Arc<HashMap<PathBuf, Frontmatter>>>,
+    alias_index: HashMap<String, PathBuf>,
+    attachment_hashes: HashMap<PathBuf, String>,
+    hash_attachment_names: Option<usize>,
+    normalize_unicode: bool,
+    filename_sanitizer: Option<(String, Option<char>)>,
     walk_options: WalkOptions<'a>,
     process_embeds_recursively: bool,
-    postprocessors: Vec<&'a Postprocessor>,
+    skip_empty_notes: bool,
+    encoding_error_strategy: EncodingErrorStrategy,
+    comment_strategy: CommentStrategy,
+    embed_ambiguity: EmbedAmbiguity,
+    unresolved_embed_style: UnresolvedEmbedStyle,
+    block_id_style: BlockIdStyle,
+    embed_skip_key: Option<String>,
+    embed_skip_comment: bool,
+    link_resolution: LinkResolution,
+    link_style: LinkStyle,
+    link_base: String,
+    publish_marker: Option<String>,
+    frontmatter_schema: Option<(PathBuf, bool)>,
+    schema: Option<jsonschema::Validator>,
+    sidecar_extension: Option<String>,
+    sidecar_precedence: SidecarPrecedence,
+    link_text_from_title: bool,
+    frontmatter_attachment_keys: Vec<String>,
+    frontmatter_key_renames: HashMap<String, String>,
+    export_files: Option<Vec<PathBuf>>,
+    backmatter: bool,
+    catch_postprocessor_panics: bool,
+    slug_key: Option<String>,
+    provenance_keys: Option<(Option<String>, Option<String>)>,
+    provenance: Frontmatter,
+    backlink_strategy: Option<BacklinkStrategy>,
+    backlinks: HashMap<PathBuf, Vec<PathBuf>>,
+    exported_notes: HashSet<PathBuf>,
+    inlined_svgs: HashSet<PathBuf>,
+    colocate_attachments: Option<(String, SharedAttachments)>,
+    attachment_references: HashMap<PathBuf, Vec<PathBuf>>,
+    include_tags: Vec<String>,
+    output_format: OutputFormat,
+    html_template: Option<String>,
+    html_template_file: Option<PathBuf>,
+    metadata_comment_patterns: Vec<(Regex, String)>,
+    directive_comment: Option<(Regex, String)>,
+    render_bases: bool,
+    inline_svg: bool,
+    sanitize_svg: bool,
+    responsive_images: HashMap<String, String>,
+    concatenate_to: Option<PathBuf>,
+    also_concatenate: Option<(PathBuf, NoteOrder)>,
+    prefix_concatenated_anchors: bool,
+    rendered_notes: Arc<Mutex<Vec<ConcatenatedNote<'static>>>>,
+    pending_attachments: Arc<Mutex<HashMap<PathBuf, Vec<PathBuf>>>>,
+    issues: Arc<Mutex<Vec<Issue>>>,
+    directory_indexes: Option<IndexConfig>,
+    indexed_notes: Arc<Mutex<Vec<(PathBuf, String)>>>,
+    encoding_warnings: Arc<Mutex<HashSet<PathBuf>>>,
+    note_sources: Arc<Mutex<HashMap<PathBuf, String>>>,
+    note_order: NoteOrder,
+    previous_export: Option<PathBuf>,
+    changes: Arc<Mutex<Vec<NoteChange>>>,
+    redirect_map_output: Option<PathBuf>,
+    netlify_redirects_output: Option<PathBuf>,
+    search_index_output: Option<(PathBuf, SearchIndexFormat)>,
+    search_index: Arc<Mutex<Vec<SearchRecord>>>,
+    metadata_sidecar: bool,
+    redirects: Arc<Mutex<Vec<(String, String)>>>,
+    summary: Arc<Mutex<RunSummary>>,
+    profile: Option<Arc<Profile>>,
+    threads: usize,
+    postprocessors: Vec<PostprocessorRef<'a>>,
     embed_postprocessors: Vec<&'a Postprocessor>,
+    attachment_processors: Vec<&'a AttachmentProcessor>,
+    progress_callback: Option<&'a ProgressCallback>,
+    warning_callback: Option<&'a WarningCallback>,
+    link_callback: Option<&'a LinkCallback>,
+    writer_factory: Option<&'a WriterFactory>,
+    log_file: Option<(PathBuf, LogLevel)>,
+    log: Arc<Mutex<Option<File>>>,
+    warnings_to_stderr: bool,
+    warning_format: WarningFormat,
 }
 
 impl<'a> fmt::Debug for Exporter<'a> {
@@ -246,13 +707,110 @@ impl<'a> fmt::Debug for Exporter<'a> {
         f.debug_struct("WalkOptions")
             .field("root", &self.root)
             .field("destination", &self.destination)
+            .field("start_at_as_root", &self.start_at_as_root)
             .field("frontmatter_strategy", &self.frontmatter_strategy)
+            .field(
+                "frontmatter_as_export_const",
+                &self.frontmatter_as_export_const,
+            )
+            .field("frontmatter_as_table", &self.frontmatter_as_table)
+            .field("body_wrapper", &self.body_wrapper)
+            .field("passthrough_unmodified", &self.passthrough_unmodified)
+            .field("keep_frontmatter_comments", &self.keep_frontmatter_comments)
+            .field("max_attachment_size", &self.max_attachment_size)
+            .field("skip_attachments", &self.skip_attachments)
+            .field("reuse_index", &self.reuse_index)
             .field("vault_contents", &self.vault_contents)
+            .field(
+                "path_index",
+                &format!("<{} paths indexed>", self.path_index.len()),
+            )
+            .field(
+                "name_index",
+                &format!("<{} filenames indexed>", self.name_index.len()),
+            )
+            .field(
+                "frontmatter_index",
+                &self
+                    .frontmatter_index
+                    .as_ref()
+                    .map(|index| format!("<{} notes indexed>", index.len())),
+            )
+            .field(
+                "alias_index",
+                &format!("<{} aliases indexed>", self.alias_index.len()),
+            )
             .field("walk_options", &self.walk_options)
+            .field("hash_attachment_names", &self.hash_attachment_names)
+            .field("normalize_unicode", &self.normalize_unicode)
+            .field("filename_sanitizer", &self.filename_sanitizer)
             .field(
                 "process_embeds_recursively",
                 &self.process_embeds_recursively,
             )
+            .field("skip_empty_notes", &self.skip_empty_notes)
+            .field("encoding_error_strategy", &self.encoding_error_strategy)
+            .field("comment_strategy", &self.comment_strategy)
+            .field("embed_ambiguity", &self.embed_ambiguity)
+            .field("unresolved_embed_style", &self.unresolved_embed_style)
+            .field("block_id_style", &self.block_id_style)
+            .field("embed_skip_key", &self.embed_skip_key)
+            .field("embed_skip_comment", &self.embed_skip_comment)
+            .field("link_resolution", &self.link_resolution)
+            .field("link_style", &self.link_style)
+            .field("link_base", &self.link_base)
+            .field("publish_marker", &self.publish_marker)
+            .field("frontmatter_schema", &self.frontmatter_schema)
+            .field("sidecar_extension", &self.sidecar_extension)
+            .field("sidecar_precedence", &self.sidecar_precedence)
+            .field("link_text_from_title", &self.link_text_from_title)
+            .field(
+                "frontmatter_attachment_keys",
+                &self.frontmatter_attachment_keys,
+            )
+            .field("frontmatter_key_renames", &self.frontmatter_key_renames)
+            .field("export_files", &self.export_files)
+            .field("backmatter", &self.backmatter)
+            .field(
+                "catch_postprocessor_panics",
+                &self.catch_postprocessor_panics,
+            )
+            .field("slug_key", &self.slug_key)
+            .field("provenance_keys", &self.provenance_keys)
+            .field("backlink_strategy", &self.backlink_strategy)
+            .field("colocate_attachments", &self.colocate_attachments)
+            .field("include_tags", &self.include_tags)
+            .field("output_format", &self.output_format)
+            .field("html_template", &self.html_template)
+            .field("html_template_file", &self.html_template_file)
+            .field("metadata_comment_patterns", &self.metadata_comment_patterns)
+            .field("directive_comment", &self.directive_comment)
+            .field("render_bases", &self.render_bases)
+            .field("inline_svg", &self.inline_svg)
+            .field("responsive_images", &self.responsive_images)
+            .field("sanitize_svg", &self.sanitize_svg)
+            .field("concatenate_to", &self.concatenate_to)
+            .field("also_concatenate", &self.also_concatenate)
+            .field(
+                "prefix_concatenated_anchors",
+                &self.prefix_concatenated_anchors,
+            )
+            .field("directory_indexes", &self.directory_indexes)
+            .field("note_order", &self.note_order)
+            .field("previous_export", &self.previous_export)
+            .field("redirect_map_output", &self.redirect_map_output)
+            .field("netlify_redirects_output", &self.netlify_redirects_output)
+            .field("search_index_output", &self.search_index_output)
+            .field("metadata_sidecar", &self.metadata_sidecar)
+            .field("profile", &self.profile.is_some())
+            .field("threads", &self.threads)
+            .field("progress_callback", &self.progress_callback.is_some())
+            .field("warning_callback", &self.warning_callback.is_some())
+            .field("link_callback", &self.link_callback.is_some())
+            .field("writer_factory", &self.writer_factory.is_some())
+            .field("log_file", &self.log_file)
+            .field("warnings_to_stderr", &self.warnings_to_stderr)
+            .field("warning_format", &self.warning_format)
             .field(
                 "postprocessors",
                 &format!("<{} postprocessors active>", self.postprocessors.len()),
@@ -264,6 +822,13 @@ impl<'a> fmt::Debug for Exporter<'a> {
                     self.embed_postprocessors.len()
                 ),
             )
+            .field(
+                "attachment_processors",
+                &format!(
+                    "<{} attachment processors active>",
+                    self.attachment_processors.len()
+                ),
+            )
             .finish()
     }
 }
@@ -274,14 +839,104 @@ impl<'a> Exporter<'a> {
     pub fn new(root: PathBuf, destination: PathBuf) -> Exporter<'a> {
         Exporter {
             start_at: root.clone(),
+            start_at_as_root: true,
             root,
             destination,
             frontmatter_strategy: FrontmatterStrategy::Auto,
+            frontmatter_as_export_const: false,
+            frontmatter_as_table: false,
+            body_wrapper: None,
+            passthrough_unmodified: false,
+            keep_frontmatter_comments: false,
+            max_attachment_size: None,
+            skip_attachments: false,
             walk_options: WalkOptions::default(),
             process_embeds_recursively: true,
+            skip_empty_notes: false,
+            encoding_error_strategy: EncodingErrorStrategy::Fail,
+            comment_strategy: CommentStrategy::Keep,
+            embed_ambiguity: EmbedAmbiguity::PreferNote,
+            unresolved_embed_style: UnresolvedEmbedStyle::Remove,
+            block_id_style: BlockIdStyle::Keep,
+            embed_skip_key: None,
+            embed_skip_comment: false,
+            link_resolution: LinkResolution::ShortestPath,
+            link_style: LinkStyle::Relative,
+            link_base: String::new(),
+            publish_marker: None,
+            frontmatter_schema: None,
+            schema: None,
+            sidecar_extension: None,
+            sidecar_precedence: SidecarPrecedence::Inline,
+            link_text_from_title: false,
+            frontmatter_attachment_keys: vec![],
+            frontmatter_key_renames: HashMap::new(),
+            export_files: None,
+            backmatter: false,
+            catch_postprocessor_panics: false,
+            slug_key: None,
+            provenance_keys: None,
+            provenance: Frontmatter::new(),
+            backlink_strategy: None,
+            backlinks: HashMap::new(),
+            exported_notes: HashSet::new(),
+            inlined_svgs: HashSet::new(),
+            colocate_attachments: None,
+            attachment_references: HashMap::new(),
+            include_tags: vec![],
+            output_format: OutputFormat::Markdown,
+            html_template: None,
+            html_template_file: None,
+            metadata_comment_patterns: vec![],
+            directive_comment: None,
+            render_bases: false,
+            inline_svg: false,
+            sanitize_svg: false,
+            responsive_images: HashMap::new(),
+            concatenate_to: None,
+            also_concatenate: None,
+            prefix_concatenated_anchors: false,
+            rendered_notes: Arc::new(Mutex::new(vec![])),
+            pending_attachments: Arc::new(Mutex::new(HashMap::new())),
+            issues: Arc::new(Mutex::new(vec![])),
+            directory_indexes: None,
+            indexed_notes: Arc::new(Mutex::new(vec![])),
+            encoding_warnings: Arc::new(Mutex::new(HashSet::new())),
+            note_sources: Arc::new(Mutex::new(HashMap::new())),
+            note_order: NoteOrder::default(),
+            previous_export: None,
+            changes: Arc::new(Mutex::new(vec![])),
+            redirect_map_output: None,
+            netlify_redirects_output: None,
+            search_index_output: None,
+            search_index: Arc::new(Mutex::new(vec![])),
+            metadata_sidecar: false,
+            redirects: Arc::new(Mutex::new(vec![])),
+            summary: Arc::new(Mutex::new(RunSummary::default())),
+            profile: None,
+            threads: 0,
+            reuse_index: false,
             vault_contents: None,
+            normalized_vault_contents: None,
+            path_index: HashMap::new(),
+            name_index: HashMap::new(),
+            frontmatter_index: None,
+            alias_index: HashMap::new(),
+            attachment_hashes: HashMap::new(),
+            hash_attachment_names: None,
+            normalize_unicode: false,
+            filename_sanitizer: None,
             postprocessors: vec![],
             embed_postprocessors: vec![],
+            attachment_processors: vec![],
+            progress_callback: None,
+            warning_callback: None,
+            link_callback: None,
+            writer_factory: None,
+            log_file: None,
+            log: Arc::new(Mutex::new(None)),
+            warnings_to_stderr: true,
+            warning_format: WarningFormat::Plain,
         }
     }
 
@@ -291,21 +946,164 @@ impl<'a> Exporter<'a> {
     /// When `start_at` is set, only notes under this path will be exported to the target destination.
     pub fn start_at(&mut self, start_at: PathBuf) -> &mut Exporter<'a> {
         self.start_at = start_at;
+        self.clear_cache();
+        self
+    }
+
+    /// Set whether the directory set through [Exporter::start_at] becomes the root of the export
+    /// (the default), or whether notes keep their location relative to the root of the vault.
+    ///
+    /// With `start_at` set to `journal/`, the note `journal/2024-01-01.md` is exported to
+    /// `2024-01-01.md` in the destination by default, and to `journal/2024-01-01.md` when this is
+    /// disabled. Links using [LinkStyle::AbsoluteFromRoot] follow the chosen layout, relative
+    /// links are the same either way. Exports of a single file are written to the destination
+    /// as before.
+    pub fn start_at_as_root(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.start_at_as_root = enabled;
+        self.clear_cache();
+        self
+    }
+
+    /// Set the destination notes are exported to, replacing the destination given to
+    /// [`Exporter::new`].
+    pub fn set_destination(&mut self, destination: PathBuf) -> &mut Exporter<'a> {
+        self.destination = destination;
+        self
+    }
+
+    /// Set whether the contents of the vault and the frontmatter index are kept across calls to
+    /// [Exporter::run], instead of walking and indexing the vault again for every run.
+    ///
+    /// This speeds up repeated exports of a vault which doesn't change, such as exports of the same
+    /// vault to multiple destinations. Files which are added, removed or changed after the first
+    /// run aren't picked up until [Exporter::clear_cache] is called. This is disabled by default.
+    pub fn reuse_index(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.reuse_index = enabled;
+        self
+    }
+
+    /// Discard the contents of the vault and the frontmatter index cached by a previous run, so
+    /// the next run picks up changes made to the vault in the meantime. This is only needed with
+    /// [reuse_index][Exporter::reuse_index] enabled.
+    pub fn clear_cache(&mut self) -> &mut Exporter<'a> {
+        self.vault_contents = None;
+        self.normalized_vault_contents = None;
+        self.path_index = HashMap::new();
+        self.name_index = HashMap::new();
+        self.frontmatter_index = None;
+        self.alias_index = HashMap::new();
+        self.attachment_hashes = HashMap::new();
+        self
+    }
+
+    /// Export only the given files, rather than every file in the vault (or under
+    /// [start_at][Exporter::start_at]).
+    ///
+    /// Paths are relative to the root of the vault, or start with the path of the vault itself.
+    /// Links and embeds are still resolved against the whole vault, and attachments embedded by the
+    /// given notes are exported along with them. A warning is printed for paths which aren't part
+    /// of the vault.
+    pub fn export_files(&mut self, files: Vec<PathBuf>) -> &mut Exporter<'a> {
+        self.export_files = Some(files);
         self
     }
 
     /// Set the [`WalkOptions`] to be used for this exporter.
     pub fn walk_options(&mut self, options: WalkOptions<'a>) -> &mut Exporter<'a> {
         self.walk_options = options;
+        self.clear_cache();
         self
     }
 
     /// Set the [`FrontmatterStrategy`] to be used for this exporter.
+    ///
+    /// Individual notes may override this by setting `export_frontmatter` to `always`, `never` or
+    /// `auto` in their frontmatter. This key is removed from the exported note.
     pub fn frontmatter_strategy(&mut self, strategy: FrontmatterStrategy) -> &mut Exporter<'a> {
         self.frontmatter_strategy = strategy;
         self
     }
 
+    /// Set whether to write frontmatter as a JavaScript export instead of a YAML block.
+    ///
+    /// MDX doesn't understand YAML frontmatter without additional configuration. When enabled, the
+    /// frontmatter of a note is written as `export const frontmatter = {...};` at the top of the
+    /// note, with the frontmatter serialized as JSON, so MDX components can read it. Whether
+    /// frontmatter is written at all is still determined by [Exporter::frontmatter_strategy].
+    pub fn frontmatter_as_export_const(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.frontmatter_as_export_const = enabled;
+        self
+    }
+
+    /// Set whether to list the frontmatter of notes in a table at the top of their body, for
+    /// targets which don't support frontmatter.
+    ///
+    /// This only applies to notes for which frontmatter isn't written, because the
+    /// [FrontmatterStrategy] (including an `export_frontmatter` override in the note) is `never`.
+    /// The table has a row for each key. Nested mappings are flattened into keys such as
+    /// `author.name`, lists are joined with commas, unless they contain mappings or lists, in
+    /// which case they're written as JSON. Notes without frontmatter don't get a table.
+    pub fn frontmatter_as_table(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.frontmatter_as_table = enabled;
+        self
+    }
+
+    /// Wrap the content of every note between `open` and `close`, such as
+    /// `<article class="note">` and `</article>`.
+    ///
+    /// Both are added to the note as raw HTML (or a JSX component, for MDX), so they end up
+    /// between the frontmatter and the end of the note. Embedded notes aren't wrapped separately.
+    pub fn wrap_body(&mut self, open: String, close: String) -> &mut Exporter<'a> {
+        self.body_wrapper = Some((open, close));
+        self
+    }
+
+    /// Copy notes which don't need any changes byte for byte, rather than writing out the parsed
+    /// note.
+    ///
+    /// Exported notes are normally rendered from their parsed form, which normalizes formatting
+    /// such as list markers, emphasis characters and whitespace. When enabled, notes whose
+    /// contents and frontmatter come out of the export unchanged (because they have no links,
+    /// embeds or other content which is rewritten and weren't modified by postprocessors) are
+    /// written exactly as they appear in the vault. This has no effect on HTML output.
+    pub fn passthrough_unmodified(&mut self, passthrough: bool) -> &mut Exporter<'a> {
+        self.passthrough_unmodified = passthrough;
+        self
+    }
+
+    /// Write the frontmatter of notes exactly as it appears in the vault when it's unchanged,
+    /// keeping its comments and formatting.
+    ///
+    /// Frontmatter is normally parsed and written out again, which drops comments (`# like this`).
+    /// When enabled, frontmatter which comes out of the export unchanged is copied from the note
+    /// instead. Comments are still lost when the frontmatter is modified in any way, such as by a
+    /// postprocessor or by keys added through other options.
+    pub fn keep_frontmatter_comments(&mut self, keep: bool) -> &mut Exporter<'a> {
+        self.keep_frontmatter_comments = keep;
+        self
+    }
+
+    /// Skip copying attachments which are larger than `size` bytes, or copy all attachments when
+    /// `size` is `None` (the default).
+    ///
+    /// Each skipped attachment gets a warning and is reported in the [RunSummary] with
+    /// [SkipReason::TooLarge]. Links and embeds which refer to it are left as they are, so they
+    /// point to a file which doesn't exist in the export.
+    pub fn max_attachment_size(&mut self, size: Option<u64>) -> &mut Exporter<'a> {
+        self.max_attachment_size = size;
+        self
+    }
+
+    /// Set whether to only export notes, without copying any attachments.
+    ///
+    /// Links and embeds which refer to attachments are still rewritten to point to where the
+    /// attachment would have been exported, so attachments may be copied to the destination by
+    /// other means.
+    pub fn skip_attachments(&mut self, skip: bool) -> &mut Exporter<'a> {
+        self.skip_attachments = skip;
+        self
+    }
+
     /// Set the behavior when recursive embeds are encountered.
     ///
     /// When `recursive` is true (the default), emdeds are always processed recursively. This may
@@ -319,154 +1117,1956 @@ impl<'a> Exporter<'a> {
         self
     }
 
-    /// Append a function to the chain of [postprocessors][Postprocessor] to run on exported Obsidian Markdown notes.
-    pub fn add_postprocessor(&mut self, processor: &'a Postprocessor) -> &mut Exporter<'a> {
-        self.postprocessors.push(processor);
+    /// Set whether notes without any content should be skipped.
+    ///
+    /// A note is considered empty when, after all postprocessors have run, its body contains
+    /// nothing but whitespace. Frontmatter is not taken into account, so stub notes consisting of
+    /// only frontmatter are skipped as well, as are notes whose only content is an embed of a note
+    /// which is excluded from the export.
+    pub fn skip_empty_notes(&mut self, skip: bool) -> &mut Exporter<'a> {
+        self.skip_empty_notes = skip;
         self
     }
 
-    /// Append a function to the chain of [postprocessors][Postprocessor] for embeds.
-    pub fn add_embed_postprocessor(&mut self, processor: &'a Postprocessor) -> &mut Exporter<'a> {
-        self.embed_postprocessors.push(processor);
+    /// Set the [`EncodingErrorStrategy`] for notes which aren't valid UTF-8.
+    pub fn on_encoding_error(&mut self, strategy: EncodingErrorStrategy) -> &mut Exporter<'a> {
+        self.encoding_error_strategy = strategy;
+        self.clear_cache();
         self
     }
 
-    /// Export notes using the settings configured on this exporter.
-    pub fn run(&mut self) -> Result<()> {
-        if !self.root.exists() {
-            return Err(ExportError::PathDoesNotExist {
-                path: self.root.clone(),
-            });
-        }
-
-        self.vault_contents = Some(vault_contents(
-            self.root.as_path(),
-            self.walk_options.clone(),
-        )?);
+    /// Set the [`CommentStrategy`] for Obsidian comments (`%% text %%`).
+    ///
+    /// Comments may span multiple lines. Comments within code blocks and inline code aren't
+    /// affected, as Obsidian doesn't treat these as comments either.
+    pub fn comments(&mut self, strategy: CommentStrategy) -> &mut Exporter<'a> {
+        self.comment_strategy = strategy;
+        self
+    }
 
-        // When a single file is specified, just need to export that specific file instead of
-        // iterating over all discovered files. This also allows us to accept destination as either
-        // a file or a directory name.
-        if self.root.is_file() || self.start_at.is_file() {
-            let source_filename = self
-                .start_at
-                .file_name()
-                .expect("File without a filename? How is that possible?")
-                .to_string_lossy();
+    /// Set the [`EmbedAmbiguity`] strategy for embeds which may refer to either a note or an
+    /// attachment.
+    ///
+    /// Regardless of the strategy, a warning is printed when such an ambiguous embed is found.
+    pub fn embed_ambiguity(&mut self, strategy: EmbedAmbiguity) -> &mut Exporter<'a> {
+        self.embed_ambiguity = strategy;
+        self
+    }
 
-            let destination = match self.destination.is_dir() {
-                true => self.destination.join(String::from(source_filename)),
-                false => {
-                    let parent = self.destination.parent().unwrap_or(&self.destination);
-                    // Avoid recursively creating self.destination through the call to
-                    // export_note when the parent directory doesn't exist.
-                    if !parent.exists() {
-                        return Err(ExportError::PathDoesNotExist {
-                            path: parent.to_path_buf(),
-                        });
-                    }
-                    self.destination.clone()
-                }
-            };
-            return self.export_note(&self.start_at, &destination);
-        }
+    /// Set the [`LinkResolution`] strategy used to find the files which links and embeds refer to.
+    ///
+    /// This matters for vaults with several files of the same name in different directories.
+    pub fn link_resolution(&mut self, strategy: LinkResolution) -> &mut Exporter<'a> {
+        self.link_resolution = strategy;
+        self
+    }
 
-        if !self.destination.exists() {
-            return Err(ExportError::PathDoesNotExist {
-                path: self.destination.clone(),
-            });
-        }
-        self.vault_contents
-            .as_ref()
-            .unwrap()
-            .clone()
-            .into_par_iter()
-            .filter(|file| file.starts_with(&self.start_at))
-            .try_for_each(|file| {
-                let relative_path = file
-                    .strip_prefix(&self.start_at.clone())
-                    .expect("file should always be nested under root")
-                    .to_path_buf();
-                let destination = &self.destination.join(&relative_path);
-                self.export_note(&file, destination)
-            })?;
-        Ok(())
+    /// Set the [`LinkStyle`] of links to other notes and attachments.
+    ///
+    /// [LinkStyle::AbsoluteFromRoot] is meant for static site generators which serve the
+    /// destination from the root of a site (or from [Exporter::link_base]). Paths follow the
+    /// layout of the destination, including changes to filenames made by other options.
+    pub fn link_style(&mut self, style: LinkStyle) -> &mut Exporter<'a> {
+        self.link_style = style;
+        self
     }
 
-    fn export_note(&self, src: &Path, dest: &Path) -> Result<()> {
-        match is_markdown_file(src) {
-            true => self.parse_and_export_obsidian_note(src, dest),
-            false => copy_file(src, dest),
-        }
-        .context(FileExportError { path: src })
+    /// Set the path under which the destination is served, such as `/docs`, which prefixes all
+    /// links when using [LinkStyle::AbsoluteFromRoot].
+    pub fn link_base(&mut self, base: String) -> &mut Exporter<'a> {
+        self.link_base = base;
+        self
     }
 
-    fn parse_and_export_obsidian_note(&self, src: &Path, dest: &Path) -> Result<()> {
-        let mut context = Context::new(src.to_path_buf(), dest.to_path_buf());
+    /// Set the [`UnresolvedEmbedStyle`] for embeds of files which can't be found in the vault.
+    ///
+    /// Regardless of the style, a warning is printed for each such embed.
+    pub fn unresolved_embed_style(&mut self, style: UnresolvedEmbedStyle) -> &mut Exporter<'a> {
+        self.unresolved_embed_style = style;
+        self
+    }
 
-        let (frontmatter, mut markdown_events) = self.parse_obsidian_note(src, &context)?;
-        context.frontmatter = frontmatter;
-        for func in &self.postprocessors {
-            let res = func(context, markdown_events);
-            context = res.0;
-            markdown_events = res.1;
-            match res.2 {
-                PostprocessorResult::StopHere => break,
-                PostprocessorResult::StopAndSkipNote => return Ok(()),
-                PostprocessorResult::Continue => (),
-            }
-        }
+    /// Set the [`BlockIdStyle`] for the `^block-id` markers at the end of blocks.
+    ///
+    /// Markers of blocks which are embedded by themselves (`![[Note#^block-id]]`) are always
+    /// removed.
+    pub fn block_ids(&mut self, style: BlockIdStyle) -> &mut Exporter<'a> {
+        self.block_id_style = style;
+        self
+    }
 
-        let dest = context.destination;
-        let mut outfile = create_file(&dest)?;
-        let write_frontmatter = match self.frontmatter_strategy {
-            FrontmatterStrategy::Always => true,
-            FrontmatterStrategy::Never => false,
-            FrontmatterStrategy::Auto => !context.frontmatter.is_empty(),
-        };
-        if write_frontmatter {
-            let mut frontmatter_str = frontmatter_to_str(context.frontmatter)
-                .context(FrontMatterEncodeError { path: src })?;
-            frontmatter_str.push('\n');
-            outfile
-                .write_all(frontmatter_str.as_bytes())
-                .context(WriteError { path: &dest })?;
-        }
-        outfile
-            .write_all(render_mdevents_to_mdtext(markdown_events).as_bytes())
-            .context(WriteError { path: &dest })?;
-        Ok(())
+    /// Leave out embeds of notes which have the frontmatter key `key` set to a truthy value (such
+    /// as `no_embed: true`).
+    ///
+    /// These notes are still exported as files of their own, but embeds of them are removed
+    /// entirely instead of inlining the note. Links to them are not affected. See also
+    /// [Exporter::embed_skip_comment].
+    pub fn embed_skip_key(&mut self, key: String) -> &mut Exporter<'a> {
+        self.embed_skip_key = Some(key);
+        self
     }
 
-    fn parse_obsidian_note<'b>(
-        &self,
-        path: &Path,
-        context: &Context,
-    ) -> Result<(Frontmatter, MarkdownEvents<'b>)> {
-        if context.note_depth() > NOTE_RECURSION_LIMIT {
-            return Err(ExportError::RecursionLimitExceeded {
-                file_tree: context.file_tree(),
-            });
-        }
-        let content = fs::read_to_string(&path).context(ReadError { path })?;
-        let (frontmatter, content) =
-            matter::matter(&content).unwrap_or(("".to_string(), content.to_string()));
-        let frontmatter =
-            frontmatter_from_str(&frontmatter).context(FrontMatterDecodeError { path })?;
+    /// Leave an HTML comment (`<!-- Embed of 'Note' omitted -->`) in place of embeds removed
+    /// because of [Exporter::embed_skip_key], rather than removing them without a trace.
+    pub fn embed_skip_comment(&mut self, comment: bool) -> &mut Exporter<'a> {
+        self.embed_skip_comment = comment;
+        self
+    }
 
-        let mut parser_options = Options::empty();
-        parser_options.insert(Options::ENABLE_TABLES);
-        parser_options.insert(Options::ENABLE_FOOTNOTES);
-        parser_options.insert(Options::ENABLE_STRIKETHROUGH);
-        parser_options.insert(Options::ENABLE_TASKLISTS);
+    /// Set whether links without an alias (`[[some-note]]`) use the `title` from the frontmatter of
+    /// the linked note as their text, instead of the name of the note.
+    ///
+    /// Links to notes without a `title` keep using the name of the note, and links with an alias
+    /// (`[[some-note|text]]`) always use the alias.
+    pub fn link_text_from_title(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.link_text_from_title = enabled;
+        self
+    }
 
-        let mut ref_parser = RefParser::new();
-        let mut events = vec![];
-        // Most of the time, a reference triggers 5 events: [ or ![, [, <text>, ], ]
-        let mut buffer = Vec::with_capacity(5);
+    /// Set the frontmatter keys which refer to attachments, such as `cover` or `banner`.
+    ///
+    /// Values of these keys may be wikilinks (`"[[hero.jpg]]"`) or paths relative to the note
+    /// (`images/hero.jpg`), or lists of these. References to files in the vault are rewritten to
+    /// the path of the exported file relative to the note, like links in the body of notes.
+    /// Values which don't refer to a file in the vault are left as-is.
+    pub fn frontmatter_attachment_keys(&mut self, keys: Vec<String>) -> &mut Exporter<'a> {
+        self.frontmatter_attachment_keys = keys;
+        self
+    }
 
-        for event in Parser::new_ext(&content, parser_options) {
-            if ref_parser.state == RefParserState::Resetting {
+    /// Rename frontmatter keys when writing notes, mapping the original name of each key to its
+    /// new name (such as `tags` to `keywords`).
+    ///
+    /// Values and the order of keys are preserved. When several keys of a note end up with the
+    /// same name, a warning is printed and only the first of these is kept.
+    pub fn rename_frontmatter_keys(
+        &mut self,
+        renames: HashMap<String, String>,
+    ) -> &mut Exporter<'a> {
+        self.frontmatter_key_renames = renames;
+        self
+    }
+
+    /// Set whether to read a "back matter" block at the end of notes.
+    ///
+    /// Back matter is a block of YAML between two `---` lines at the very end of a note, with the
+    /// opening line preceded by a blank line. When enabled, this block is removed from the note
+    /// and its keys are added to the frontmatter, with keys from the frontmatter taking precedence.
+    /// Blocks which don't contain a YAML mapping are left as-is, so thematic breaks (horizontal
+    /// rules) at the end of a note are not affected.
+    pub fn backmatter(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.backmatter = enabled;
+        self.clear_cache();
+        self
+    }
+
+    /// Add a hash of their contents to the filenames of attachments, so `image.png` is exported as
+    /// `image.a1b2c3.png`.
+    ///
+    /// The hash is shortened to `len` hexadecimal characters (at most 16). Links and embeds which
+    /// refer to attachments are updated to use the new names. This allows attachments to be cached
+    /// indefinitely when published on the web, as a changed attachment gets a new name.
+    pub fn hash_attachment_names(&mut self, len: usize) -> &mut Exporter<'a> {
+        self.hash_attachment_names = Some(len);
+        self.clear_cache();
+        self
+    }
+
+    /// Copy the attachments a note links to or embeds into a folder next to the note, instead of
+    /// exporting them to their location in the vault.
+    ///
+    /// `pattern` is the path of this folder relative to the directory of the note, in which
+    /// `{note}` is replaced by the filename of the note without its extension. With
+    /// `{note}/attachments`, the image embedded by `Trip.md` ends up at `Trip/attachments/`.
+    /// Links and embeds are rewritten to point to the co-located copy. Attachments referenced by
+    /// more than one note are handled according to `shared`, those not referenced by any note are
+    /// exported to their usual location. So are attachments which would end up at the same path as
+    /// a different attachment (such as `a/img.png` and `b/img.png` embedded by one note), which is
+    /// reported as a warning.
+    pub fn colocate_attachments(
+        &mut self,
+        pattern: String,
+        shared: SharedAttachments,
+    ) -> &mut Exporter<'a> {
+        self.colocate_attachments = Some((pattern, shared));
+        self.clear_cache();
+        self
+    }
+
+    /// Set whether to normalize Unicode text to NFC when resolving links and embeds.
+    ///
+    /// macOS stores filenames in decomposed form (NFD), so a link to `[[café]]` typed as a single
+    /// `é` doesn't match a `café.md` created on macOS once the vault is moved to another platform.
+    /// With this enabled, filenames and references are compared in composed form (NFC), and the
+    /// content of exported notes is normalized to NFC as well. Exported files keep their original
+    /// names.
+    pub fn normalize_unicode(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.normalize_unicode = enabled;
+        self.clear_cache();
+        self
+    }
+
+    /// Replace the characters in `disallowed` in the names of exported files with `replacement`,
+    /// or strip them when `replacement` is `None`.
+    ///
+    /// This is useful for characters which are fine in Obsidian but not on the platform or web
+    /// server the export ends up on, such as `:`, `?` or `|`. Links and embeds are rewritten to
+    /// use the sanitized names. A warning is emitted when sanitizing makes two files end up at the
+    /// same destination.
+    pub fn sanitize_filename(
+        &mut self,
+        disallowed: &str,
+        replacement: Option<char>,
+    ) -> &mut Exporter<'a> {
+        self.filename_sanitizer = Some((disallowed.to_string(), replacement));
+        self
+    }
+
+    /// Set whether a panic in a postprocessor only affects the note it was processing, instead of
+    /// aborting the export.
+    ///
+    /// When enabled, a postprocessor which panics causes the note it was called for to be skipped,
+    /// with a warning naming the note and the position of the postprocessor. Other notes continue
+    /// to be exported. The skipped note is reported in the [RunSummary] returned by
+    /// [Exporter::run_with_summary] with [SkipReason::PostprocessorPanicked].
+    ///
+    /// This also applies to [embed postprocessors][Exporter::add_embed_postprocessor], where a
+    /// panic leaves out the embed it was processing, and to
+    /// [attachment processors][Exporter::add_attachment_processor], where a panic skips the
+    /// attachment like a note.
+    ///
+    /// Panics are caught with [std::panic::catch_unwind], treating postprocessors as if they were
+    /// [UnwindSafe][std::panic::UnwindSafe]. A postprocessor which panics while modifying state
+    /// it shares between notes (such as one added through [Exporter::add_postprocessor_boxed])
+    /// may leave that state inconsistent for the notes processed after it. The default panic hook
+    /// still prints the panic message.
+    pub fn catch_postprocessor_panics(&mut self, catch: bool) -> &mut Exporter<'a> {
+        self.catch_postprocessor_panics = catch;
+        self
+    }
+
+    /// Add a slug, derived from the filename of the note, to the frontmatter of every note under
+    /// the frontmatter key `key`.
+    ///
+    /// Notes which already define a value for `key` keep their existing value. The slug is added
+    /// before postprocessors run, so they may inspect or change it.
+    pub fn inject_slug(&mut self, key: String) -> &mut Exporter<'a> {
+        self.slug_key = Some(key);
+        self
+    }
+
+    /// Read additional frontmatter for a note from a sidecar file next to it, named after the note
+    /// with `extension` appended (with `yaml`, the sidecar of `Note.md` is `Note.md.yaml`).
+    ///
+    /// Frontmatter from the sidecar is merged with the frontmatter of the note itself, with
+    /// conflicting keys resolved as set through [Exporter::sidecar_precedence]. Sidecar files are
+    /// not copied to the destination.
+    pub fn sidecar_frontmatter(&mut self, extension: String) -> &mut Exporter<'a> {
+        self.sidecar_extension = Some(extension.trim_start_matches('.').to_string());
+        self.clear_cache();
+        self
+    }
+
+    /// Set whether the frontmatter of a note or that of its sidecar file wins when both set the
+    /// same key. See [Exporter::sidecar_frontmatter].
+    pub fn sidecar_precedence(&mut self, precedence: SidecarPrecedence) -> &mut Exporter<'a> {
+        self.sidecar_precedence = precedence;
+        self.clear_cache();
+        self
+    }
+
+    /// Record where exported notes came from in their frontmatter.
+    ///
+    /// When `commit_key` is set, the hash of the git commit checked out in the vault is written to
+    /// this key. Vaults which aren't part of a git repository (or systems without git) get a
+    /// warning and the key is left out. When `exported_at_key` is set, the time the export started
+    /// is written to this key as an RFC 3339 timestamp in UTC, which is the same for all notes of
+    /// an export. Existing values of these keys are overwritten.
+    pub fn inject_provenance(
+        &mut self,
+        commit_key: Option<String>,
+        exported_at_key: Option<String>,
+    ) -> &mut Exporter<'a> {
+        self.provenance_keys = Some((commit_key, exported_at_key));
+        self
+    }
+
+    /// List the notes which link to a note in the note itself, as determined by `strategy`.
+    ///
+    /// Links are collected from all notes under `start_at` before any of them are exported, leaving
+    /// out notes which are skipped (such as notes excluded by [Exporter::include_tags]). Both
+    /// wikilinks and markdown links to other notes count, embeds don't. Each linking note is listed
+    /// once, with a link relative to the note it's listed in.
+    pub fn inject_backlinks(&mut self, strategy: BacklinkStrategy) -> &mut Exporter<'a> {
+        self.backlink_strategy = Some(strategy);
+        self
+    }
+
+    /// Only export notes which have at least one of the given tags.
+    ///
+    /// Tags may be given with or without a leading `#` and are matched case-insensitively against
+    /// both frontmatter and inline tags (see [Context::tags]). Nested tags match their parents,
+    /// so `project` also matches notes tagged with `project/alpha`.
+    ///
+    /// Notes are filtered before any postprocessors run. Files other than notes (such as images)
+    /// are not affected. When `tags` is empty (the default), all notes are exported.
+    pub fn include_tags(&mut self, tags: Vec<String>) -> &mut Exporter<'a> {
+        self.include_tags = tags;
+        self
+    }
+
+    /// Set the [`OutputFormat`] notes are written in.
+    ///
+    /// With [`OutputFormat::Html`], notes are written with an `.html` extension instead of `.md`
+    /// and links to other notes are updated to match. Frontmatter is not written to HTML output,
+    /// but it remains available to postprocessors and is used to determine a note's title.
+    pub fn output_format(&mut self, format: OutputFormat) -> &mut Exporter<'a> {
+        self.output_format = format;
+        self
+    }
+
+    /// Set the template used to wrap notes when exporting with [`OutputFormat::Html`].
+    ///
+    /// The placeholders `{{title}}` and `{{content}}` are replaced by the title of the note and
+    /// its rendered HTML content respectively. The title is taken from the `title` key in
+    /// frontmatter, falling back to the filename of the note when that isn't set. Frontmatter
+    /// values can be inserted with `{{frontmatter.key}}`. Placeholders without a value (such as a
+    /// key which a note doesn't have) are left empty, with a warning.
+    ///
+    /// When no template is set, a minimal HTML5 document is generated.
+    pub fn html_template(&mut self, template: String) -> &mut Exporter<'a> {
+        self.html_template = Some(template);
+        self.html_template_file = None;
+        self
+    }
+
+    /// Read the template used to wrap notes when exporting with [`OutputFormat::Html`] from the
+    /// file `path`. See [Exporter::html_template] for the placeholders which may be used.
+    ///
+    /// The file is read at the start of each export.
+    pub fn html_template_file(&mut self, path: PathBuf) -> &mut Exporter<'a> {
+        self.html_template_file = Some(path);
+        self
+    }
+
+    /// Extract text matching `pattern` from the body of notes into the frontmatter key `key`.
+    ///
+    /// This allows metadata which plugins store in special comments (for example `%%+draft+%%`)
+    /// to be turned into regular frontmatter. Matches are removed from the note body before it is
+    /// parsed. The value stored in frontmatter is the capture group named `value` if the pattern
+    /// defines one, otherwise the first capture group or, if there are no capture groups, the
+    /// entire match. When a note contains multiple matches, the values are stored as a list.
+    /// Extracted values replace any value already present under `key`.
+    ///
+    /// Patterns may be added multiple times to extract different keys. An error is returned when
+    /// `pattern` is not a valid regular expression.
+    pub fn metadata_comment_pattern(
+        &mut self,
+        pattern: &str,
+        key: &str,
+    ) -> Result<&mut Exporter<'a>> {
+        let regex = Regex::new(pattern).context(RegexError { pattern })?;
+        self.metadata_comment_patterns
+            .push((regex, key.to_string()));
+        self.clear_cache();
+        Ok(self)
+    }
+
+    /// Read export directives from comments starting with `prefix`, such as `%% export: false %%`
+    /// for a `prefix` of `export`.
+    ///
+    /// Directive comments are removed from the body of notes before they are parsed, except within
+    /// fenced code blocks and inline code, where they're left as-is. A note with
+    /// `%% export: false %%` is skipped (see [SkipReason::Directive]), while directives such as
+    /// `%% export.title: Some title %%` set the frontmatter key after the prefix (`title`) to the
+    /// given value, replacing any value already present. Values are read as YAML, so `false`,
+    /// `3` and `[a, b]` become a boolean, a number and a list respectively.
+    ///
+    /// Directives are applied through frontmatter, so a `prefix` key in the frontmatter of a note
+    /// (`export: false`) has the same effect. This key is not written to exported notes.
+    pub fn directive_comment(&mut self, prefix: String) -> &mut Exporter<'a> {
+        self.directive_comment = Some((directive_regex(&prefix), prefix));
+        self.clear_cache();
+        self
+    }
+
+    /// Set whether Obsidian Bases (`.base` files) are rendered as markdown tables.
+    ///
+    /// By default, `.base` files are copied as-is like any other attachment. When enabled, the
+    /// filters of the first table view of a Base are evaluated against the frontmatter of the
+    /// notes which are exported (so notes outside of [Exporter::start_at] or left out by
+    /// [Exporter::include_tags] are never listed) and the matching notes are written out as a
+    /// table with the columns configured for that view. These tables are written with the extension of notes (`.md` or
+    /// `.html`), and links to `.base` files are updated to match.
+    ///
+    /// Only a subset of the [Bases syntax] is supported: `and`, `or` and `not` groups,
+    /// comparisons of a property against a literal (such as `status == "done"`), `file.hasTag()`,
+    /// `file.inFolder()` and bare properties. Unsupported expressions never match. The `sort` and
+    /// `limit` settings of a view are honored. Only the frontmatter of notes is considered, so
+    /// `file.hasTag()` doesn't match inline tags.
+    ///
+    /// [Bases syntax]: https://help.obsidian.md/bases/syntax
+    pub fn render_bases(&mut self, render: bool) -> &mut Exporter<'a> {
+        self.render_bases = render;
+        self.clear_cache();
+        self
+    }
+
+    /// Validate the frontmatter of each note against the [JSON schema](https://json-schema.org/) in
+    /// the file `path`.
+    ///
+    /// Frontmatter is validated as it would be written out, after postprocessors have modified it.
+    /// Notes which don't conform to the schema are reported with a warning listing each
+    /// violation, or fail the export with [ExportError::FrontmatterSchemaViolation] when `strict`
+    /// is true.
+    ///
+    /// Schemas are validated with the [jsonschema](https://docs.rs/jsonschema) crate, which
+    /// supports all drafts of JSON Schema from draft 4 onwards, using the draft named by `$schema`
+    /// (or the latest one if not specified). References (`$ref`) to other schemas may only point
+    /// within the schema itself. Schemas which aren't valid fail the export with
+    /// [ExportError::InvalidSchema].
+    pub fn frontmatter_schema(&mut self, path: PathBuf, strict: bool) -> &mut Exporter<'a> {
+        self.frontmatter_schema = Some((path, strict));
+        self
+    }
+
+    /// Only export files in directories which contain a file named `filename` (such as
+    /// `.publish`), or which are nested under such a directory.
+    ///
+    /// Files outside of these directories are left out of the vault entirely, as if they were
+    /// ignored, so links to them are reported as links to missing notes. The marker files
+    /// themselves aren't exported.
+    pub fn publish_marker(&mut self, filename: String) -> &mut Exporter<'a> {
+        self.publish_marker = Some(filename);
+        self.clear_cache();
+        self
+    }
+
+    /// Set whether to inline embedded SVG images (`![[image.svg]]`) as raw `<svg>` markup, instead
+    /// of linking to them.
+    ///
+    /// This makes exported notes self-contained. As the SVG images are part of the notes they're
+    /// embedded in, `.svg` files which are only ever embedded this way are not copied to the
+    /// destination when this is enabled. SVG images which are also linked to, or used by markdown
+    /// images (`![alt](image.svg)`), are still copied.
+    pub fn inline_svg(&mut self, inline: bool) -> &mut Exporter<'a> {
+        self.inline_svg = inline;
+        self
+    }
+
+    /// Set whether to strip `<script>` elements and `on*` event handler attributes from SVG
+    /// images inlined through [Exporter::inline_svg].
+    pub fn sanitize_svg(&mut self, sanitize: bool) -> &mut Exporter<'a> {
+        self.sanitize_svg = sanitize;
+        self
+    }
+
+    /// Turn embeds of images which exist in several sizes into `<img>` tags with a `srcset`.
+    ///
+    /// `suffixes` maps the suffix of the filename of each variant to its descriptor in the
+    /// `srcset`. With `@2x` mapped to `2x`, `![[photo.jpg]]` lists both `photo.jpg` (as `1x`) and
+    /// `photo@2x.jpg` (as `2x`) when the latter exists next to it. Embeds of images without any
+    /// variants are exported as regular images.
+    pub fn responsive_images(&mut self, suffixes: HashMap<String, String>) -> &mut Exporter<'a> {
+        self.responsive_images = suffixes;
+        self.clear_cache();
+        self
+    }
+
+    /// Concatenate all exported notes into the single file `path`, instead of writing each note to
+    /// a file of its own.
+    ///
+    /// `path` is relative to the destination. Notes are written in the order set through
+    /// [Exporter::note_order], each preceded by an anchor and a heading with the name of the note and separated by horizontal
+    /// rules. Links between notes which are part of the concatenated file become links to these
+    /// anchors (or to the linked section, for links to a section of a note), while other relative
+    /// links are rewritten to be relative to `path`. Attachments are copied as usual. Links to notes
+    /// which aren't part of the concatenated file (because they're outside of
+    /// [Exporter::start_at] or were skipped) are reported with a warning, as these notes aren't
+    /// exported.
+    ///
+    /// Frontmatter of the individual notes is not written to the concatenated file.
+    pub fn concatenate_to(&mut self, path: PathBuf) -> &mut Exporter<'a> {
+        self.concatenate_to = Some(path);
+        self
+    }
+
+    /// Concatenate all exported notes into the single file `path` (relative to the destination), in
+    /// addition to writing each note to a file of its own.
+    ///
+    /// This is meant for sites which offer both a browsable version and a single-page download.
+    /// Notes are only processed once: the concatenated file is assembled from the same rendered
+    /// output as the individual files, in the given `order`. Links in the individual files point
+    /// to other files as usual, while links in the concatenated file are rewritten as described
+    /// for [Exporter::concatenate_to]: links between notes become links to anchors within the
+    /// file and other relative links are made relative to `path`.
+    ///
+    /// This has no effect when [Exporter::concatenate_to] is set.
+    pub fn also_concatenate(&mut self, path: PathBuf, order: NoteOrder) -> &mut Exporter<'a> {
+        self.also_concatenate = Some((path, order));
+        self
+    }
+
+    /// Give the headings of each note in a concatenated file (see [Exporter::concatenate_to] and
+    /// [Exporter::also_concatenate]) an anchor prefixed with the anchor of the note, such as
+    /// `note-a-overview` for the heading `Overview` in `Note A.md`.
+    ///
+    /// Without this, sections with the same name in different notes can't be told apart once the
+    /// notes are concatenated. Links to a section of a note are rewritten to point to these
+    /// anchors, including links within a note (`[[#Overview]]`).
+    pub fn prefix_concatenated_anchors(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.prefix_concatenated_anchors = enabled;
+        self
+    }
+
+    /// Write an index note to every directory of the destination, listing the notes and
+    /// subdirectories within it as links.
+    ///
+    /// Indexes are written after all notes have been exported, with the filename and titles set
+    /// by `config`. Subdirectories are listed first, followed by notes sorted by title. Only notes
+    /// which were exported are listed, so notes which were ignored or skipped are left out.
+    /// Directories which already have a note with the index filename are left alone.
+    pub fn generate_directory_indexes(&mut self, config: IndexConfig) -> &mut Exporter<'a> {
+        self.directory_indexes = Some(config);
+        self
+    }
+
+    /// Set the [`NoteOrder`] used by all output which combines multiple notes, such as
+    /// [Exporter::concatenate_to].
+    pub fn note_order(&mut self, order: NoteOrder) -> &mut Exporter<'a> {
+        self.note_order = order;
+        self
+    }
+
+    /// Compare exported notes against those of a previous export in the directory `path`.
+    ///
+    /// When set, each exported note is compared to the note at the same location under `path`
+    /// and classified by [ChangeKind], which is reported through [Exporter::changes]. Notes which
+    /// were removed since the previous export are not reported.
+    ///
+    /// `path` may be the destination itself, in which case notes which are unchanged are not
+    /// rewritten.
+    pub fn previous_export(&mut self, path: PathBuf) -> &mut Exporter<'a> {
+        self.previous_export = Some(path);
+        self
+    }
+
+    /// Return the changes to notes found by the last call to [Exporter::run], sorted by path.
+    ///
+    /// This is empty unless a previous export is set (see [Exporter::previous_export]).
+    pub fn changes(&self) -> Vec<NoteChange> {
+        let mut changes = self.changes.lock().expect("changes lock poisoned").clone();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        changes
+    }
+
+    /// Write a redirect map to the file `path`, relative to the destination.
+    ///
+    /// The redirect map is a JSON object mapping the name of each exported note, as well as each of
+    /// the `aliases` in its frontmatter, to the path of the exported note relative to the
+    /// destination (after any changes made by postprocessors). This can be used to generate
+    /// redirects from old Obsidian names to the final URLs of notes. When several notes share a
+    /// name, it maps to the note whose path sorts first.
+    pub fn redirect_map_output(&mut self, path: PathBuf) -> &mut Exporter<'a> {
+        self.redirect_map_output = Some(path);
+        self
+    }
+
+    /// Write a `_redirects` file, as used by Netlify and Cloudflare Pages, to the file `path`,
+    /// relative to the destination.
+    ///
+    /// This contains the same redirects as the [redirect map][Exporter::redirect_map_output], with
+    /// one `/old-name /final-url 301` line per name.
+    pub fn netlify_redirects_output(&mut self, path: PathBuf) -> &mut Exporter<'a> {
+        self.netlify_redirects_output = Some(path);
+        self
+    }
+
+    /// Write a JSON search index of the exported notes to the file `path`, relative to the
+    /// destination.
+    ///
+    /// The index is an array with a record for each exported note, holding the URL of the note
+    /// (its path relative to the destination), its title (the `title` in its frontmatter or else
+    /// its filename), its [tags][Context::tags] and its content as plain text. The layout of these
+    /// records is determined by `format`. Notes which are skipped aren't included.
+    pub fn search_index_output(
+        &mut self,
+        path: PathBuf,
+        format: SearchIndexFormat,
+    ) -> &mut Exporter<'a> {
+        self.search_index_output = Some((path, format));
+        self
+    }
+
+    /// Set whether to write a JSON file with metadata next to each exported note, such as
+    /// `Note.md.json` for `Note.md`.
+    ///
+    /// This holds the URL of the note (its path relative to the destination), its
+    /// [tags][Context::tags], its word count and the links and embeds it contains. Links are
+    /// listed as they appear in the exported note, while embeds list the notes and attachments
+    /// embedded directly in the note (relative to the note). This is intended for custom site
+    /// builders which need to know how notes relate to each other.
+    pub fn metadata_sidecar(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.metadata_sidecar = enabled;
+        self
+    }
+
+    /// Set the number of threads notes are exported with, or 0 (the default) to use one thread per
+    /// logical CPU.
+    ///
+    /// Notes are parsed, processed and written in parallel, so postprocessors may run on several
+    /// threads at once. When an export fails, the error reported is that of the first file (in
+    /// the order files are walked) which failed, regardless of the number of threads.
+    pub fn threads(&mut self, threads: usize) -> &mut Exporter<'a> {
+        self.threads = threads;
+        self
+    }
+
+    /// Set whether to collect a timing breakdown of the stages of the export.
+    ///
+    /// When enabled, the time spent walking the vault, parsing notes, resolving embeds, running
+    /// each postprocessor, serializing and writing notes is accumulated during [Exporter::run]
+    /// and made available through [Exporter::profile_report]. Postprocessors are identified by
+    /// their position in the chain, such as `postprocessor #1` for the first one added.
+    pub fn profile(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.profile = match enabled {
+            true => Some(Arc::new(Profile::new())),
+            false => None,
+        };
+        self
+    }
+
+    /// Return the [Profile] collected by the last call to [Exporter::run], if profiling is
+    /// enabled (see [Exporter::profile]).
+    pub fn profile_report(&self) -> Option<&Profile> {
+        self.profile.as_deref()
+    }
+
+    /// Append a function to the chain of [postprocessors][Postprocessor] to run on exported Obsidian Markdown notes.
+    pub fn add_postprocessor(&mut self, processor: &'a Postprocessor) -> &mut Exporter<'a> {
+        self.postprocessors
+            .push(PostprocessorRef::Borrowed(processor));
+        self
+    }
+
+    /// Append a function which owns its state to the chain of [postprocessors][Postprocessor] to
+    /// run on exported Obsidian Markdown notes.
+    ///
+    /// This is an alternative to [Exporter::add_postprocessor] for `move` closures which capture
+    /// and mutate owned state, such as a counter. Both kinds of postprocessors may be mixed, in
+    /// which case they run in the order they were added.
+    ///
+    /// # Thread safety
+    ///
+    /// Notes are exported in parallel, but a boxed postprocessor is never called concurrently:
+    /// calls are serialized through a lock, so it may take `&mut self` without any synchronization
+    /// of its own. This does mean that a slow boxed postprocessor limits the parallelism of the
+    /// export. The order in which notes are passed to it is unspecified.
+    ///
+    /// State is kept across multiple calls to [Exporter::run], and is shared between clones of
+    /// the exporter. To read the state after the export has finished, capture it through an
+    /// [Arc].
+    ///
+    /// ```
+    /// # use obsidian_export::{Exporter, PostprocessorResult};
+    /// # use std::path::PathBuf;
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// # use std::sync::Arc;
+    /// # use tempfile::TempDir;
+    /// # let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    /// # let source = PathBuf::from("tests/testdata/input/postprocessors");
+    /// # let destination = tmp_dir.path().to_path_buf();
+    /// let mut exporter = Exporter::new(source, destination);
+    /// let notes = Arc::new(AtomicUsize::new(0));
+    /// let counter = notes.clone();
+    /// exporter.add_postprocessor_boxed(Box::new(move |context, events| {
+    ///     counter.fetch_add(1, Ordering::Relaxed);
+    ///     (context, events, PostprocessorResult::Continue)
+    /// }));
+    /// exporter.run().unwrap();
+    /// println!("Exported {} notes", notes.load(Ordering::Relaxed));
+    /// ```
+    pub fn add_postprocessor_boxed(
+        &mut self,
+        processor: Box<BoxedPostprocessor>,
+    ) -> &mut Exporter<'a> {
+        self.postprocessors
+            .push(PostprocessorRef::Boxed(Arc::new(Mutex::new(processor))));
+        self
+    }
+
+    /// Append a postprocessor implemented by the WebAssembly module at `path` (in either the
+    /// binary or the text format) to the chain of [postprocessors][Postprocessor].
+    ///
+    /// This allows postprocessors to be supplied without recompiling this crate. It requires the
+    /// `wasm` feature.
+    ///
+    /// The module must export its `memory`, an `alloc(len: i32) -> i32` function which returns a
+    /// pointer to `len` bytes of memory, and a `process(ptr: i32, len: i32) -> i64` function. For
+    /// each note, `alloc` is called for the input, which is written to the returned memory before
+    /// calling `process` with its location. The input is a JSON object with the `frontmatter`
+    /// (an object) and the `markdown` of the note (as a string). `process` returns the location of
+    /// its output as the pointer in the upper and the length in the lower 32 bits. The output is a
+    /// JSON object with the new `markdown` and optionally the new `frontmatter`, a `result`
+    /// (`continue`, `stop_here`, `stop_and_skip_note` or `abort`, see [PostprocessorResult]) and
+    /// the `message` to abort with.
+    ///
+    /// Each note is processed by a new instance of the module. Failures of the module (such as
+    /// traps or invalid output) abort the export with [ExportError::PostprocessorAborted].
+    #[cfg(feature = "wasm")]
+    pub fn add_wasm_postprocessor(&mut self, path: PathBuf) -> Result<&mut Exporter<'a>> {
+        let processor = wasm::WasmPostprocessor::load(&path)
+            .map_err(|message| ExportError::WasmLoadError { path, message })?;
+        self.postprocessors
+            .push(PostprocessorRef::Wasm(Arc::new(processor)));
+        Ok(self)
+    }
+
+    /// Set a function which is called with the [Progress] of the export each time a file has been
+    /// processed.
+    ///
+    /// Before any files are processed, the callback is called once for both notes and attachments
+    /// with the total number of files of that kind. Exports of a single file or into a single file
+    /// (see [Exporter::concatenate_to]) don't report progress.
+    pub fn on_progress(&mut self, callback: &'a ProgressCallback) -> &mut Exporter<'a> {
+        self.progress_callback = Some(callback);
+        self
+    }
+
+    /// Write a log of each run to the file at `path`, replacing the file if it already exists.
+    ///
+    /// Each warning (and at [LogLevel::Info], each note exported, attachment copied and file
+    /// skipped) is written to a line of its own. Warnings are still printed to stderr as well,
+    /// unless this is disabled through [Exporter::warnings_to_stderr].
+    pub fn log_file(&mut self, path: PathBuf, level: LogLevel) -> &mut Exporter<'a> {
+        self.log_file = Some((path, level));
+        self
+    }
+
+    /// Set whether warnings are printed to stderr (the default).
+    pub fn warnings_to_stderr(&mut self, enabled: bool) -> &mut Exporter<'a> {
+        self.warnings_to_stderr = enabled;
+        self
+    }
+
+    /// Set the [`WarningFormat`] used to print warnings to stderr.
+    ///
+    /// This doesn't affect the log file set through [Exporter::log_file].
+    pub fn warning_format(&mut self, format: WarningFormat) -> &mut Exporter<'a> {
+        self.warning_format = format;
+        self
+    }
+
+    /// Set a function which is called with each warning (formatted according to the
+    /// [`WarningFormat`]) instead of printing it to stderr.
+    ///
+    /// This allows warnings to be printed without interfering with other output, such as a
+    /// progress bar. It isn't called when warnings are disabled through
+    /// [Exporter::warnings_to_stderr].
+    pub fn on_warning(&mut self, callback: &'a WarningCallback) -> &mut Exporter<'a> {
+        self.warning_callback = Some(callback);
+        self
+    }
+
+    /// Set a function which is called with a [LinkEvent] for every wikilink and embed, describing
+    /// the file it resolved to (if any).
+    ///
+    /// This only observes link resolution and doesn't affect the exported notes. References in
+    /// embedded notes are reported each time the note is embedded.
+    pub fn on_link(&mut self, callback: &'a LinkCallback) -> &mut Exporter<'a> {
+        self.link_callback = Some(callback);
+        self
+    }
+
+    /// Write exported files to the writers returned by `factory` instead of to the filesystem.
+    ///
+    /// The factory is called with the path of each exported file relative to the destination
+    /// (notes, attachments, as well as any indexes or other files generated by the export), and
+    /// may be called from several threads at once. Directories aren't created and the destination
+    /// doesn't need to exist. The log file set through [Exporter::log_file] is still written to
+    /// disk.
+    pub fn set_writer_factory(&mut self, factory: &'a WriterFactory) -> &mut Exporter<'a> {
+        self.writer_factory = Some(factory);
+        self
+    }
+
+    /// Append a function to the chain of [postprocessors][Postprocessor] for embeds.
+    pub fn add_embed_postprocessor(&mut self, processor: &'a Postprocessor) -> &mut Exporter<'a> {
+        self.embed_postprocessors.push(processor);
+        self
+    }
+
+    /// Append a function to the chain of [attachment processors][AttachmentProcessor] to run on
+    /// files which aren't notes, such as images.
+    pub fn add_attachment_processor(
+        &mut self,
+        processor: &'a AttachmentProcessor,
+    ) -> &mut Exporter<'a> {
+        self.attachment_processors.push(processor);
+        self
+    }
+
+    /// Check the configuration of this exporter for problems that would prevent a successful
+    /// export.
+    ///
+    /// Unlike [Exporter::run], which stops at the first error it encounters, this reports all
+    /// problems that were found at once. Calling this is optional, but it allows misconfigurations
+    /// to be caught before starting a potentially long-running export.
+    pub fn validate(&self) -> std::result::Result<(), Vec<ConfigError>> {
+        let mut errors = vec![];
+
+        if !self.root.exists() {
+            errors.push(ConfigError::SourceNotFound {
+                path: self.root.clone(),
+            });
+        }
+        if !self.start_at.exists() {
+            errors.push(ConfigError::StartAtNotFound {
+                path: self.start_at.clone(),
+            });
+        } else if !self.start_at.starts_with(&self.root) {
+            errors.push(ConfigError::StartAtOutsideSource {
+                path: self.start_at.clone(),
+                root: self.root.clone(),
+            });
+        }
+
+        // When exporting a single file, destination may name a file that doesn't exist yet, in
+        // which case its parent directory is what must exist and be writable.
+        let destination =
+            match self.destination.is_dir() || !(self.root.is_file() || self.start_at.is_file()) {
+                true => self.destination.as_path(),
+                false => self.destination.parent().unwrap_or(&self.destination),
+            };
+        match fs::metadata(destination) {
+            _ if self.writer_factory.is_some() => (),
+            Err(_) => errors.push(ConfigError::DestinationNotFound {
+                path: destination.to_path_buf(),
+            }),
+            Ok(metadata) if !is_writable(destination, metadata.is_dir()) => {
+                errors.push(ConfigError::DestinationNotWritable {
+                    path: destination.to_path_buf(),
+                })
+            }
+            Ok(_) => {}
+        }
+        if let (Ok(root), Ok(destination)) = (self.root.canonicalize(), destination.canonicalize())
+        {
+            if self.destination.canonicalize().ok().as_ref() == Some(&root) {
+                errors.push(ConfigError::DestinationIsSource { path: destination });
+            } else if destination.starts_with(&root) {
+                errors.push(ConfigError::DestinationInsideSource {
+                    path: destination,
+                    root,
+                });
+            }
+        }
+
+        let conflicting_keys = [
+            (
+                self.publish_marker.as_deref(),
+                Some(self.walk_options.ignore_filename),
+                "Exporter::publish_marker",
+                "WalkOptions::ignore_filename",
+            ),
+            (
+                self.directive_comment
+                    .as_ref()
+                    .map(|(_, prefix)| prefix.as_str()),
+                self.embed_skip_key.as_deref(),
+                "Exporter::directive_comment",
+                "Exporter::embed_skip_key",
+            ),
+        ];
+        for (include_key, exclude_key, include, exclude) in conflicting_keys {
+            if let (Some(key), true) = (include_key, include_key == exclude_key) {
+                errors.push(ConfigError::ConflictingKeys {
+                    key: key.to_string(),
+                    include,
+                    exclude,
+                });
+            }
+        }
+        if self.root.is_dir() {
+            errors.extend(
+                ignore_pattern_errors(&self.root, self.walk_options.clone())
+                    .into_iter()
+                    .map(|source| ConfigError::InvalidGlob { source }),
+            );
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// Export notes using the settings configured on this exporter.
+    pub fn run(&mut self) -> Result<()> {
+        self.run_with_summary().map(|_| ())
+    }
+
+    /// Export notes using the settings configured on this exporter, returning a [RunSummary] with
+    /// the number of notes exported, the files which were skipped and the number of warnings.
+    pub fn run_with_summary(&mut self) -> Result<RunSummary> {
+        let start = Instant::now();
+        self.summary = Arc::new(Mutex::new(RunSummary::default()));
+        self.in_thread_pool(Self::export)?;
+        let mut summary = self.summary.lock().expect("summary lock poisoned").clone();
+        summary.duration = start.elapsed();
+        Ok(summary)
+    }
+
+    // Run `func` on a pool of as many threads as set through Exporter::threads, or on the global
+    // pool of rayon when this isn't set.
+    fn in_thread_pool<T: Send>(
+        &mut self,
+        func: impl FnOnce(&mut Self) -> Result<T> + Send,
+    ) -> Result<T> {
+        if self.threads == 0 {
+            return func(self);
+        }
+        let threads = self.threads;
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .context(ThreadPoolError { threads })?
+            .install(|| func(self))
+    }
+
+    // Check the source and set up the state of a new run: open the log file, load the files
+    // used during the run and index the vault.
+    fn prepare_run(&mut self) -> Result<()> {
+        if !self.root.exists() {
+            return Err(ExportError::PathDoesNotExist {
+                path: self.root.clone(),
+            });
+        }
+
+        self.log = Arc::new(Mutex::new(match &self.log_file {
+            Some((path, _)) => Some(create_file(path)?),
+            None => None,
+        }));
+        if self.profile.is_some() {
+            self.profile = Some(Arc::new(Profile::new()));
+        }
+        self.changes = Arc::new(Mutex::new(vec![]));
+        self.redirects = Arc::new(Mutex::new(vec![]));
+        self.search_index = Arc::new(Mutex::new(vec![]));
+        self.rendered_notes = Arc::new(Mutex::new(vec![]));
+        self.pending_attachments = Arc::new(Mutex::new(HashMap::new()));
+        self.issues = Arc::new(Mutex::new(vec![]));
+        self.indexed_notes = Arc::new(Mutex::new(vec![]));
+        self.encoding_warnings = Arc::new(Mutex::new(HashSet::new()));
+        self.note_sources = Arc::new(Mutex::new(HashMap::new()));
+        if let Some(path) = &self.html_template_file {
+            self.html_template = Some(fs::read_to_string(path).context(ReadError { path })?);
+        }
+        self.schema = match &self.frontmatter_schema {
+            Some((path, _)) => Some(Self::load_frontmatter_schema(path)?),
+            None => None,
+        };
+        self.provenance = match &self.provenance_keys {
+            Some((commit_key, exported_at_key)) => {
+                self.provenance_frontmatter(commit_key, exported_at_key)
+            }
+            None => Frontmatter::new(),
+        };
+        let mut walk_options = self.walk_options.clone();
+        if let Ok(start_at) = self.start_at.strip_prefix(&self.root) {
+            if self.start_at.is_dir() {
+                walk_options.max_depth = walk_options
+                    .max_depth
+                    .map(|depth| depth + start_at.components().count());
+            }
+        }
+        if !self.reuse_index || self.vault_contents.is_none() {
+            self.build_index(walk_options)?;
+        }
+        self.exported_notes = match self.render_bases || self.backlink_strategy.is_some() {
+            true => self.timed("exported notes", || self.build_exported_notes()),
+            false => HashSet::new(),
+        };
+        self.inlined_svgs = match self.inline_svg {
+            true => self.timed("references", || self.build_inlined_svgs()),
+            false => HashSet::new(),
+        };
+        self.backlinks = match self.backlink_strategy {
+            Some(_) => self.timed("backlinks", || self.build_backlinks()),
+            None => HashMap::new(),
+        };
+        self.attachment_references = match self.colocate_attachments {
+            Some(_) => self.timed("references", || self.build_attachment_references()),
+            None => HashMap::new(),
+        };
+        Ok(())
+    }
+
+    fn export(&mut self) -> Result<()> {
+        self.prepare_run()?;
+
+        // When a single file is specified, just need to export that specific file instead of
+        // iterating over all discovered files. This also allows us to accept destination as either
+        // a file or a directory name.
+        if self.root.is_file() || self.start_at.is_file() {
+            let source_filename = self
+                .start_at
+                .file_name()
+                .expect("File without a filename? How is that possible?")
+                .to_string_lossy();
+
+            let destination = match self.destination.is_dir() {
+                true => self.output_path(
+                    &self.start_at,
+                    &self.destination.join(String::from(source_filename)),
+                ),
+                false => {
+                    let parent = self.destination.parent().unwrap_or(&self.destination);
+                    // Avoid recursively creating self.destination through the call to
+                    // export_note when the parent directory doesn't exist.
+                    if !parent.exists() && self.writer_factory.is_none() {
+                        return Err(ExportError::PathDoesNotExist {
+                            path: parent.to_path_buf(),
+                        });
+                    }
+                    self.destination.clone()
+                }
+            };
+            self.export_note(&self.start_at, &destination)?;
+            return self.copy_pending_attachments(None);
+        }
+
+        if !self.destination.exists() && self.writer_factory.is_none() {
+            return Err(ExportError::PathDoesNotExist {
+                path: self.destination.clone(),
+            });
+        }
+        if self.filename_sanitizer.is_some() {
+            self.warn_filename_collisions();
+        }
+        if let Some(path) = &self.concatenate_to {
+            return self.export_concatenated(path);
+        }
+        let files = self.exported_files();
+        let phase = |file: &Path| match is_markdown_file(file) && !self.is_verbatim_file(file) {
+            true => ProgressPhase::Notes,
+            false => ProgressPhase::Attachments,
+        };
+        let progress = self.progress_callback.map(|callback| {
+            let notes = files
+                .iter()
+                .filter(|file| phase(file) == ProgressPhase::Notes)
+                .count();
+            ProgressTracker::new(callback, notes, files.len() - notes)
+        });
+        first_error(files.par_iter().map(|file| {
+            let relative_path = file
+                .strip_prefix(self.output_root())
+                .expect("file should always be nested under root")
+                .to_path_buf();
+            let destination = self.output_path(file, &self.destination.join(&relative_path));
+            self.export_note(file, &destination)?;
+            // Attachments which were queued are reported once they've been copied.
+            if let Some(progress) = &progress {
+                if phase(file) == ProgressPhase::Notes || !self.is_pending_attachment(file) {
+                    progress.advance(phase(file));
+                }
+            }
+            Ok(())
+        }))?;
+        if let (Some(progress), Some(_)) = (&progress, &self.export_files) {
+            progress.add_attachments(self.pending_dependencies(&files));
+        }
+        self.copy_pending_attachments(progress.as_ref())?;
+        if let Some(path) = &self.redirect_map_output {
+            self.write_redirect_map(path)?;
+        }
+        if let Some(path) = &self.netlify_redirects_output {
+            self.write_netlify_redirects(path)?;
+        }
+        if let Some((path, format)) = &self.search_index_output {
+            self.write_search_index(path, *format)?;
+        }
+        if let Some((path, order)) = &self.also_concatenate {
+            self.export_also_concatenated(path, order)?;
+        }
+        if let Some(config) = &self.directory_indexes {
+            self.write_directory_indexes(config)?;
+        }
+        Ok(())
+    }
+
+    // Return the directory which corresponds to the destination, which the paths of exported files
+    // are relative to (see Exporter::start_at_as_root).
+    fn output_root(&self) -> &Path {
+        match self.start_at_as_root {
+            true => &self.start_at,
+            false => &self.root,
+        }
+    }
+
+    // Return the files under start_at which should be exported, which are limited to those set
+    // through Exporter::export_files when given.
+    fn exported_files(&self) -> Vec<PathBuf> {
+        let files = self
+            .vault_contents
+            .as_ref()
+            .unwrap()
+            .iter()
+            .filter(|file| file.starts_with(&self.start_at));
+        let listed = match &self.export_files {
+            Some(listed) => listed,
+            None => return files.cloned().collect(),
+        };
+        let listed: HashSet<PathBuf> = listed
+            .iter()
+            .map(|path| match path.starts_with(&self.root) {
+                true => normalize_path(path),
+                false => normalize_path(&self.root.join(path)),
+            })
+            .collect();
+        let files: Vec<PathBuf> = files
+            .filter(|file| listed.contains(&normalize_path(file)))
+            .cloned()
+            .collect();
+        let found: HashSet<PathBuf> = files.iter().map(|file| normalize_path(file)).collect();
+        let mut missing: Vec<&PathBuf> = listed.difference(&found).collect();
+        missing.sort();
+        for path in missing {
+            // TODO: Extract into configurable function.
+            self.warn(
+                Warning::new("File to export is not part of the vault")
+                    .detail("Path", path.display()),
+            );
+        }
+        files
+    }
+
+    // Walk the vault and index the frontmatter of its notes. With reuse_index, these are kept
+    // across runs until they're invalidated through clear_cache.
+    fn build_index(&mut self, walk_options: WalkOptions) -> Result<()> {
+        let mut files = self.timed("walk", || vault_contents(self.root.as_path(), walk_options))?;
+        if let Some(marker) = &self.publish_marker {
+            files = self.published_files(files, marker);
+        }
+        self.vault_contents = Some(files);
+        self.normalized_vault_contents = match self.normalize_unicode {
+            true => Some(
+                self.vault_contents
+                    .as_ref()
+                    .unwrap()
+                    .iter()
+                    .map(|path| PathBuf::from(to_nfc(&path.to_string_lossy()).as_ref()))
+                    .collect(),
+            ),
+            false => None,
+        };
+        self.path_index = self.build_path_index();
+        self.name_index = self.build_name_index();
+        self.frontmatter_index = Some(Arc::new(
+            self.timed("index", || self.build_frontmatter_index()),
+        ));
+        self.alias_index = self.build_alias_index();
+        self.attachment_hashes = match self.hash_attachment_names {
+            Some(len) => match self.timed("hash", || self.hash_attachments(len)) {
+                Ok(hashes) => hashes,
+                Err(err) => {
+                    self.clear_cache();
+                    return Err(err);
+                }
+            },
+            None => HashMap::new(),
+        };
+        Ok(())
+    }
+
+    // Keep only the files in (or nested under) a directory which contains the file `marker`.
+    fn published_files(&self, files: Vec<PathBuf>, marker: &str) -> Vec<PathBuf> {
+        let root_dir = match self.root.is_file() {
+            true => self.root.parent().unwrap_or(&self.root),
+            false => &self.root,
+        };
+        let mut published_dirs: HashMap<PathBuf, bool> = HashMap::new();
+        let mut is_published = |dir: &Path| {
+            *published_dirs
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| dir.join(marker).is_file())
+        };
+        files
+            .into_iter()
+            .filter(|file| file.file_name().is_some_and(|name| name != marker))
+            .filter(|file| {
+                file.ancestors()
+                    .skip(1)
+                    .take_while(|dir| dir.starts_with(root_dir))
+                    .any(&mut is_published)
+            })
+            .collect()
+    }
+
+    // Map the aliases of every note in the vault (lowercased, as references are matched
+    // case-insensitively) to the path of the note. When notes share an alias, the first one in
+    // path order wins.
+    fn build_alias_index(&self) -> HashMap<String, PathBuf> {
+        let frontmatter_index = self.frontmatter_index.as_ref().unwrap();
+        let mut paths: Vec<&PathBuf> = frontmatter_index.keys().collect();
+        paths.sort();
+        let mut alias_index = HashMap::new();
+        for path in paths {
+            for alias in note_aliases(&frontmatter_index[path]) {
+                alias_index
+                    .entry(self.alias_key(&alias))
+                    .or_insert_with(|| path.clone());
+            }
+        }
+        alias_index
+    }
+
+    fn alias_key(&self, alias: &str) -> String {
+        match self.normalize_unicode {
+            true => to_nfc(alias.trim()).to_lowercase(),
+            false => alias.trim().to_lowercase(),
+        }
+    }
+
+    // Read the frontmatter of every note in the vault up-front, so that features which need to know
+    // about other notes than the one currently being exported can consult it.
+    //
+    // Notes which fail to read or decode are left out of the index. These same errors will occur
+    // and be reported when the note itself is exported.
+    fn build_frontmatter_index(&self) -> HashMap<PathBuf, Frontmatter> {
+        self.vault_contents
+            .as_ref()
+            .unwrap()
+            .par_iter()
+            .filter(|path| is_markdown_file(path) && !self.is_verbatim_file(path))
+            .filter_map(|path| {
+                let (frontmatter, _) = self.read_note(path, false).ok()??;
+                Some((path.to_path_buf(), frontmatter))
+            })
+            .collect()
+    }
+
+    // Find the SVG images which are referenced by notes under start_at, but only ever through
+    // embeds. These are inlined into the notes by Exporter::inline_svg, so they don't need to be
+    // copied.
+    fn build_inlined_svgs(&self) -> HashSet<PathBuf> {
+        let references: Vec<(PathBuf, ReferenceKind)> = self
+            .vault_contents
+            .as_ref()
+            .unwrap()
+            .par_iter()
+            .filter(|path| path.starts_with(&self.start_at))
+            .filter(|path| is_markdown_file(path) && !self.is_verbatim_file(path))
+            .flat_map(|path| self.note_references(path))
+            .filter(|(target, _)| is_svg_file(target))
+            .collect();
+
+        let mut inlined = HashSet::new();
+        let mut referenced_otherwise = HashSet::new();
+        for (target, kind) in references {
+            match kind {
+                ReferenceKind::Embed => inlined.insert(target),
+                _ => referenced_otherwise.insert(target),
+            };
+        }
+        &inlined - &referenced_otherwise
+    }
+
+    // Determine which notes under start_at will be exported, as far as this is known before
+    // running postprocessors (see Exporter::is_exported_note). Notes which fail to read are left
+    // out, the error is reported when the note itself is exported.
+    fn build_exported_notes(&self) -> HashSet<PathBuf> {
+        self.vault_contents
+            .as_ref()
+            .unwrap()
+            .par_iter()
+            .filter(|path| path.starts_with(&self.start_at))
+            .filter(|path| is_markdown_file(path) && !self.is_verbatim_file(path))
+            .filter(|path| match self.read_note(path, false) {
+                Ok(Some((frontmatter, content))) => self.is_exported_note(&frontmatter, &content),
+                _ => false,
+            })
+            .cloned()
+            .collect()
+    }
+
+    // Whether a note with the given frontmatter and content (as returned by read_note) is
+    // exported, applying the same checks as process_obsidian_note: export directives,
+    // Exporter::include_tags and Exporter::skip_empty_notes. Notes outside of publish marker
+    // directories never make it into the vault in the first place.
+    fn is_exported_note(&self, frontmatter: &Frontmatter, content: &str) -> bool {
+        if let Some((_, prefix)) = &self.directive_comment {
+            if frontmatter.get(&Value::String(prefix.to_string())) == Some(&Value::Bool(false)) {
+                return false;
+            }
+        }
+        let events: Vec<Event> = Parser::new_ext(content, parser_options()).collect();
+        if !self.include_tags.is_empty() {
+            let mut tags = frontmatter_tags(frontmatter);
+            tags.append(&mut inline_tags(&events));
+            if !self
+                .include_tags
+                .iter()
+                .any(|filter| tags.iter().any(|tag| tag_matches(tag, filter)))
+            {
+                return false;
+            }
+        }
+        !(self.skip_empty_notes && is_empty_note(&events))
+    }
+
+    // Hash the contents of every attachment in the vault, keeping the first `len` hexadecimal
+    // characters of each hash.
+    fn hash_attachments(&self, len: usize) -> Result<HashMap<PathBuf, String>> {
+        self.vault_contents
+            .as_ref()
+            .unwrap()
+            .par_iter()
+            .filter(|path| !(is_markdown_file(path) || self.render_bases && is_base_file(path)))
+            .map(|path| {
+                let contents = fs::read(path).context(ReadError { path })?;
+                let mut hash = format!("{:016x}", fnv1a_hash(&contents));
+                hash.truncate(len);
+                Ok((path.to_path_buf(), hash))
+            })
+            .collect()
+    }
+
+    // Return the path the file `src` should be written to when its destination would otherwise be
+    // `path`, accounting for the extension change of notes when exporting to a format other than
+    // markdown, for hashed attachment names and for sanitized filenames.
+    fn output_path(&self, src: &Path, path: &Path) -> PathBuf {
+        self.sanitize_output_path(&self.unsanitized_output_path(src, path))
+    }
+
+    fn unsanitized_output_path(&self, src: &Path, path: &Path) -> PathBuf {
+        if let Some(hash) = self.attachment_hashes.get(src) {
+            let mut filename = path.file_stem().unwrap_or_default().to_os_string();
+            filename.push(".");
+            filename.push(hash);
+            if let Some(extension) = path.extension() {
+                filename.push(".");
+                filename.push(extension);
+            }
+            return path.with_file_name(filename);
+        }
+        if self.is_verbatim_file(src) {
+            return path.to_path_buf();
+        }
+        let is_rendered_base = self.render_bases && is_base_file(path);
+        match (
+            self.output_format,
+            is_markdown_file(path) || is_rendered_base,
+        ) {
+            (OutputFormat::Html, true) => path.with_extension("html"),
+            (OutputFormat::Markdown, true) if is_rendered_base => path.with_extension("md"),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    fn export_note(&self, src: &Path, dest: &Path) -> Result<()> {
+        match is_markdown_file(src) {
+            true if self.is_verbatim_file(src) => self.timed("copy", || {
+                self.copy_output(src, dest)?;
+                self.record_copied_attachment(src, dest);
+                Ok(())
+            }),
+            true => self.parse_and_export_obsidian_note(src, dest),
+            false if self.render_bases && is_base_file(src) => self.export_base(src, dest),
+            false if self.inlined_svgs.contains(src) => Ok(()),
+            false if self.is_sidecar_file(src) => Ok(()),
+            false if self.skip_attachments => Ok(()),
+            false if self.is_oversized_attachment(src) => {
+                self.warn(
+                    Warning::new("Attachment exceeds the maximum size, skipping").source(src),
+                );
+                self.record_skipped(src, SkipReason::TooLarge);
+                Ok(())
+            }
+            false => {
+                self.queue_attachment(src, self.attachment_destinations(src, dest));
+                Ok(())
+            }
+        }
+        .context(FileExportError { path: src })
+    }
+
+    fn is_oversized_attachment(&self, path: &Path) -> bool {
+        match self.max_attachment_size {
+            // Errors reading the metadata are left to be reported when copying the file.
+            Some(size) => fs::metadata(path).is_ok_and(|metadata| metadata.len() > size),
+            None => false,
+        }
+    }
+
+    fn export_attachment(&self, src: &Path, dest: &Path) -> Result<()> {
+        if self.attachment_processors.is_empty() {
+            self.copy_output(src, dest)?;
+            self.record_copied_attachment(src, dest);
+            return Ok(());
+        }
+        let mut contents = fs::read(src).context(ReadError { path: src })?;
+        for (idx, func) in self.attachment_processors.iter().enumerate() {
+            let res = match self.catch_panic(|| func(src, &mut contents)) {
+                Ok(res) => res,
+                Err(message) => {
+                    self.warn(
+                        Warning::new(format_args!(
+                            "Attachment processor #{} panicked, skipping attachment",
+                            idx + 1
+                        ))
+                        .detail("Message", message)
+                        .source(src),
+                    );
+                    self.record_skipped(src, SkipReason::PostprocessorPanicked);
+                    return Ok(());
+                }
+            };
+            match res {
+                PostprocessorResult::StopHere => break,
+                PostprocessorResult::StopAndSkipNote => {
+                    self.record_skipped(src, SkipReason::Postprocessor);
+                    return Ok(());
+                }
+                PostprocessorResult::Abort(message) => {
+                    return Err(ExportError::PostprocessorAborted {
+                        path: src.to_path_buf(),
+                        message,
+                    })
+                }
+                PostprocessorResult::Continue => (),
+            }
+        }
+        self.create_output(dest)?
+            .write_all(&contents)
+            .context(WriteError { path: dest })?;
+        self.record_copied_attachment(src, dest);
+        Ok(())
+    }
+
+    // Render the first table view of the Obsidian Base at `src` to a table of the notes matching
+    // its filters.
+    fn export_base(&self, src: &Path, dest: &Path) -> Result<()> {
+        let definition = fs::read_to_string(src).context(ReadError { path: src })?;
+        // Only notes which are exported themselves are listed, so the table doesn't link to notes
+        // which are missing from the export.
+        let notes: HashMap<PathBuf, Frontmatter> = self
+            .frontmatter_index
+            .as_ref()
+            .unwrap()
+            .iter()
+            .filter(|(path, _)| self.exported_notes.contains(*path))
+            .map(|(path, frontmatter)| (path.clone(), frontmatter.clone()))
+            .collect();
+        let table = evaluate_base(&definition, &notes, &self.root)
+            .context(BaseDecodeError { path: src })?;
+
+        let mut events = vec![
+            Event::Start(Tag::Table(vec![Alignment::None; table.columns.len()])),
+            Event::Start(Tag::TableHead),
+        ];
+        for header in table.headers {
+            events.push(Event::Start(Tag::TableCell));
+            events.push(Event::Text(CowStr::from(header)));
+            events.push(Event::End(Tag::TableCell));
+        }
+        events.push(Event::End(Tag::TableHead));
+        for (path, frontmatter) in table.rows {
+            events.push(Event::Start(Tag::TableRow));
+            for column in &table.columns {
+                events.push(Event::Start(Tag::TableCell));
+                let value = note_property(path, frontmatter, &self.root, column);
+                if column == "file.name" {
+                    let rel_link = diff_paths(
+                        path,
+                        src.parent()
+                            .expect("obsidian content files should always have a parent"),
+                    )
+                    .expect(
+                        "should be able to build relative path when target file is found in vault",
+                    );
+                    let link_tag = Tag::Link(
+                        pulldown_cmark::LinkType::Inline,
+                        CowStr::from(path_to_link(&self.output_path(path, &rel_link))),
+                        CowStr::from(""),
+                    );
+                    events.push(Event::Start(link_tag.clone()));
+                    events.push(Event::Text(CowStr::from(frontmatter_value_to_string(
+                        &value,
+                    ))));
+                    events.push(Event::End(link_tag));
+                } else if value != Value::Null {
+                    events.push(Event::Text(CowStr::from(frontmatter_value_to_string(
+                        &value,
+                    ))));
+                }
+                events.push(Event::End(Tag::TableCell));
+            }
+            events.push(Event::End(Tag::TableRow));
+        }
+        events.push(Event::End(Tag::Table(vec![
+            Alignment::None;
+            table.columns.len()
+        ])));
+
+        let output = match self.output_format {
+            OutputFormat::Html => self.render_html(events, &Frontmatter::new(), src),
+            OutputFormat::Markdown => render_mdevents_to_mdtext(events),
+        };
+        self.create_output(dest)?
+            .write_all(output.as_bytes())
+            .context(WriteError { path: dest })?;
+        self.record_exported_note(src, dest);
+        Ok(())
+    }
+
+    // Run `func`, accumulating the time it takes under `stage` when profiling is enabled.
+    fn timed<T>(&self, stage: &str, func: impl FnOnce() -> T) -> T {
+        match &self.profile {
+            Some(profile) => profile.time(stage, func),
+            None => func(),
+        }
+    }
+
+    fn parse_and_export_obsidian_note(&self, src: &Path, dest: &Path) -> Result<()> {
+        let (context, markdown_events) = match self.process_obsidian_note(src, dest)? {
+            Some(note) => note,
+            None => return Ok(()),
+        };
+        // The frontmatter is checked against the schema before the note is recorded anywhere, so
+        // notes which fail a strict schema don't end up in indexes written alongside the export.
+        let mut frontmatter = context.frontmatter.clone();
+        let frontmatter_strategy = self.note_frontmatter_strategy(src, &mut frontmatter);
+        if !self.frontmatter_key_renames.is_empty() {
+            let (renamed, collisions) =
+                rename_frontmatter_keys(frontmatter, &self.frontmatter_key_renames);
+            frontmatter = renamed;
+            for key in collisions {
+                // TODO: Extract into configurable function.
+                self.warn(
+                    Warning::new("Several frontmatter keys are renamed to the same key")
+                        .detail("Key", key)
+                        .source(src),
+                );
+            }
+        }
+        if let Some((_, strict)) = self.frontmatter_schema {
+            self.check_frontmatter_schema(src, &frontmatter, strict)?;
+        }
+        if self.redirect_map_output.is_some() || self.netlify_redirects_output.is_some() {
+            self.record_redirects(&context);
+        }
+        if self.search_index_output.is_some() {
+            self.record_search_entry(&context, &markdown_events);
+        }
+        if self.metadata_sidecar {
+            self.write_metadata_sidecar(&context, &markdown_events)?;
+        }
+        if let Some(config) = &self.directory_indexes {
+            self.record_indexed_note(config, &context);
+        }
+        if self.also_concatenate.is_some() {
+            self.rendered_notes
+                .lock()
+                .expect("rendered notes lock poisoned")
+                .push(ConcatenatedNote {
+                    source: src.to_path_buf(),
+                    context: context.clone(),
+                    events: markdown_events.clone(),
+                });
+        }
+        let markdown_events = match &self.body_wrapper {
+            Some((open, close)) => wrap_events(markdown_events, open, close),
+            None => markdown_events,
+        };
+
+        let dest = context.destination;
+        let passthrough = match self.passthrough_unmodified
+            && self.output_format == OutputFormat::Markdown
+            && !self.frontmatter_as_export_const
+            && self.body_wrapper.is_none()
+        {
+            true => {
+                self.unmodified_source(src, &frontmatter, frontmatter_strategy, &markdown_events)
+            }
+            false => None,
+        };
+        let output = self.timed("serialize", || -> Result<String> {
+            if let Some(output) = passthrough {
+                return Ok(output);
+            }
+            let markdown_events = match self.frontmatter_as_table
+                && matches!(frontmatter_strategy, FrontmatterStrategy::Never)
+                && !frontmatter.is_empty()
+            {
+                true => frontmatter_table(&frontmatter)
+                    .into_iter()
+                    .chain(markdown_events)
+                    .collect(),
+                false => markdown_events,
+            };
+            if self.output_format == OutputFormat::Html {
+                return Ok(self.render_html(markdown_events, &frontmatter, src));
+            }
+            let write_frontmatter = match frontmatter_strategy {
+                FrontmatterStrategy::Always => true,
+                FrontmatterStrategy::Never => false,
+                FrontmatterStrategy::Auto => !frontmatter.is_empty(),
+            };
+            let mut output = String::new();
+            if write_frontmatter && self.frontmatter_as_export_const {
+                output.push_str(&format!(
+                    "export const frontmatter = {};\n\n",
+                    frontmatter_to_json(&frontmatter)
+                ));
+            } else if write_frontmatter {
+                let original = match self.keep_frontmatter_comments {
+                    true => self.unmodified_frontmatter(src, &frontmatter),
+                    false => None,
+                };
+                match original {
+                    Some(original) => {
+                        output.push_str(&format!("---\n{}\n---\n", original.trim_end()))
+                    }
+                    None => output.push_str(
+                        &frontmatter_to_str(frontmatter)
+                            .context(FrontMatterEncodeError { path: src })?,
+                    ),
+                }
+                output.push('\n');
+            }
+            output.push_str(&render_mdevents_to_mdtext(markdown_events));
+            Ok(output)
+        })?;
+        if let Some(previous_export) = &self.previous_export {
+            let relative_path = dest.strip_prefix(&self.destination).unwrap_or(&dest);
+            let previous_path = previous_export.join(relative_path);
+            let kind = classify_change(fs::read_to_string(&previous_path).ok().as_deref(), &output);
+            self.changes
+                .lock()
+                .expect("changes lock poisoned")
+                .push(NoteChange {
+                    path: relative_path.to_path_buf(),
+                    kind,
+                });
+            if kind == ChangeKind::Unchanged && previous_path == dest {
+                self.record_skipped(src, SkipReason::Unchanged);
+                return Ok(());
+            }
+        }
+        self.timed("write", || {
+            self.create_output(&dest)?
+                .write_all(output.as_bytes())
+                .context(WriteError { path: &dest })
+        })?;
+        self.record_exported_note(src, &dest);
+        Ok(())
+    }
+
+    // Return the frontmatter strategy for the note at `src`, which may be overridden by the note
+    // itself through the export_frontmatter key. This key is removed from `frontmatter`.
+    fn note_frontmatter_strategy(
+        &self,
+        src: &Path,
+        frontmatter: &mut Frontmatter,
+    ) -> FrontmatterStrategy {
+        let value = match frontmatter.remove(&Value::String("export_frontmatter".to_string())) {
+            Some(value) => value,
+            None => return self.frontmatter_strategy,
+        };
+        match value.as_str() {
+            Some("always") => FrontmatterStrategy::Always,
+            Some("never") => FrontmatterStrategy::Never,
+            Some("auto") => FrontmatterStrategy::Auto,
+            _ => {
+                self.warn(
+                    Warning::new("Invalid value for export_frontmatter (expected one of: always, never, auto)")
+                        .source(src),
+                );
+                self.frontmatter_strategy
+            }
+        }
+    }
+
+    // Parse a note and run postprocessors on it, returning its final context and contents.
+    //
+    // Returns None when the note should not be exported.
+    fn process_obsidian_note<'b>(
+        &self,
+        src: &Path,
+        dest: &Path,
+    ) -> Result<Option<(Context, MarkdownEvents<'b>)>> {
+        let mut context = Context::new(src.to_path_buf(), dest.to_path_buf());
+        context.vault_notes = self.frontmatter_index.clone();
+
+        let note = match self.timed("parse", || self.parse_obsidian_note(src, &context))? {
+            Some(note) => note,
+            None => {
+                self.record_skipped(src, SkipReason::InvalidEncoding);
+                return Ok(None);
+            }
+        };
+        let mut markdown_events = note.events;
+        context.frontmatter = note.frontmatter;
+        context.embed_ranges = note.embed_ranges;
+        context.inline_tags = note.inline_tags;
+        context.embeds = note.embeds;
+        if self.block_id_style != BlockIdStyle::Keep {
+            markdown_events = convert_block_ids(markdown_events, self.block_id_style);
+        }
+        for key in &self.frontmatter_attachment_keys {
+            let key = Value::String(key.to_string());
+            let value = match context.frontmatter.get(&key) {
+                Some(value) => self.rewrite_frontmatter_attachment(value, &context),
+                None => None,
+            };
+            if let Some(value) = value {
+                context.frontmatter.insert(key, value);
+            }
+        }
+        if let Some((_, prefix)) = &self.directive_comment {
+            let export = context
+                .frontmatter
+                .remove(&Value::String(prefix.to_string()));
+            if export == Some(Value::Bool(false)) {
+                self.record_skipped(src, SkipReason::Directive);
+                return Ok(None);
+            }
+        }
+        if !self.include_tags.is_empty()
+            && !self.include_tags.iter().any(|tag| context.has_tag(tag))
+        {
+            self.record_skipped(src, SkipReason::ExcludedByTags);
+            return Ok(None);
+        }
+        if let Some(key) = &self.slug_key {
+            let key = Value::String(key.to_string());
+            if !context.frontmatter.contains_key(&key) {
+                let stem = src.file_stem().unwrap_or_default().to_string_lossy();
+                context
+                    .frontmatter
+                    .insert(key, Value::String(slugify(stem)));
+            }
+        }
+        for (key, value) in &self.provenance {
+            context.frontmatter.insert(key.clone(), value.clone());
+        }
+        if let Some(strategy) = &self.backlink_strategy {
+            self.add_backlinks(strategy, &mut context, &mut markdown_events);
+        }
+        for (idx, func) in self.postprocessors.iter().enumerate() {
+            let stage = format!("postprocessor #{}", idx + 1);
+            let call = || self.catch_panic(|| func.call(context, markdown_events));
+            let res = match self.timed(&stage, call) {
+                Ok(res) => res,
+                Err(message) => {
+                    self.warn(
+                        Warning::new(format_args!(
+                            "Postprocessor #{} panicked, skipping note",
+                            idx + 1
+                        ))
+                        .detail("Message", message)
+                        .source(src),
+                    );
+                    self.record_skipped(src, SkipReason::PostprocessorPanicked);
+                    return Ok(None);
+                }
+            };
+            context = res.0;
+            markdown_events = res.1;
+            match res.2 {
+                PostprocessorResult::StopHere => break,
+                PostprocessorResult::StopAndSkipNote => {
+                    self.record_skipped(src, SkipReason::Postprocessor);
+                    return Ok(None);
+                }
+                PostprocessorResult::Abort(message) => {
+                    return Err(ExportError::PostprocessorAborted {
+                        path: src.to_path_buf(),
+                        message,
+                    })
+                }
+                PostprocessorResult::Continue => (),
+            }
+        }
+
+        if self.skip_empty_notes && is_empty_note(&markdown_events) {
+            self.record_skipped(src, SkipReason::Empty);
+            return Ok(None);
+        }
+        Ok(Some((context, markdown_events)))
+    }
+
+    // Call `func`, catching any panic when Exporter::catch_postprocessor_panics is enabled. The
+    // message of a caught panic is returned as the error.
+    fn catch_panic<T>(&self, func: impl FnOnce() -> T) -> std::result::Result<T, String> {
+        if !self.catch_postprocessor_panics {
+            return Ok(func());
+        }
+        panic::catch_unwind(AssertUnwindSafe(func)).map_err(|payload| {
+            payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default()
+        })
+    }
+
+    // Whether no encoding warning was emitted for the note at `path` yet during this run.
+    fn first_encoding_warning(&self, path: &Path) -> bool {
+        self.encoding_warnings
+            .lock()
+            .expect("encoding warnings lock poisoned")
+            .insert(path.to_path_buf())
+    }
+
+    // Read a note from disk, splitting it into its frontmatter and the remaining body content.
+    //
+    // Returns None when the note should be skipped because it isn't valid UTF-8 (see
+    // EncodingErrorStrategy::Skip). Such notes are reported with a warning when `report` is set,
+    // once per run no matter how often the note is read (such as when it's embedded).
+    fn read_note(&self, path: &Path, report: bool) -> Result<Option<(Frontmatter, String)>> {
+        let content = fs::read(path).context(ReadError { path })?;
+        let content = match String::from_utf8(content) {
+            Ok(content) => content,
+            Err(err) => match self.encoding_error_strategy {
+                EncodingErrorStrategy::Fail => {
+                    return Err(ExportError::CharacterEncodingError {
+                        path: path.to_path_buf(),
+                        source: err.utf8_error(),
+                    })
+                }
+                EncodingErrorStrategy::Lossy => {
+                    if report && self.first_encoding_warning(path) {
+                        self.warn(Warning::new("Invalid UTF-8 replaced with U+FFFD").source(path));
+                    }
+                    String::from_utf8_lossy(err.as_bytes()).into_owned()
+                }
+                EncodingErrorStrategy::Skip => {
+                    if report && self.first_encoding_warning(path) {
+                        self.warn(Warning::new("Skipping note with invalid UTF-8").source(path));
+                    }
+                    return Ok(None);
+                }
+            },
+        };
+        self.record_note_source(path, &content);
+        let content = match self.normalize_unicode {
+            true => to_nfc(&content).into_owned(),
+            false => content,
+        };
+        let (frontmatter, content) = match matter::matter(&content) {
+            Some((frontmatter, content)) => (frontmatter, separate_yaml_documents(content)),
+            None => ("".to_string(), content.to_string()),
+        };
+        let mut frontmatter =
+            frontmatter_from_str(&frontmatter).context(FrontMatterDecodeError { path })?;
+        if let Some(sidecar) = self.read_sidecar_frontmatter(path)? {
+            merge_sidecar_frontmatter(&mut frontmatter, sidecar, self.sidecar_precedence);
+        }
+        let content = match self.backmatter {
+            true => match extract_backmatter(&content) {
+                Some((content, backmatter)) => {
+                    for (key, value) in backmatter {
+                        if !frontmatter.contains_key(&key) {
+                            frontmatter.insert(key, value);
+                        }
+                    }
+                    content
+                }
+                None => content,
+            },
+            false => content,
+        };
+        let content =
+            extract_metadata_comments(content, &mut frontmatter, &self.metadata_comment_patterns);
+        let content = match &self.directive_comment {
+            Some((regex, prefix)) => {
+                extract_directive_comments(content, &mut frontmatter, regex, prefix)
+            }
+            None => content,
+        };
+        let content = process_comments(content, self.comment_strategy);
+        Ok(Some((frontmatter, content)))
+    }
+
+    // Read the frontmatter of the sidecar file of the note at `path`, if there is one.
+    fn read_sidecar_frontmatter(&self, path: &Path) -> Result<Option<Frontmatter>> {
+        let extension = match &self.sidecar_extension {
+            Some(extension) => extension,
+            None => return Ok(None),
+        };
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(".");
+        sidecar.push(extension);
+        let sidecar = PathBuf::from(sidecar);
+        if !sidecar.is_file() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&sidecar).context(ReadError { path: &sidecar })?;
+        let frontmatter =
+            frontmatter_from_str(&content).context(FrontMatterDecodeError { path: &sidecar })?;
+        Ok(Some(frontmatter))
+    }
+
+    // Whether `path` is inside one of the verbatim_dirs of the walk options.
+    fn is_verbatim_file(&self, path: &Path) -> bool {
+        self.walk_options
+            .verbatim_dirs
+            .iter()
+            .any(|dir| path.starts_with(self.root.join(dir)))
+    }
+
+    // Whether `path` is the sidecar file of a note (see Exporter::sidecar_frontmatter).
+    fn is_sidecar_file(&self, path: &Path) -> bool {
+        let extension = match &self.sidecar_extension {
+            Some(extension) => extension,
+            None => return false,
+        };
+        path.extension()
+            .is_some_and(|ext| ext == extension.as_str())
+            && is_markdown_file(&path.with_extension(""))
+    }
+
+    fn parse_obsidian_note<'b>(
+        &self,
+        path: &Path,
+        context: &Context,
+    ) -> Result<Option<ParsedNote<'b>>> {
+        if context.note_depth() > NOTE_RECURSION_LIMIT {
+            return Err(ExportError::RecursionLimitExceeded {
+                file_tree: context.file_tree(),
+            });
+        }
+        let (frontmatter, content) = match self.read_note(path, true)? {
+            Some(note) => note,
+            None => return Ok(None),
+        };
+
+        let mut ref_parser = RefParser::new();
+        let mut events = vec![];
+        let mut embed_ranges = vec![];
+        let mut embeds = vec![];
+        // Most of the time, a reference triggers 5 events: [ or ![, [, <text>, ], ]
+        let mut buffer = Vec::with_capacity(5);
+        // Links can't be placed within the alt text of images, so references in there are turned
+        // into plain text instead.
+        let mut image_depth = 0;
+
+        for event in Parser::new_ext(&content, parser_options()) {
+            let event = match event {
+                Event::Start(Tag::Image(kind, url, title)) => {
+                    image_depth += 1;
+                    Event::Start(Tag::Image(kind, url, wikilinks_to_text(&title).into()))
+                }
+                Event::End(Tag::Image(kind, url, title)) => {
+                    image_depth -= 1;
+                    Event::End(Tag::Image(kind, url, wikilinks_to_text(&title).into()))
+                }
+                event => event,
+            };
+            if ref_parser.state == RefParserState::Resetting {
                 events.append(&mut buffer);
                 buffer.clear();
                 ref_parser.reset();
@@ -483,6 +3083,12 @@ impl<'a> Exporter<'a> {
                             ref_parser.ref_type = Some(RefType::Link);
                             ref_parser.transition(RefParserState::ExpectSecondOpenBracket);
                         }
+                        Event::Html(html) => {
+                            events.push(Event::Html(CowStr::from(
+                                self.rewrite_html_paths(&html, context),
+                            )));
+                            buffer.clear();
+                        }
                         _ => {
                             events.push(event);
                             buffer.clear();
@@ -522,6 +3128,13 @@ impl<'a> Exporter<'a> {
                 },
                 RefParserState::ExpectFinalCloseBracket => match event {
                     Event::Text(CowStr::Borrowed("]")) => match ref_parser.ref_type {
+                        Some(_) if image_depth > 0 => {
+                            events.push(Event::Text(CowStr::from(
+                                ObsidianNoteReference::from_str(&ref_parser.ref_text).display(),
+                            )));
+                            buffer.clear();
+                            ref_parser.transition(RefParserState::Resetting);
+                        }
                         Some(RefType::Link) => {
                             let mut elements = self.make_link_to_file(
                                 ObsidianNoteReference::from_str(
@@ -534,10 +3147,23 @@ impl<'a> Exporter<'a> {
                             ref_parser.transition(RefParserState::Resetting);
                         }
                         Some(RefType::Embed) => {
-                            let mut elements = self.embed_file(
+                            if self.metadata_sidecar {
+                                let target = ObsidianNoteReference::from_str(&ref_parser.ref_text)
+                                    .file
+                                    .and_then(|file| self.lookup_embed_target(file, context, false));
+                                embeds.extend(target.cloned());
+                            }
+                            let embed = || self.embed_file(
                                 ref_parser.ref_text.clone().as_ref(),
                                 context
-                            )?;
+                            );
+                            // Embeds nested within embeds are already accounted for by the time
+                            // spent on the outer-most embed.
+                            let mut elements = match context.note_depth() {
+                                1 => self.timed("parse;embeds", embed),
+                                _ => embed(),
+                            }?;
+                            embed_ranges.push(events.len()..events.len() + elements.len());
                             events.append(&mut elements);
                             buffer.clear();
                             ref_parser.transition(RefParserState::Resetting);
@@ -554,10 +3180,23 @@ impl<'a> Exporter<'a> {
         if !buffer.is_empty() {
             events.append(&mut buffer);
         }
-        Ok((
+        let mut tags = vec![];
+        let mut start = 0;
+        for range in embed_ranges
+            .iter()
+            .chain(std::iter::once(&(events.len()..events.len())))
+        {
+            tags.append(&mut inline_tags(&events[start..range.start]));
+            start = range.end;
+        }
+
+        Ok(Some(ParsedNote {
             frontmatter,
-            events.into_iter().map(event_to_owned).collect(),
-        ))
+            events: events.into_iter().map(event_to_owned).collect(),
+            embed_ranges,
+            inline_tags: tags,
+            embeds,
+        }))
     }
 
     // Generate markdown elements for a file that is embedded within another note.
@@ -573,49 +3212,142 @@ impl<'a> Exporter<'a> {
         let note_ref = ObsidianNoteReference::from_str(link_text);
 
         let path = match note_ref.file {
-            Some(file) => lookup_filename_in_vault(file, self.vault_contents.as_ref().unwrap()),
+            Some(file) => self.lookup_embed_target(file, context, true),
 
             // If we have None file it is either to a section or id within the same file and thus
             // the current embed logic will fail, recurssing until it reaches it's limit.
             // For now we just bail early.
             None => return Ok(self.make_link_to_file(note_ref, context)),
         };
+        self.report_link(
+            LinkKind::Embed,
+            &note_ref,
+            path.map(PathBuf::as_path),
+            context,
+        );
 
         if path.is_none() {
             // TODO: Extract into configurable function.
-            eprintln!(
-                "Warning: Unable to find embedded note\n\tReference: '{}'\n\tSource: '{}'\n",
-                note_ref
-                    .file
-                    .unwrap_or_else(|| context.current_file().to_str().unwrap()),
-                context.current_file().display(),
+            self.warn(
+                Warning::new("Unable to find embedded note")
+                    .reference(
+                        note_ref
+                            .file
+                            .unwrap_or_else(|| context.current_file().to_str().unwrap()),
+                    )
+                    .source(context.current_file()),
+            );
+            self.record_issue(
+                IssueKind::MissingEmbed,
+                context.current_file(),
+                note_ref.file.unwrap_or_default(),
             );
-            return Ok(vec![]);
+            return Ok(self.unresolved_embed(link_text, &note_ref));
         }
 
         let path = path.unwrap();
+        if self.export_files.is_some() && !is_markdown_file(path) {
+            self.export_dependency(path)?;
+        }
+        if let Some(field) = note_ref.frontmatter_field() {
+            return Ok(self.embed_frontmatter_field(path, field, context));
+        }
+
+        if self.skips_embed(path) {
+            return Ok(match self.embed_skip_comment {
+                true => vec![Event::Html(CowStr::from(format!(
+                    "<!-- Embed of '{}' omitted -->",
+                    note_ref.display()
+                )))],
+                false => vec![],
+            });
+        }
+
         let mut child_context = Context::from_parent(context, path);
         let no_ext = OsString::new();
 
         if !self.process_embeds_recursively && context.file_tree().contains(path) {
             return Ok([
                 vec![Event::Text(CowStr::Borrowed("→ "))],
-                self.make_link_to_file(note_ref, &child_context),
+                self.make_link_to_path(note_ref, path, &child_context),
             ]
             .concat());
         }
 
         let events = match path.extension().unwrap_or(&no_ext).to_str() {
             Some("md") => {
-                let (frontmatter, mut events) = self.parse_obsidian_note(path, &child_context)?;
-                child_context.frontmatter = frontmatter;
+                let note = match self.parse_obsidian_note(path, &child_context)? {
+                    Some(note) => note,
+                    None => return Ok(vec![]),
+                };
+                let mut events = note.events;
+                child_context.frontmatter = note.frontmatter;
+                child_context.embed_ranges = note.embed_ranges;
+                child_context.inline_tags = note.inline_tags;
+                child_context.embeds = note.embeds;
                 if let Some(section) = note_ref.section {
-                    events = reduce_to_section(events, section);
+                    events = match (section.strip_prefix('^'), section.split_once("..")) {
+                        (Some(block_id), _) => match reduce_to_block(events, block_id) {
+                            Some(events) => events,
+                            None => {
+                                // TODO: Extract into configurable function.
+                                self.warn(
+                                    Warning::new(
+                                        "Unable to find the embedded block, leaving out embed",
+                                    )
+                                    .reference(note_ref.display())
+                                    .source(context.current_file()),
+                                );
+                                vec![]
+                            }
+                        },
+                        (None, Some((from, to))) => {
+                            let (events, found_end) =
+                                reduce_to_section_range(events, section, from, to);
+                            if !found_end {
+                                // TODO: Extract into configurable function.
+                                self.warn(
+                                    Warning::new("Unable to find the heading ending the embedded sections, embedding up to the end of the note")
+                                        .reference(note_ref.display())
+                                        .source(context.current_file()),
+                                );
+                            }
+                            events
+                        }
+                        (None, None) => reduce_to_section(events, section),
+                    };
+                    // Event positions no longer line up after taking out a section, so it's not
+                    // possible to tell which of the remaining events came from nested embeds.
+                    child_context.embed_ranges.clear();
+                }
+                if let Some(columns) = note_ref.label.and_then(|label| label.strip_prefix("cols="))
+                {
+                    let columns: Vec<usize> = columns
+                        .split(',')
+                        .filter_map(|column| column.trim().parse().ok())
+                        .collect();
+                    events = select_table_columns(events, &columns);
                 }
-                for func in &self.embed_postprocessors {
+                for (idx, func) in self.embed_postprocessors.iter().enumerate() {
                     // Postprocessors running on embeds shouldn't be able to change frontmatter (or
                     // any other metadata), so we give them a clone of the context.
-                    let res = func(child_context, events);
+                    let stage = format!("parse;embeds;embed postprocessor #{}", idx + 1);
+                    let call = || self.catch_panic(|| func(child_context, events));
+                    let res = match self.timed(&stage, call) {
+                        Ok(res) => res,
+                        Err(message) => {
+                            self.warn(
+                                Warning::new(format_args!(
+                                    "Embed postprocessor #{} panicked, leaving out embed",
+                                    idx + 1
+                                ))
+                                .detail("Message", message)
+                                .reference(note_ref.display())
+                                .source(context.current_file()),
+                            );
+                            return Ok(vec![]);
+                        }
+                    };
                     child_context = res.0;
                     events = res.1;
                     match res.2 {
@@ -623,13 +3355,28 @@ impl<'a> Exporter<'a> {
                         PostprocessorResult::StopAndSkipNote => {
                             events = vec![];
                         }
+                        PostprocessorResult::Abort(message) => {
+                            return Err(ExportError::PostprocessorAborted {
+                                path: path.to_path_buf(),
+                                message,
+                            })
+                        }
                         PostprocessorResult::Continue => (),
                     }
                 }
                 events
             }
+            Some("svg") if self.inline_svg => {
+                let svg = fs::read_to_string(path).context(ReadError { path })?;
+                vec![Event::Html(CowStr::from(self.prepare_inline_svg(&svg)))]
+            }
+            Some("png") | Some("jpg") | Some("jpeg") | Some("gif") | Some("webp") | Some("svg")
+                if !self.image_variants(path).is_empty() =>
+            {
+                self.make_responsive_image(note_ref, path, &child_context)
+            }
             Some("png") | Some("jpg") | Some("jpeg") | Some("gif") | Some("webp") | Some("svg") => {
-                self.make_link_to_file(note_ref, &child_context)
+                self.make_link_to_path(note_ref, path, &child_context)
                     .into_iter()
                     .map(|event| match event {
                         // make_link_to_file returns a link to a file. With this we turn the link
@@ -654,11 +3401,261 @@ impl<'a> Exporter<'a> {
                     })
                     .collect()
             }
-            _ => self.make_link_to_file(note_ref, &child_context),
+            _ => self.make_link_to_path(note_ref, path, &child_context),
         };
         Ok(events)
     }
 
+    // Turn the contents of an SVG file into markup which can be placed inline in a note.
+    fn prepare_inline_svg(&self, svg: &str) -> String {
+        lazy_static! {
+            static ref PROLOG_RE: Regex =
+                Regex::new(r"(?is)<\?xml.*?\?>|<!DOCTYPE[^>]*>|<!--.*?-->").unwrap();
+            static ref SCRIPT_RE: Regex =
+                Regex::new(r"(?is)<script\b.*?(/>|</script\s*>)").unwrap();
+            static ref EVENT_HANDLER_RE: Regex =
+                Regex::new(r#"(?i)\s+on\w+\s*=\s*("[^"]*"|'[^']*'|[^\s>]+)"#).unwrap();
+            static ref BLANK_LINES_RE: Regex = Regex::new(r"\n\s*\n").unwrap();
+        }
+        let mut svg = PROLOG_RE.replace_all(svg, "").into_owned();
+        if self.sanitize_svg {
+            svg = SCRIPT_RE.replace_all(&svg, "").into_owned();
+            svg = EVENT_HANDLER_RE.replace_all(&svg, "").into_owned();
+        }
+        // A blank line would end the HTML block when the note is rendered as markdown.
+        BLANK_LINES_RE.replace_all(svg.trim(), "\n").into_owned()
+    }
+
+    // Find the file in the vault which `filename` refers to, when referenced from the note at
+    // `source`.
+    fn lookup_filename(&self, filename: &str, source: &Path) -> Option<&PathBuf> {
+        self.lookup_filename_in(filename, source, &self.matching_files(filename))
+    }
+
+    // Like lookup_filename, where `matches` are the files returned by matching_files for
+    // `filename`.
+    fn lookup_filename_in<'s>(
+        &'s self,
+        filename: &str,
+        source: &Path,
+        matches: &[&'s PathBuf],
+    ) -> Option<&'s PathBuf> {
+        let base_dir = match self.link_resolution {
+            LinkResolution::ShortestPath => None,
+            LinkResolution::RelativeFirst => source.parent(),
+            LinkResolution::Absolute => Some(self.root.as_path()),
+        };
+        if let Some(path) = base_dir.and_then(|dir| self.file_at(&dir.join(filename))) {
+            return Some(path);
+        }
+        match matches.first() {
+            Some(path) => Some(path),
+            // References which don't match a file may use one of the aliases of a note instead.
+            None => self.alias_index.get(&self.alias_key(filename)),
+        }
+    }
+
+    // Find all files in the vault which `filename` may refer to, such as every file with a given
+    // name when it's referred to by name only. The file with the shortest path comes first.
+    fn matching_files(&self, filename: &str) -> Vec<&PathBuf> {
+        let vault_contents = self.vault_contents.as_ref().unwrap();
+        let positions = match &self.normalized_vault_contents {
+            Some(normalized) => positions_in_vault(&to_nfc(filename), normalized, &self.name_index),
+            None => positions_in_vault(filename, vault_contents, &self.name_index),
+        };
+        let mut files: Vec<&PathBuf> = positions
+            .into_iter()
+            .map(|idx| &vault_contents[idx])
+            .collect();
+        files.sort_by_key(|path| (path.components().count(), *path));
+        files
+    }
+
+    // Map the lexically normalized path of every file in the vault to its position in
+    // vault_contents, for lookups through file_at.
+    fn build_path_index(&self) -> HashMap<PathBuf, usize> {
+        let paths = match &self.normalized_vault_contents {
+            Some(normalized) => normalized,
+            None => self.vault_contents.as_ref().unwrap(),
+        };
+        let mut path_index = HashMap::new();
+        for (idx, path) in paths.iter().enumerate() {
+            path_index.entry(normalize_path(path)).or_insert(idx);
+        }
+        path_index
+    }
+
+    // Map the lowercased filename of every file in the vault to its positions in vault_contents,
+    // so references by name only need to be compared against the files with that name.
+    fn build_name_index(&self) -> HashMap<String, Vec<usize>> {
+        let paths = match &self.normalized_vault_contents {
+            Some(normalized) => normalized,
+            None => self.vault_contents.as_ref().unwrap(),
+        };
+        let mut name_index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (idx, path) in paths.iter().enumerate() {
+            if let Some(key) = name_key(path) {
+                name_index.entry(key).or_default().push(idx);
+            }
+        }
+        name_index
+    }
+
+    // Find the file in the vault at exactly `path`, which may leave out the extension of notes.
+    fn file_at(&self, path: &Path) -> Option<&PathBuf> {
+        let path = match self.normalize_unicode {
+            true => normalize_path(Path::new(to_nfc(&path.to_string_lossy()).as_ref())),
+            false => normalize_path(path),
+        };
+        let mut note = path.clone().into_os_string();
+        note.push(".md");
+        let idx = self
+            .path_index
+            .get(&path)
+            .or_else(|| self.path_index.get(Path::new(&note)))?;
+        Some(&self.vault_contents.as_ref().unwrap()[*idx])
+    }
+
+    // Whether embeds of the note at `path` should be left out, because its frontmatter sets the
+    // embed_skip_key.
+    fn skips_embed(&self, path: &Path) -> bool {
+        let key = match &self.embed_skip_key {
+            Some(key) => Value::String(key.to_string()),
+            None => return false,
+        };
+        let value = self
+            .frontmatter_index
+            .as_ref()
+            .and_then(|index| index.get(path))
+            .and_then(|frontmatter| frontmatter.get(&key));
+        match value {
+            None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+            Some(Value::String(value)) => !value.is_empty(),
+            Some(Value::Sequence(values)) => !values.is_empty(),
+            Some(_) => true,
+        }
+    }
+
+    // Find the file referenced by an embed, resolving ambiguity between notes and attachments of
+    // the same name according to self.embed_ambiguity. Ambiguous embeds are only warned about when
+    // `report` is true, so callers looking up the same embed again don't repeat the warning.
+    fn lookup_embed_target(
+        &self,
+        filename: &str,
+        context: &Context,
+        report: bool,
+    ) -> Option<&PathBuf> {
+        let vault_contents = self.vault_contents.as_ref().unwrap();
+        let matches = self.matching_files(filename);
+        let path = self.lookup_filename_in(filename, context.current_file(), &matches)?;
+        if !is_markdown_file(path) {
+            if report && matches.len() > 1 && matches[0] == path {
+                // TODO: Extract into configurable function.
+                self.warn(
+                    Warning::new("Ambiguous embed matches several files")
+                        .reference(filename)
+                        .source(context.current_file())
+                        .detail("Using", path.display()),
+                );
+            }
+            return Some(path);
+        }
+        // Only references without an extension resolve to a note by having `.md` appended.
+        if path.ends_with(filename) {
+            return Some(path);
+        }
+        let attachment = vault_contents.iter().find(|candidate| {
+            !is_markdown_file(candidate) && candidate.with_extension("") == path.with_extension("")
+        });
+        let attachment = match attachment {
+            Some(attachment) => attachment,
+            None => return Some(path),
+        };
+        let chosen = match self.embed_ambiguity {
+            EmbedAmbiguity::PreferNote => path,
+            EmbedAmbiguity::PreferAttachment => attachment,
+        };
+        if report {
+            // TODO: Extract into configurable function.
+            self.warn(
+                Warning::new("Ambiguous embed refers to both a note and an attachment")
+                    .reference(filename)
+                    .source(context.current_file())
+                    .detail("Using", chosen.display()),
+            );
+        }
+        Some(chosen)
+    }
+
+    // Generate markdown elements for the value of a single frontmatter field from the note at
+    // `path`, for embeds of the form `![[Note#meta:field]]`.
+    fn embed_frontmatter_field<'b>(
+        &self,
+        path: &Path,
+        field: &str,
+        context: &Context,
+    ) -> MarkdownEvents<'b> {
+        let value = self
+            .frontmatter_index
+            .as_ref()
+            .unwrap()
+            .get(path)
+            .and_then(|frontmatter| frontmatter.get(&Value::String(field.to_string())));
+
+        match value {
+            Some(value) => vec![Event::Text(CowStr::from(frontmatter_value_to_string(
+                value,
+            )))],
+            None => {
+                // TODO: Extract into configurable function.
+                self.warn(
+                    Warning::new("Unable to find embedded frontmatter field")
+                        .reference(path.display())
+                        .detail("Field", field)
+                        .source(context.current_file()),
+                );
+                vec![]
+            }
+        }
+    }
+
+    // Generate markdown elements for an embed of a file which doesn't exist in the vault,
+    // according to self.unresolved_embed_style.
+    fn unresolved_embed<'b>(
+        &self,
+        link_text: &str,
+        note_ref: &ObsidianNoteReference,
+    ) -> MarkdownEvents<'b> {
+        let target = note_ref.file.unwrap_or_default();
+        match &self.unresolved_embed_style {
+            // Written as raw HTML, as text would have its brackets escaped.
+            UnresolvedEmbedStyle::Leave => {
+                vec![Event::Html(CowStr::from(format!("![[{}]]", link_text)))]
+            }
+            UnresolvedEmbedStyle::Placeholder(placeholder) => vec![Event::Text(CowStr::from(
+                placeholder.replace("{target}", target),
+            ))],
+            UnresolvedEmbedStyle::Link => {
+                let mut path = PathBuf::from(target);
+                if path.extension().is_none() {
+                    path.set_extension("md");
+                }
+                let path = self.output_path(&path, &path);
+                let link = Tag::Link(
+                    pulldown_cmark::LinkType::Inline,
+                    CowStr::from(path_to_link(&path)),
+                    CowStr::Borrowed(""),
+                );
+                vec![
+                    Event::Start(link.clone()),
+                    Event::Text(CowStr::from(note_ref.display())),
+                    Event::End(link),
+                ]
+            }
+            UnresolvedEmbedStyle::Remove => vec![],
+        }
+    }
+
     fn make_link_to_file<'b, 'c>(
         &self,
         reference: ObsidianNoteReference<'b>,
@@ -666,17 +3663,30 @@ impl<'a> Exporter<'a> {
     ) -> MarkdownEvents<'c> {
         let target_file = reference
             .file
-            .map(|file| lookup_filename_in_vault(file, self.vault_contents.as_ref().unwrap()))
+            .map(|file| self.lookup_filename(file, context.current_file()))
             .unwrap_or_else(|| Some(context.current_file()));
+        self.report_link(
+            LinkKind::Link,
+            &reference,
+            target_file.map(PathBuf::as_path),
+            context,
+        );
 
         if target_file.is_none() {
             // TODO: Extract into configurable function.
-            eprintln!(
-                "Warning: Unable to find referenced note\n\tReference: '{}'\n\tSource: '{}'\n",
-                reference
-                    .file
-                    .unwrap_or_else(|| context.current_file().to_str().unwrap()),
-                context.current_file().display(),
+            self.warn(
+                Warning::new("Unable to find referenced note")
+                    .reference(
+                        reference
+                            .file
+                            .unwrap_or_else(|| context.current_file().to_str().unwrap()),
+                    )
+                    .source(context.current_file()),
+            );
+            self.record_issue(
+                IssueKind::BrokenLink,
+                context.current_file(),
+                reference.file.unwrap_or_default(),
             );
             return vec![
                 Event::Start(Tag::Emphasis),
@@ -684,21 +3694,16 @@ impl<'a> Exporter<'a> {
                 Event::End(Tag::Emphasis),
             ];
         }
-        let target_file = target_file.unwrap();
-        // We use root_file() rather than current_file() here to make sure links are always
-        // relative to the outer-most note, which is the note which this content is inserted into
-        // in case of embedded notes.
-        let rel_link = diff_paths(
-            target_file,
-            &context
-                .root_file()
-                .parent()
-                .expect("obsidian content files should always have a parent"),
-        )
-        .expect("should be able to build relative path when target file is found in vault");
+        self.make_link_to_path(reference, target_file.unwrap(), context)
+    }
 
-        let rel_link = rel_link.to_string_lossy();
-        let mut link = utf8_percent_encode(&rel_link, PERCENTENCODE_CHARS).to_string();
+    fn make_link_to_path<'b, 'c>(
+        &self,
+        reference: ObsidianNoteReference<'b>,
+        target_file: &Path,
+        context: &Context,
+    ) -> MarkdownEvents<'c> {
+        let mut link = self.link_to_path(target_file, context);
 
         if let Some(section) = reference.section {
             link.push('#');
@@ -711,34 +3716,265 @@ impl<'a> Exporter<'a> {
             CowStr::from(""),
         );
 
+        let text = match self.title_of(&reference, target_file) {
+            Some(title) => match reference.section {
+                Some(section) => format!("{} > {}", title, section),
+                None => title,
+            },
+            None => reference.display(),
+        };
+
         vec![
             Event::Start(link_tag.clone()),
-            Event::Text(CowStr::from(reference.display())),
+            Event::Text(CowStr::from(text)),
             Event::End(link_tag.clone()),
         ]
     }
+
+    // Return the link to the exported version of `target_file` from the note described by
+    // `context`.
+    fn link_to_path(&self, target_file: &Path, context: &Context) -> String {
+        let location = self.attachment_location(target_file, context.root_file());
+        let location = location.as_deref().unwrap_or(target_file);
+        if self.link_style == LinkStyle::AbsoluteFromRoot {
+            let relative_path = location
+                .strip_prefix(self.output_root())
+                .or_else(|_| location.strip_prefix(&self.root))
+                .unwrap_or(location);
+            return format!(
+                "{}/{}",
+                self.link_base.trim_end_matches('/'),
+                path_to_link(&self.output_path(target_file, relative_path))
+            );
+        }
+        // We use root_file() rather than current_file() here to make sure links are always
+        // relative to the outer-most note, which is the note which this content is inserted into
+        // in case of embedded notes.
+        let rel_link = diff_paths(
+            location,
+            context
+                .root_file()
+                .parent()
+                .expect("obsidian content files should always have a parent"),
+        )
+        .expect("should be able to build relative path when target file is found in vault");
+
+        path_to_link(&self.output_path(target_file, &rel_link))
+    }
+
+    // Rewrite references to attachments in a value of one of the frontmatter_attachment_keys, so
+    // they point to the exported attachment. Returns None when the value doesn't refer to a file
+    // in the vault.
+    fn rewrite_frontmatter_attachment(&self, value: &Value, context: &Context) -> Option<Value> {
+        lazy_static! {
+            static ref WIKILINK_VALUE_RE: Regex = Regex::new(r"^!?\[\[(?P<ref>.+)\]\]$").unwrap();
+        }
+        let vault_contents = self.vault_contents.as_ref().unwrap();
+        let reference = match value {
+            Value::String(reference) => reference.trim(),
+            // An unquoted `[[image.png]]` is read by YAML as a list within a list.
+            Value::Sequence(outer) => match outer.as_slice() {
+                [Value::Sequence(inner)] => match inner.as_slice() {
+                    [Value::String(reference)] => {
+                        let target = ObsidianNoteReference::from_str(reference).file?;
+                        let path = self.lookup_filename(target, context.current_file())?;
+                        return Some(Value::String(self.link_to_path(path, context)));
+                    }
+                    _ => return None,
+                },
+                values => {
+                    let values: Vec<Value> = values
+                        .iter()
+                        .map(|value| {
+                            self.rewrite_frontmatter_attachment(value, context)
+                                .unwrap_or_else(|| value.clone())
+                        })
+                        .collect();
+                    return Some(Value::Sequence(values));
+                }
+            },
+            _ => return None,
+        };
+        let target_file = match WIKILINK_VALUE_RE.captures(reference) {
+            Some(captures) => {
+                let target = ObsidianNoteReference::from_str(&captures["ref"]).file?;
+                self.lookup_filename(target, context.current_file())?
+            }
+            None if is_absolute_url(reference) => return None,
+            None => {
+                let path = percent_decode_str(reference).decode_utf8_lossy();
+                let target_file = normalize_path(
+                    &context
+                        .current_file()
+                        .parent()
+                        .expect("obsidian content files should always have a parent")
+                        .join(path.as_ref()),
+                );
+                vault_contents
+                    .iter()
+                    .find(|path| normalize_path(path) == target_file)?
+            }
+        };
+        Some(Value::String(self.link_to_path(target_file, context)))
+    }
+
+    // Return the frontmatter title of the note at `target_file`, when it should be used as the text
+    // of `reference` (see Exporter::link_text_from_title).
+    fn title_of(&self, reference: &ObsidianNoteReference, target_file: &Path) -> Option<String> {
+        if !self.link_text_from_title || reference.label.is_some() || reference.file.is_none() {
+            return None;
+        }
+        let title = self
+            .frontmatter_index
+            .as_ref()?
+            .get(target_file)?
+            .get(&Value::String("title".to_string()))?;
+        match title {
+            Value::Null => None,
+            title => Some(frontmatter_value_to_string(title)),
+        }
+    }
+
+    // Rewrite relative paths in the src/href attributes of raw HTML <img> and <a> tags, so they
+    // remain valid when the HTML ends up in a different note due to embedding.
+    //
+    // References which don't point to a file in the vault, such as links to files outside of it,
+    // are left as they are.
+    fn rewrite_html_paths(&self, html: &str, context: &Context) -> String {
+        rewrite_html_references(html, |value| {
+            if is_absolute_url(value) {
+                return None;
+            }
+            // The query string and fragment aren't part of the path, but are kept in the link.
+            let (path, suffix) = match value.find(['?', '#']) {
+                Some(index) => value.split_at(index),
+                None => (value, ""),
+            };
+            let path = percent_decode_str(path).decode_utf8_lossy();
+            let target_file = self.file_at(
+                &context
+                    .current_file()
+                    .parent()
+                    .expect("obsidian content files should always have a parent")
+                    .join(path.as_ref()),
+            )?;
+            let rel_link = diff_paths(
+                target_file,
+                context
+                    .root_file()
+                    .parent()
+                    .expect("obsidian content files should always have a parent"),
+            )
+            .expect("should be able to build relative path when target file is found in vault");
+            Some(format!(
+                "{}{}",
+                path_to_link(&self.output_path(target_file, &rel_link)),
+                suffix
+            ))
+        })
+    }
+}
+
+// Serialize a relative path into a (percent-encoded) link target.
+//
+// Components are always joined with forward slashes, so links don't end up containing
+// backslashes when exporting on Windows.
+fn path_to_link(path: &Path) -> String {
+    let link = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    utf8_percent_encode(&link, PERCENTENCODE_CHARS).to_string()
+}
+
+// Return true if `url` is not a relative path within the vault, such as `https://example.com`,
+// `//example.com`, `/absolute/path`, `mailto:` and `data:` URIs or a fragment-only `#anchor`.
+fn is_absolute_url(url: &str) -> bool {
+    lazy_static! {
+        static ref SCHEME_RE: Regex = Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:").unwrap();
+    }
+    url.is_empty() || url.starts_with('/') || url.starts_with('#') || SCHEME_RE.is_match(url)
 }
 
-fn lookup_filename_in_vault<'a>(
+// Lexically normalize a path, resolving `.` and `..` components without touching the filesystem.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// Return the indices of all files in `vault_contents` which `filename` may refer to, using the
+// `name_index` built from them (see Exporter::build_name_index) to find candidates.
+fn positions_in_vault(
     filename: &str,
-    vault_contents: &'a [PathBuf],
-) -> Option<&'a PathBuf> {
+    vault_contents: &[PathBuf],
+    name_index: &HashMap<String, Vec<usize>>,
+) -> Vec<usize> {
     // Markdown files don't have their .md extension added by Obsidian, but other files (images,
     // PDFs, etc) do so we match on both possibilities.
     //
     // References can also refer to notes in a different case (to lowercase text in a
     // sentence even if the note is capitalized for example) so we also try a case-insensitive
     // lookup.
-    vault_contents.iter().find(|path| {
-        let path_lowered = PathBuf::from(path.to_string_lossy().to_lowercase());
-        path.ends_with(&filename)
-            || path_lowered.ends_with(&filename.to_lowercase())
-            || path.ends_with(format!("{}.md", &filename))
-            || path_lowered.ends_with(format!("{}.md", &filename.to_lowercase()))
-    })
+    let note = format!("{}.md", filename);
+    let (filename_lowered, note_lowered) = (filename.to_lowercase(), note.to_lowercase());
+    let mut positions: Vec<usize> = [filename, note.as_str()]
+        .iter()
+        .filter_map(|name| name_index.get(&name_key(Path::new(name))?))
+        .flatten()
+        .copied()
+        .filter(|idx| {
+            let path = &vault_contents[*idx];
+            let path_lowered = PathBuf::from(path.to_string_lossy().to_lowercase());
+            path.ends_with(filename)
+                || path_lowered.ends_with(&filename_lowered)
+                || path.ends_with(&note)
+                || path_lowered.ends_with(&note_lowered)
+        })
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+    positions
 }
 
-fn render_mdevents_to_mdtext(markdown: MarkdownEvents) -> String {
+// Return the key of `path` in the name index: its lowercased filename.
+fn name_key(path: &Path) -> Option<String> {
+    Some(path.file_name()?.to_string_lossy().to_lowercase())
+}
+
+// Render a frontmatter value as plain text. Lists are joined with commas, other non-scalar values
+// are rendered as (inline) YAML.
+fn frontmatter_value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Null => String::new(),
+        Value::Sequence(values) => values
+            .iter()
+            .map(frontmatter_value_to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Mapping(_) => serde_yaml::to_string(value)
+            .unwrap_or_default()
+            .trim_start_matches("---")
+            .trim()
+            .to_string(),
+    }
+}
+
+pub(crate) fn render_mdevents_to_mdtext(markdown: MarkdownEvents) -> String {
     let mut buffer = String::new();
     cmark_with_options(
         markdown.iter(),
@@ -766,6 +4002,23 @@ fn create_file(dest: &Path) -> Result<File> {
     Ok(file)
 }
 
+// Whether `path` can be written to. Directories are checked by creating (and removing) a file in
+// them, as their permissions alone don't tell whether the current user may write to them.
+fn is_writable(path: &Path, is_dir: bool) -> bool {
+    if !is_dir {
+        return fs::OpenOptions::new().append(true).open(path).is_ok();
+    }
+    let probe = path.join(format!(".obsidian-export-{}", std::process::id()));
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+    {
+        Ok(_) => fs::remove_file(&probe).is_ok(),
+        Err(_) => false,
+    }
+}
+
 fn copy_file(src: &Path, dest: &Path) -> Result<()> {
     std::fs::copy(&src, &dest)
         .or_else(|err| {
@@ -781,6 +4034,38 @@ fn copy_file(src: &Path, dest: &Path) -> Result<()> {
     Ok(())
 }
 
+// Return the first error among `results`, in the order of the items these were produced from, so
+// the error reported doesn't depend on how work was scheduled across threads. Items after the
+// first error may not be processed.
+fn first_error(results: impl ParallelIterator<Item = Result<()>>) -> Result<()> {
+    results.find_first(Result::is_err).unwrap_or(Ok(()))
+}
+
+// Return true when the given events don't produce any visible content.
+fn is_empty_note(events: &[Event]) -> bool {
+    events.iter().all(|event| match event {
+        Event::Text(text) | Event::Code(text) | Event::Html(text) => text.trim().is_empty(),
+        Event::Start(Tag::Image(..)) | Event::Rule | Event::TaskListMarker(_) => false,
+        Event::FootnoteReference(_) => false,
+        _ => true,
+    })
+}
+
+fn is_base_file(file: &Path) -> bool {
+    file.extension().is_some_and(|ext| ext == "base")
+}
+
+// Compute the 64-bit FNV-1a hash of `bytes`.
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn is_svg_file(file: &Path) -> bool {
+    file.extension().is_some_and(|ext| ext == "svg")
+}
+
 fn is_markdown_file(file: &Path) -> bool {
     let no_ext = OsString::new();
     let ext = file.extension().unwrap_or(&no_ext).to_string_lossy();
@@ -789,55 +4074,375 @@ fn is_markdown_file(file: &Path) -> bool {
 
 /// Reduce a given `MarkdownEvents` to just those elements which are children of the given section
 /// (heading name).
+///
+/// Headings are matched on their plain text (ignoring formatting, leading and trailing whitespace
+/// and case) or on their slug, so both `Bold Title` and `bold-title` match `## **Bold** Title`.
 fn reduce_to_section<'a, 'b>(events: MarkdownEvents<'a>, section: &'b str) -> MarkdownEvents<'a> {
-    let mut filtered_events = Vec::with_capacity(events.len());
-    let mut target_section_encountered = false;
-    let mut currently_in_target_section = false;
-    let mut section_level = HeadingLevel::H1;
-    let mut last_level = HeadingLevel::H1;
-    let mut last_tag_was_heading = false;
+    let section = section.trim();
+    let mut current_heading: Option<(usize, HeadingLevel)> = None;
+    let mut heading_text = String::new();
+    let mut target_section: Option<(usize, HeadingLevel)> = None;
+    let mut section_end = events.len();
 
-    for event in events.into_iter() {
-        filtered_events.push(event.clone());
+    for (idx, event) in events.iter().enumerate() {
         match event {
             // FIXME: This should propagate fragment_identifier and classes.
             Event::Start(Tag::Heading(level, _fragment_identifier, _classes)) => {
-                last_tag_was_heading = true;
-                last_level = level;
-                if currently_in_target_section && level <= section_level {
-                    currently_in_target_section = false;
-                    filtered_events.pop();
+                if let Some((_, section_level)) = target_section {
+                    if *level <= section_level {
+                        section_end = idx;
+                        break;
+                    }
                 }
+                current_heading = Some((idx, *level));
+                heading_text.clear();
+            }
+            Event::Text(text) | Event::Code(text) if current_heading.is_some() => {
+                heading_text.push_str(text);
+            }
+            Event::End(Tag::Heading(..)) => {
+                let heading = current_heading.take();
+                if target_section.is_none() && heading_matches(&heading_text, section) {
+                    target_section = heading;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match target_section {
+        Some((section_start, _)) => events
+            .into_iter()
+            .take(section_end)
+            .skip(section_start)
+            .collect(),
+        None => events,
+    }
+}
+
+/// Reduce a given `MarkdownEvents` to the sections from the heading `from` up to, but excluding,
+/// the heading `to`, for embeds of the form `![[Note#From..To]]` where `section` is `From..To`.
+///
+/// Headings are matched like in [reduce_to_section]. `section` is only treated as a range when
+/// both `from` and a `to` following it match headings. Otherwise, a heading matching all of
+/// `section` (which may contain `..` itself) is reduced to like in [reduce_to_section]. Without
+/// such a heading, everything from `from` to the end of the note is kept, which is signaled by
+/// returning false. When `from` isn't found either, all events are returned.
+fn reduce_to_section_range<'a>(
+    events: MarkdownEvents<'a>,
+    section: &str,
+    from: &str,
+    to: &str,
+) -> (MarkdownEvents<'a>, bool) {
+    let mut headings = vec![];
+    let mut current_heading: Option<usize> = None;
+    let mut heading_text = String::new();
+    for (idx, event) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::Heading(..)) => {
+                current_heading = Some(idx);
+                heading_text.clear();
+            }
+            Event::Text(text) | Event::Code(text) if current_heading.is_some() => {
+                heading_text.push_str(text);
             }
-            Event::Text(cowstr) => {
-                if !last_tag_was_heading {
-                    last_tag_was_heading = false;
-                    continue;
+            Event::End(Tag::Heading(..)) => {
+                if let Some(start) = current_heading.take() {
+                    headings.push((start, heading_text.clone()));
                 }
-                last_tag_was_heading = false;
+            }
+            _ => {}
+        }
+    }
+
+    let start = headings
+        .iter()
+        .position(|(_, text)| heading_matches(text, from.trim()));
+    let end = start.and_then(|start| {
+        headings[start + 1..]
+            .iter()
+            .find(|(_, text)| heading_matches(text, to.trim()))
+            .map(|(idx, _)| *idx)
+    });
+    if end.is_none()
+        && headings
+            .iter()
+            .any(|(_, text)| heading_matches(text, section.trim()))
+    {
+        return (reduce_to_section(events, section), true);
+    }
+    let start = match start {
+        Some(start) => start,
+        None => return (events, true),
+    };
+    let range_start = headings[start].0;
+    let range_end = end.unwrap_or(events.len());
+    (
+        events
+            .into_iter()
+            .take(range_end)
+            .skip(range_start)
+            .collect(),
+        end.is_some(),
+    )
+}
+
+// Whether the plain text of a heading matches the section name `section`, either ignoring case or
+// on its slug.
+fn heading_matches(heading_text: &str, section: &str) -> bool {
+    let heading_text = heading_text.trim();
+    heading_text.to_lowercase() == section.to_lowercase() || slugify(heading_text) == section
+}
 
-                if cowstr.to_string().to_lowercase() == section.to_lowercase() {
-                    target_section_encountered = true;
-                    currently_in_target_section = true;
-                    section_level = last_level;
+// Surround `events` with the raw HTML `open` and `close`. The renderer separates these from the
+// content with a blank line, so markdown within them is still rendered.
+fn wrap_events<'a>(events: MarkdownEvents<'a>, open: &str, close: &str) -> MarkdownEvents<'a> {
+    let mut wrapped = Vec::with_capacity(events.len() + 2);
+    wrapped.push(Event::Html(CowStr::from(format!("{}\n", open))));
+    wrapped.extend(events);
+    wrapped.push(Event::Html(CowStr::from(close.to_string())));
+    wrapped
+}
+
+/// Reduce a given `MarkdownEvents` to just the block marked with the given block identifier.
+///
+/// A block is marked by ending it with `^block_id`. Blocks which can't hold the marker themselves,
+/// such as tables, are marked by putting it in a paragraph of its own right after them. A marker
+/// within a list marks only the list item it ends (along with the items nested in it). When no
+/// block has the given identifier, None is returned.
+fn reduce_to_block<'a>(events: MarkdownEvents<'a>, block_id: &str) -> Option<MarkdownEvents<'a>> {
+    let marker = format!("^{}", block_id);
+    let mut blocks: Vec<MarkdownEvents> = vec![];
+    let mut depth = 0;
+    for event in events.into_iter() {
+        if depth == 0 {
+            blocks.push(vec![]);
+        }
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+        blocks.last_mut().unwrap().push(event);
+    }
+
+    for idx in 0..blocks.len() {
+        let block = &blocks[idx];
+        if block.first() == Some(&Event::Start(Tag::Paragraph))
+            && block.len() == 3
+            && matches!(&block[1], Event::Text(text) if text.trim() == marker)
+        {
+            return match idx {
+                0 => Some(vec![]),
+                _ => Some(blocks.swap_remove(idx - 1)),
+            };
+        }
+        let marker_position = block.iter().rposition(|event| match event {
+            Event::Text(text) => text
+                .trim_end()
+                .strip_suffix(&marker)
+                .is_some_and(|text| text.ends_with(char::is_whitespace)),
+            _ => false,
+        });
+        if let Some(position) = marker_position {
+            let mut block = blocks.swap_remove(idx);
+            if let Event::Text(text) = &block[position] {
+                let text = text.trim_end();
+                let text = text[..text.len() - marker.len()].trim_end().to_string();
+                block[position] = Event::Text(CowStr::from(text));
+            }
+            return match block.first() {
+                Some(Event::Start(Tag::List(_))) => Some(reduce_to_list_item(block, position)),
+                _ => Some(block),
+            };
+        }
+    }
+    None
+}
+
+// Reduce the events of a list to the innermost list item containing the event at `position`,
+// keeping the list around it. Ordered lists are renumbered to start at the number of the item.
+fn reduce_to_list_item(block: MarkdownEvents, position: usize) -> MarkdownEvents {
+    let mut open = vec![];
+    for (idx, event) in block[..position].iter().enumerate() {
+        match event {
+            Event::Start(_) => open.push(idx),
+            Event::End(_) => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    let item = match open
+        .iter()
+        .rposition(|idx| block[*idx] == Event::Start(Tag::Item))
+    {
+        Some(item) if item > 0 => item,
+        _ => return block,
+    };
+    let (list_start, item_start) = (open[item - 1], open[item]);
 
-                    let current_event = filtered_events.pop().unwrap();
-                    let heading_start_event = filtered_events.pop().unwrap();
-                    filtered_events.clear();
-                    filtered_events.push(heading_start_event);
-                    filtered_events.push(current_event);
+    // Count the items of the list before this one, and find where this one ends.
+    let mut depth = 0;
+    let mut preceding = 0;
+    let mut item_end = block.len() - 1;
+    for (idx, event) in block.iter().enumerate().skip(list_start + 1) {
+        match event {
+            Event::Start(Tag::Item) if depth == 0 && idx < item_start => preceding += 1,
+            _ => {}
+        }
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => {
+                depth -= 1;
+                if idx > item_start && depth == 0 {
+                    item_end = idx;
+                    break;
                 }
             }
             _ => {}
         }
-        if target_section_encountered && !currently_in_target_section {
-            return filtered_events;
+    }
+    let list = match &block[list_start] {
+        Event::Start(Tag::List(Some(number))) => Tag::List(Some(number + preceding)),
+        _ => Tag::List(None),
+    };
+    let mut events = vec![Event::Start(list.clone())];
+    events.extend(block.into_iter().take(item_end + 1).skip(item_start));
+    events.push(Event::End(list));
+    events
+}
+
+/// Remove the `^block_id` markers at the end of blocks, or replace them with an anchor, depending
+/// on `style`. Markers in a paragraph of their own are removed along with the paragraph.
+fn convert_block_ids(events: MarkdownEvents, style: BlockIdStyle) -> MarkdownEvents {
+    lazy_static! {
+        static ref BLOCK_ID_RE: Regex = Regex::new(r"(^|\s)\^([A-Za-z0-9-]+)\s*$").unwrap();
+    }
+    let mut converted: MarkdownEvents = Vec::with_capacity(events.len());
+    let mut in_code_block = false;
+    let mut events = events.into_iter().peekable();
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            _ => {}
+        }
+        let text = match &event {
+            // Markers are only recognized at the very end of a block.
+            Event::Text(text)
+                if !in_code_block
+                    && matches!(
+                        events.peek(),
+                        Some(Event::End(Tag::Paragraph)) | Some(Event::End(Tag::Item))
+                    ) =>
+            {
+                text.to_string()
+            }
+            _ => {
+                converted.push(event);
+                continue;
+            }
+        };
+        // Without whitespace in front, a marker has to start the line.
+        let captures = BLOCK_ID_RE.captures(&text).filter(|captures| {
+            !captures[1].is_empty()
+                || matches!(
+                    converted.last(),
+                    Some(Event::Start(_)) | Some(Event::SoftBreak)
+                )
+        });
+        let captures = match captures {
+            Some(captures) => captures,
+            None => {
+                converted.push(event);
+                continue;
+            }
+        };
+        let prefix = text[..captures.get(0).unwrap().start()]
+            .trim_end()
+            .to_string();
+        let block_id = &captures[2];
+        if prefix.is_empty() && converted.last() == Some(&Event::SoftBreak) {
+            converted.pop();
+        }
+        if !prefix.is_empty() {
+            converted.push(Event::Text(CowStr::from(prefix)));
+        }
+        match style {
+            BlockIdStyle::Anchor => converted.push(Event::Html(CowStr::from(format!(
+                "<span id=\"{}\"></span>",
+                slugify(block_id)
+            )))),
+            BlockIdStyle::Strip
+                if converted.last() == Some(&Event::Start(Tag::Paragraph))
+                    && events.peek() == Some(&Event::End(Tag::Paragraph)) =>
+            {
+                converted.pop();
+                events.next();
+            }
+            _ => {}
+        }
+    }
+    converted
+}
+
+/// Keep only the given columns (counting from 1) of the tables in `events`.
+fn select_table_columns<'a>(events: MarkdownEvents<'a>, columns: &[usize]) -> MarkdownEvents<'a> {
+    let select_alignments = |alignments: Vec<Alignment>| {
+        alignments
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, _)| columns.contains(&(idx + 1)))
+            .map(|(_, alignment)| alignment)
+            .collect()
+    };
+    let mut selected_events = Vec::with_capacity(events.len());
+    let mut column = 0;
+    let mut in_skipped_cell = false;
+    for event in events.into_iter() {
+        match event {
+            Event::Start(Tag::Table(alignments)) => {
+                selected_events.push(Event::Start(Tag::Table(select_alignments(alignments))));
+            }
+            Event::End(Tag::Table(alignments)) => {
+                selected_events.push(Event::End(Tag::Table(select_alignments(alignments))));
+            }
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                column = 0;
+                selected_events.push(event);
+            }
+            Event::Start(Tag::TableCell) => {
+                column += 1;
+                in_skipped_cell = !columns.contains(&column);
+                if !in_skipped_cell {
+                    selected_events.push(event);
+                }
+            }
+            Event::End(Tag::TableCell) => {
+                if !in_skipped_cell {
+                    selected_events.push(event);
+                }
+                in_skipped_cell = false;
+            }
+            event if !in_skipped_cell => selected_events.push(event),
+            _ => {}
         }
     }
-    filtered_events
+    selected_events
+}
+
+// The markdown extensions which are enabled when parsing notes.
+pub(crate) fn parser_options() -> Options {
+    let mut parser_options = Options::empty();
+    parser_options.insert(Options::ENABLE_TABLES);
+    parser_options.insert(Options::ENABLE_FOOTNOTES);
+    parser_options.insert(Options::ENABLE_STRIKETHROUGH);
+    parser_options.insert(Options::ENABLE_TASKLISTS);
+    parser_options
 }
 
-fn event_to_owned<'a>(event: Event) -> Event<'a> {
+pub(crate) fn event_to_owned<'a>(event: Event) -> Event<'a> {
     match event {
         Event::Start(tag) => Event::Start(tag_to_owned(tag)),
         Event::End(tag) => Event::End(tag_to_owned(tag)),
@@ -894,3 +4499,24 @@ fn codeblock_kind_to_owned<'a>(codeblock_kind: CodeBlockKind) -> CodeBlockKind<'
         CodeBlockKind::Fenced(cowstr) => CodeBlockKind::Fenced(CowStr::from(cowstr.into_string())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::Component;
+
+    #[test]
+    fn links_use_forward_slashes() {
+        let path: PathBuf = [
+            Component::ParentDir,
+            Component::Normal("folder".as_ref()),
+            Component::Normal("my note.md".as_ref()),
+        ]
+        .iter()
+        .collect();
+        let link = path_to_link(&path);
+        assert_eq!(link, "../folder/my%20note.md");
+        assert!(!link.contains('\\'));
+    }
+}
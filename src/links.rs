@@ -0,0 +1,59 @@
+use crate::references::ObsidianNoteReference;
+use crate::{Context, Exporter};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The kind of reference a [LinkEvent] is about.
+pub enum LinkKind {
+    /// A link to a note or file, such as `[[Note]]`.
+    Link,
+    /// An embed of a note or file, such as `![[Note]]`.
+    Embed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A reference in a note and the outcome of resolving it, as passed to the [LinkCallback] set
+/// through [Exporter::on_link][crate::Exporter::on_link].
+pub struct LinkEvent {
+    /// Whether the reference is a link or an embed.
+    pub kind: LinkKind,
+    /// The note containing the reference. For references in embedded notes, this is the embedded
+    /// note rather than the note it's embedded into.
+    pub source: PathBuf,
+    /// The file being referenced, as written in the note. This is None for references to a section
+    /// within the same note.
+    pub target: Option<String>,
+    /// The section or block being referenced, if any.
+    pub section: Option<String>,
+    /// The file in the vault the reference resolved to, or None when no such file was found.
+    pub resolved: Option<PathBuf>,
+}
+
+/// A function which is called for every link and embed as it's resolved.
+///
+/// Notes are exported in parallel, so this may be called from several threads at once.
+pub type LinkCallback = dyn Fn(&LinkEvent) + Send + Sync;
+
+impl<'a> Exporter<'a> {
+    // Report the resolution of `reference`, found in the note described by `context`, to the
+    // callback set through Exporter::on_link.
+    pub(crate) fn report_link(
+        &self,
+        kind: LinkKind,
+        reference: &ObsidianNoteReference,
+        resolved: Option<&Path>,
+        context: &Context,
+    ) {
+        let callback = match self.link_callback {
+            Some(callback) => callback,
+            None => return,
+        };
+        callback(&LinkEvent {
+            kind,
+            source: context.current_file().to_path_buf(),
+            target: reference.file.map(str::to_string),
+            section: reference.section.map(str::to_string),
+            resolved: resolved.map(Path::to_path_buf),
+        });
+    }
+}
@@ -0,0 +1,34 @@
+use crate::Exporter;
+use serde::Deserialize;
+use std::io::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// How much is written to the log file set through [Exporter::log_file][crate::Exporter::log_file].
+pub enum LogLevel {
+    /// Only warnings, such as for links to notes which don't exist.
+    Warning,
+    /// Warnings, along with a line for every note exported, attachment copied and file skipped.
+    Info,
+}
+
+impl<'a> Exporter<'a> {
+    // Write `message` to the log file of the current run, if any, when it's at or above the
+    // configured level. Messages spanning several lines are joined into a single line with tabs.
+    pub(crate) fn log(&self, level: LogLevel, message: &str) {
+        match &self.log_file {
+            Some((_, max_level)) if level <= *max_level => {}
+            _ => return,
+        }
+        let line = message
+            .trim_end()
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\t");
+        if let Some(file) = self.log.lock().expect("log lock poisoned").as_mut() {
+            // Failing to write the log shouldn't fail the export itself.
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
@@ -1,8 +1,13 @@
 use eyre::{eyre, Result};
 use gumdrop::Options;
-use obsidian_export::postprocessors::softbreaks_to_hardbreaks;
-use obsidian_export::{ExportError, Exporter, FrontmatterStrategy, WalkOptions};
-use std::{env, path::PathBuf};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use obsidian_export::{
+    Config, EncodingErrorStrategy, ExportError, Exporter, FrontmatterStrategy, LogLevel,
+    OutputFormat, Progress, ProgressPhase, SharedAttachments,
+};
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::{env, fs, path::PathBuf};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -14,46 +19,233 @@ struct Opts {
     #[options(help = "Display version information")]
     version: bool,
 
+    #[options(
+        no_short,
+        help = "Read options from this TOML file, with keys named after the long options (such as max_depth = 2). Options given on the command line take precedence, flags may be disabled with --flag=false",
+        meta = "FILE"
+    )]
+    config: Option<PathBuf>,
+
     #[options(help = "Read notes from this source", free, required)]
     source: Option<PathBuf>,
 
-    #[options(help = "Write notes to this destination", free, required)]
+    #[options(
+        help = "Write notes to this destination (not needed with --check)",
+        free
+    )]
     destination: Option<PathBuf>,
 
+    #[options(
+        no_short,
+        help = "Check the vault for broken links and other problems instead of exporting it",
+        default = "false"
+    )]
+    check: bool,
+
     #[options(no_short, help = "Only export notes under this sub-path")]
     start_at: Option<PathBuf>,
 
     #[options(
-        help = "Frontmatter strategy (one of: always, never, auto)",
+        no_short,
+        help = "Keep the --start-at sub-path in the destination instead of exporting to its root",
+        default = "false"
+    )]
+    keep_start_at_path: bool,
+
+    #[options(
+        no_short,
+        help = "Only export the files listed in this file (one path per line, relative to the source)",
+        meta = "FILE"
+    )]
+    files_from: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        help = "Only export notes with this tag (may be given multiple times)",
+        meta = "TAG"
+    )]
+    tag: Vec<String>,
+
+    #[options(
+        help = "Frontmatter strategy (one of: always, never, auto) (default: auto)",
         no_short,
         long = "frontmatter",
         parse(try_from_str = "frontmatter_strategy_from_str"),
-        default = "auto"
+        meta = "FRONTMATTER-STRATEGY"
+    )]
+    frontmatter_strategy: Option<FrontmatterStrategy>,
+
+    #[options(
+        no_short,
+        help = "Keep comments in frontmatter which isn't modified by the export",
+        default = "false"
+    )]
+    keep_frontmatter_comments: bool,
+
+    #[options(
+        no_short,
+        help = "With --frontmatter=never, list frontmatter in a table at the top of notes instead",
+        default = "false"
+    )]
+    frontmatter_as_table: bool,
+
+    #[options(
+        help = "Output format (one of: markdown, html) (default: markdown)",
+        no_short,
+        long = "output-format",
+        parse(try_from_str = "output_format_from_str"),
+        meta = "OUTPUT-FORMAT"
     )]
-    frontmatter_strategy: FrontmatterStrategy,
+    output_format: Option<OutputFormat>,
 
     #[options(
         no_short,
-        help = "Read ignore patterns from files with this name",
-        default = ".export-ignore"
+        help = "Wrap notes in this HTML template when using --output-format=html",
+        meta = "FILE"
     )]
-    ignore_file: String,
+    html_template: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        help = "Read ignore patterns from files with this name (default: .export-ignore)",
+        meta = "IGNORE-FILE"
+    )]
+    ignore_file: Option<String>,
+
+    #[options(
+        help = "How to handle notes that aren't valid UTF-8 (one of: fail, lossy, skip) (default: fail)",
+        no_short,
+        long = "encoding-errors",
+        parse(try_from_str = "encoding_error_strategy_from_str"),
+        meta = "ENCODING-ERROR-STRATEGY"
+    )]
+    encoding_error_strategy: Option<EncodingErrorStrategy>,
 
     #[options(no_short, help = "Export hidden files", default = "false")]
     hidden: bool,
 
+    #[options(
+        no_short,
+        help = "Copy the vault's .obsidian configuration directory as well",
+        default = "false"
+    )]
+    include_obsidian_config: bool,
+
     #[options(no_short, help = "Disable git integration", default = "false")]
     no_git: bool,
 
+    #[options(
+        no_short,
+        help = "Don't descend more than this many directories deep (1 exports only top-level notes)"
+    )]
+    max_depth: Option<usize>,
+
+    #[options(
+        no_short,
+        help = "Only export directories containing a file with this name (such as .publish)",
+        meta = "FILENAME"
+    )]
+    publish_marker: Option<String>,
+
+    #[options(
+        no_short,
+        help = "Don't copy attachments larger than this size (such as 500KB or 10MB)",
+        parse(try_from_str = "size_from_str"),
+        meta = "SIZE"
+    )]
+    max_attachment_size: Option<u64>,
+
+    #[options(
+        no_short,
+        help = "Only export notes, without copying attachments",
+        default = "false"
+    )]
+    notes_only: bool,
+
+    #[options(
+        no_short,
+        help = "Copy attachments into a folder next to the notes using them, such as {note}/attachments",
+        meta = "PATTERN"
+    )]
+    colocate_attachments: Option<String>,
+
+    #[options(
+        no_short,
+        help = "Where to put co-located attachments used by several notes [centralize, duplicate] (default: centralize)",
+        parse(try_from_str = "shared_attachments_from_str"),
+        meta = "SHARED-ATTACHMENTS"
+    )]
+    shared_attachments: Option<SharedAttachments>,
+
     #[options(no_short, help = "Don't process embeds recursively", default = "false")]
     no_recursive_embeds: bool,
 
+    #[options(
+        no_short,
+        help = "Don't export notes without any content",
+        default = "false"
+    )]
+    skip_empty_notes: bool,
+
     #[options(
         no_short,
         help = "Convert soft line breaks to hard line breaks. This mimics Obsidian's 'Strict line breaks' setting",
         default = "false"
     )]
     hard_linebreaks: bool,
+
+    #[options(
+        no_short,
+        help = "Run the WebAssembly module at this path as a postprocessor (may be given multiple times, requires the wasm feature)",
+        meta = "PATH"
+    )]
+    wasm_postprocessor: Vec<PathBuf>,
+
+    #[options(
+        no_short,
+        help = "Render Obsidian Bases (.base files) as tables of matching notes",
+        default = "false"
+    )]
+    render_bases: bool,
+
+    #[options(
+        no_short,
+        help = "Number of threads to export notes with (0 uses one per logical CPU) (default: 0)",
+        meta = "N"
+    )]
+    threads: Option<usize>,
+
+    #[options(
+        no_short,
+        help = "Print a breakdown of the time spent in each stage of the export",
+        default = "false"
+    )]
+    profile: bool,
+
+    #[options(help = "Don't show a progress bar", default = "false")]
+    quiet: bool,
+
+    #[options(
+        no_short,
+        help = "Print warnings as GitHub Actions annotations",
+        default = "false"
+    )]
+    github_annotations: bool,
+
+    #[options(
+        no_short,
+        help = "Write a log of the export to this file",
+        meta = "FILE"
+    )]
+    log_file: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        help = "What to write to the log file [warning, info] (default: warning)",
+        parse(try_from_str = "log_level_from_str"),
+        meta = "LOG-LEVEL"
+    )]
+    log_level: Option<LogLevel>,
 }
 
 fn frontmatter_strategy_from_str(input: &str) -> Result<FrontmatterStrategy> {
@@ -65,6 +257,215 @@ fn frontmatter_strategy_from_str(input: &str) -> Result<FrontmatterStrategy> {
     }
 }
 
+fn output_format_from_str(input: &str) -> Result<OutputFormat> {
+    match input {
+        "markdown" => Ok(OutputFormat::Markdown),
+        "html" => Ok(OutputFormat::Html),
+        _ => Err(eyre!("must be one of: markdown, html")),
+    }
+}
+
+fn log_level_from_str(input: &str) -> Result<LogLevel> {
+    match input {
+        "warning" => Ok(LogLevel::Warning),
+        "info" => Ok(LogLevel::Info),
+        _ => Err(eyre!("must be one of: warning, info")),
+    }
+}
+
+fn shared_attachments_from_str(input: &str) -> Result<SharedAttachments> {
+    match input {
+        "centralize" => Ok(SharedAttachments::Centralize),
+        "duplicate" => Ok(SharedAttachments::Duplicate),
+        _ => Err(eyre!("must be one of: centralize, duplicate")),
+    }
+}
+
+fn encoding_error_strategy_from_str(input: &str) -> Result<EncodingErrorStrategy> {
+    match input {
+        "fail" => Ok(EncodingErrorStrategy::Fail),
+        "lossy" => Ok(EncodingErrorStrategy::Lossy),
+        "skip" => Ok(EncodingErrorStrategy::Skip),
+        _ => Err(eyre!("must be one of: fail, lossy, skip")),
+    }
+}
+
+// Parse a size in bytes, optionally followed by a decimal (KB, MB, GB) or binary (KiB, MiB, GiB)
+// unit.
+fn size_from_str(input: &str) -> Result<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "m" | "mb" => 1_000_000,
+        "g" | "gb" => 1_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => return Err(eyre!("unknown unit '{}'", unit.trim())),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| eyre!("must be a size such as 500KB or 10MB"))?;
+    Ok((number * multiplier as f64) as u64)
+}
+
+// Progress bars for the notes and attachments of an export, drawn on stderr. Clones draw the same
+// bars.
+#[derive(Clone)]
+struct ProgressBars {
+    bars: MultiProgress,
+    notes: ProgressBar,
+    attachments: ProgressBar,
+}
+
+impl ProgressBars {
+    fn new(visible: bool) -> ProgressBars {
+        let bars = MultiProgress::with_draw_target(match visible {
+            true => ProgressDrawTarget::stderr(),
+            false => ProgressDrawTarget::hidden(),
+        });
+        let style = ProgressStyle::with_template("{prefix:>11} [{bar:20}] {pos}/{len}")
+            .expect("progress bar template should be valid")
+            .progress_chars("#>-");
+        let add_bar = |label| {
+            bars.add(
+                ProgressBar::new(0)
+                    .with_style(style.clone())
+                    .with_prefix(label),
+            )
+        };
+        let notes = add_bar("Notes");
+        let attachments = add_bar("Attachments");
+        ProgressBars {
+            bars,
+            notes,
+            attachments,
+        }
+    }
+
+    fn update(&self, progress: Progress) {
+        let bar = match progress.phase {
+            ProgressPhase::Notes => &self.notes,
+            ProgressPhase::Attachments => &self.attachments,
+        };
+        bar.set_length(progress.total as u64);
+        // Updates from different threads may arrive out of order.
+        if progress.done as u64 > bar.position() {
+            bar.set_position(progress.done as u64);
+        }
+    }
+
+    // Print `message` above the progress bars, so it doesn't end up in the middle of them.
+    fn println(&self, message: &str) {
+        let _ = self.bars.println(message);
+    }
+
+    fn finish(&self) {
+        self.notes.finish();
+        self.attachments.finish();
+    }
+}
+
+// Flags which may be set in the config file. Since a flag can't be turned off on the command line
+// once the config file enables it, these also accept an explicit value (`--hidden=false`).
+const CONFIG_FLAGS: &[&str] = &[
+    "keep-start-at-path",
+    "keep-frontmatter-comments",
+    "frontmatter-as-table",
+    "hidden",
+    "include-obsidian-config",
+    "no-git",
+    "notes-only",
+    "no-recursive-embeds",
+    "skip-empty-notes",
+    "hard-linebreaks",
+    "render-bases",
+    "profile",
+    "github-annotations",
+];
+
+// Take the flags given with an explicit value (`--hidden=false`) out of `args`, which Gumdrop
+// doesn't accept, returning the remaining arguments and the values of these flags.
+fn split_flag_values(args: Vec<String>) -> Result<(Vec<String>, HashMap<String, bool>)> {
+    let mut remaining = vec![];
+    let mut flags = HashMap::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            remaining.push(arg);
+            remaining.extend(args.by_ref());
+            break;
+        }
+        let flag = arg
+            .strip_prefix("--")
+            .and_then(|arg| arg.split_once('='))
+            .filter(|(name, _)| CONFIG_FLAGS.contains(name));
+        match flag {
+            Some((name, value)) => {
+                let value = value
+                    .parse()
+                    .map_err(|_| eyre!("option `--{}` must be true or false", name))?;
+                flags.insert(name.to_string(), value);
+            }
+            None => remaining.push(arg),
+        }
+    }
+    Ok((remaining, flags))
+}
+
+// Read the config file at `path`.
+fn load_config(path: &PathBuf) -> Result<Config> {
+    let config = fs::read_to_string(path)?;
+    Ok(toml::from_str(&config)?)
+}
+
+// Return the settings given on the command line, leaving out those which weren't given so that
+// they don't override the config file.
+fn cli_config(args: &Opts, flags: &HashMap<String, bool>) -> Config {
+    let flag = |name: &str, enabled: bool| match enabled {
+        true => Some(true),
+        false => flags.get(name).copied(),
+    };
+    Config {
+        start_at: args.start_at.clone(),
+        keep_start_at_path: flag("keep-start-at-path", args.keep_start_at_path),
+        tag: Some(args.tag.clone()).filter(|tags| !tags.is_empty()),
+        frontmatter_strategy: args.frontmatter_strategy,
+        keep_frontmatter_comments: flag(
+            "keep-frontmatter-comments",
+            args.keep_frontmatter_comments,
+        ),
+        frontmatter_as_table: flag("frontmatter-as-table", args.frontmatter_as_table),
+        output_format: args.output_format,
+        html_template: args.html_template.clone(),
+        ignore_file: args.ignore_file.clone(),
+        encoding_error_strategy: args.encoding_error_strategy,
+        hidden: flag("hidden", args.hidden),
+        include_obsidian_config: flag("include-obsidian-config", args.include_obsidian_config),
+        no_git: flag("no-git", args.no_git),
+        max_depth: args.max_depth,
+        publish_marker: args.publish_marker.clone(),
+        max_attachment_size: args.max_attachment_size,
+        notes_only: flag("notes-only", args.notes_only),
+        colocate_attachments: args.colocate_attachments.clone(),
+        shared_attachments: args.shared_attachments,
+        no_recursive_embeds: flag("no-recursive-embeds", args.no_recursive_embeds),
+        skip_empty_notes: flag("skip-empty-notes", args.skip_empty_notes),
+        hard_linebreaks: flag("hard-linebreaks", args.hard_linebreaks),
+        render_bases: flag("render-bases", args.render_bases),
+        threads: args.threads,
+        profile: flag("profile", args.profile),
+        github_annotations: flag("github-annotations", args.github_annotations),
+        log_file: args.log_file.clone(),
+        log_level: args.log_level,
+    }
+}
+
 fn main() {
     // Due to the use of free arguments in Opts, we must bypass Gumdrop to determine whether the
     // version flag was specified. Without this, "missing required free argument" would get printed
@@ -74,31 +475,117 @@ fn main() {
         std::process::exit(0);
     }
 
-    let args = Opts::parse_args_default_or_exit();
+    let program = env::args().next().unwrap_or_default();
+    let (cli_args, flags) =
+        split_flag_values(env::args().skip(1).collect()).unwrap_or_else(|err| {
+            eprintln!("{}: {}", program, err);
+            std::process::exit(2);
+        });
+    let args = Opts::parse_args_default(&cli_args).unwrap_or_else(|err| {
+        eprintln!("{}: {}", program, err);
+        std::process::exit(2);
+    });
+    if args.help_requested() {
+        println!("Usage: {} [OPTIONS]", program);
+        println!();
+        println!("{}", Opts::usage());
+        std::process::exit(0);
+    }
+    // Options given on the command line take precedence over those in the config file, which in
+    // turn take precedence over the defaults.
+    let file_config = match &args.config {
+        Some(path) => load_config(path).unwrap_or_else(|err| {
+            eprintln!(
+                "Error: {:?}",
+                err.wrap_err(format!("failed to load config file '{}'", path.display()))
+            );
+            std::process::exit(2);
+        }),
+        None => Config::default(),
+    };
+    let config = file_config.merge(cli_config(&args, &flags));
+
     let root = args.source.unwrap();
-    let destination = args.destination.unwrap();
+    let destination = match (args.destination, args.check) {
+        (Some(destination), _) => destination,
+        (None, true) => PathBuf::new(),
+        (None, false) => {
+            eprintln!("{}: missing required free argument", program);
+            std::process::exit(2);
+        }
+    };
 
-    let walk_options = WalkOptions {
-        ignore_filename: &args.ignore_file,
-        ignore_hidden: !args.hidden,
-        honor_gitignore: !args.no_git,
-        ..Default::default()
+    // Only draw a progress bar for people watching the export, not when the output is redirected.
+    let show_progress = !args.quiet && std::io::stderr().is_terminal();
+    let progress_bars = ProgressBars::new(show_progress);
+    let update_progress = {
+        let progress_bars = progress_bars.clone();
+        move |progress| progress_bars.update(progress)
+    };
+    let print_warning = {
+        let progress_bars = progress_bars.clone();
+        move |warning: &str| progress_bars.println(warning)
     };
 
-    let mut exporter = Exporter::new(root, destination);
-    exporter.frontmatter_strategy(args.frontmatter_strategy);
-    exporter.process_embeds_recursively(!args.no_recursive_embeds);
-    exporter.walk_options(walk_options);
+    let mut exporter = Exporter::from_config(root, destination, &config);
 
-    if args.hard_linebreaks {
-        exporter.add_postprocessor(&softbreaks_to_hardbreaks);
+    if let Some(path) = args.files_from {
+        let list = fs::read_to_string(&path).unwrap_or_else(|err| {
+            eprintln!(
+                "Error: {:?}",
+                eyre!(err).wrap_err(format!("failed to read file list '{}'", path.display()))
+            );
+            std::process::exit(2);
+        });
+        exporter.export_files(
+            list.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect(),
+        );
     }
 
-    if let Some(path) = args.start_at {
-        exporter.start_at(path);
+    for path in args.wasm_postprocessor {
+        add_wasm_postprocessor(&mut exporter, path);
     }
 
-    if let Err(err) = exporter.run() {
+    if args.check {
+        // Issues are printed below, printing the warnings about these as well would list them
+        // twice.
+        exporter.warnings_to_stderr(false);
+        let issues = exporter.check().unwrap_or_else(|err| {
+            eprintln!("Error: {:?}", eyre!(err));
+            std::process::exit(1);
+        });
+        for issue in &issues {
+            println!("{}", issue);
+        }
+        if !issues.is_empty() {
+            eprintln!("Found {} issues", issues.len());
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if show_progress {
+        exporter.on_progress(&update_progress);
+        exporter.on_warning(&print_warning);
+    }
+
+    if let Err(errors) = exporter.validate() {
+        for err in errors {
+            eprintln!("Error: {:?}", eyre!(err));
+        }
+        std::process::exit(1);
+    }
+
+    let summary = exporter.run_with_summary();
+    if show_progress {
+        progress_bars.finish();
+        eprintln!();
+    }
+    let summary = summary.unwrap_or_else(|err| {
         match err {
             ExportError::FileExportError {
                 ref path,
@@ -126,5 +613,142 @@ fn main() {
             _ => eprintln!("Error: {:?}", eyre!(err)),
         };
         std::process::exit(1);
-    };
+    });
+
+    if show_progress {
+        eprintln!(
+            "Exported {} notes and copied {} attachments in {:.2}s (warnings: {})",
+            summary.notes_exported,
+            summary.attachments_copied,
+            summary.duration.as_secs_f64(),
+            summary.warnings
+        );
+    }
+
+    if let Some(profile) = exporter.profile_report() {
+        eprint!("{}", profile);
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn add_wasm_postprocessor(exporter: &mut Exporter, path: PathBuf) {
+    if let Err(err) = exporter.add_wasm_postprocessor(path) {
+        eprintln!("Error: {:?}", eyre!(err));
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "wasm"))]
+fn add_wasm_postprocessor(_exporter: &mut Exporter, path: PathBuf) {
+    eprintln!(
+        "Error: can't load WASM postprocessor '{}': obsidian-export was built without the wasm feature",
+        path.display()
+    );
+    std::process::exit(2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(size_from_str("512").unwrap(), 512);
+        assert_eq!(size_from_str("10MB").unwrap(), 10_000_000);
+        assert_eq!(size_from_str("1.5 KiB").unwrap(), 1536);
+        assert!(size_from_str("10 parsecs").is_err());
+        assert!(size_from_str("MB").is_err());
+    }
+
+    // Parse `cli_args` and merge them into `config` like main does.
+    fn effective_config(config: &str, cli_args: &[&str]) -> Config {
+        let (cli_args, flags) = split_flag_values(args(cli_args)).unwrap();
+        let opts = Opts::parse_args_default(&cli_args).unwrap();
+        let config: Config = toml::from_str(config).unwrap();
+        config.merge(cli_config(&opts, &flags))
+    }
+
+    #[test]
+    fn cli_options_override_config() {
+        let config = r#"
+# Export settings for the blog
+frontmatter = "never"
+max_depth = 2
+hidden = true
+no_git = false
+tag = [
+    "blog",
+    'public', # only published notes
+]
+encoding_errors = "lossy"
+"#;
+        let effective = effective_config(config, &["--config", "export.toml", "vault", "out"]);
+        assert_eq!(effective.max_depth, Some(2));
+        assert_eq!(effective.hidden, Some(true));
+        assert_eq!(effective.no_git, Some(false));
+        assert_eq!(effective.tag, Some(vec!["blog".into(), "public".into()]));
+        assert!(matches!(
+            effective.frontmatter_strategy,
+            Some(FrontmatterStrategy::Never)
+        ));
+        assert_eq!(
+            effective.encoding_error_strategy,
+            Some(EncodingErrorStrategy::Lossy)
+        );
+
+        let effective = effective_config(
+            config,
+            &[
+                "--max-depth=3",
+                "--tag=draft",
+                "--frontmatter=auto",
+                "--hidden=false",
+                "--no-git",
+                "vault",
+                "out",
+            ],
+        );
+        assert_eq!(effective.max_depth, Some(3));
+        assert_eq!(effective.tag, Some(vec!["draft".into()]));
+        assert!(matches!(
+            effective.frontmatter_strategy,
+            Some(FrontmatterStrategy::Auto)
+        ));
+        assert_eq!(effective.hidden, Some(false));
+        assert_eq!(effective.no_git, Some(true));
+        assert_eq!(
+            effective.encoding_error_strategy,
+            Some(EncodingErrorStrategy::Lossy)
+        );
+    }
+
+    #[test]
+    fn separate_option_values_override_config() {
+        let effective = effective_config("threads = 4", &["--threads", "2", "vault", "out"]);
+        assert_eq!(effective.threads, Some(2));
+    }
+
+    #[test]
+    fn config_flags_are_flags() {
+        for flag in CONFIG_FLAGS {
+            let cli_args = args(&[&format!("--{}", flag), "vault", "out"]);
+            assert!(Opts::parse_args_default(&cli_args).is_ok(), "{}", flag);
+            let cli_args = args(&[&format!("--{}=false", flag), "vault", "out"]);
+            let (_, flags) = split_flag_values(cli_args).unwrap();
+            assert_eq!(flags.get(*flag), Some(&false), "{}", flag);
+        }
+        assert!(split_flag_values(args(&["--hidden=maybe", "vault", "out"])).is_err());
+    }
+
+    #[test]
+    fn invalid_config() {
+        assert!(toml::from_str::<Config>("source = \"vault\"").is_err());
+        assert!(toml::from_str::<Config>("max_depth = \"two\"").is_err());
+        assert!(toml::from_str::<Config>("frontmatter = \"sometimes\"").is_err());
+    }
 }
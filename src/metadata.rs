@@ -0,0 +1,58 @@
+use crate::json::json_string;
+use crate::postprocessors::count_words;
+use crate::{is_absolute_url, path_to_link, Context, Exporter, MarkdownEvents, Result, WriteError};
+use pulldown_cmark::{Event, Tag};
+use snafu::ResultExt;
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+impl<'a> Exporter<'a> {
+    // Write the metadata of the note described by `context` and `events` to a JSON file next to
+    // the note.
+    pub(crate) fn write_metadata_sidecar(
+        &self,
+        context: &Context,
+        events: &MarkdownEvents,
+    ) -> Result<()> {
+        let relative_path = context
+            .destination
+            .strip_prefix(&self.destination)
+            .unwrap_or(&context.destination);
+        let mut links: Vec<String> = vec![];
+        for event in events {
+            if let Event::Start(Tag::Link(_, target, _)) = event {
+                if !is_absolute_url(target) && !links.iter().any(|link| link == target.as_ref()) {
+                    links.push(target.to_string());
+                }
+            }
+        }
+        let mut embeds: Vec<String> = vec![];
+        for embed in &context.embeds {
+            let link = self.link_to_path(embed, context);
+            if !embeds.contains(&link) {
+                embeds.push(link);
+            }
+        }
+
+        let output = format!(
+            "{{\n  \"url\": {},\n  \"tags\": {},\n  \"word_count\": {},\n  \"links\": {},\n  \"embeds\": {}\n}}\n",
+            json_string(&path_to_link(relative_path)),
+            json_list(&context.tags()),
+            count_words(events),
+            json_list(&links),
+            json_list(&embeds),
+        );
+        let mut dest = context.destination.clone().into_os_string();
+        dest.push(".json");
+        let dest = PathBuf::from(dest);
+        self.create_output(&dest)?
+            .write_all(output.as_bytes())
+            .context(WriteError { path: &dest })
+    }
+}
+
+// Encode `values` as a JSON array of strings.
+fn json_list(values: &[String]) -> String {
+    let values: Vec<String> = values.iter().map(|value| json_string(value)).collect();
+    format!("[{}]", values.join(", "))
+}
@@ -0,0 +1,95 @@
+use crate::bases::compare_values;
+use crate::Context;
+use serde_yaml::Value;
+use std::cmp::Ordering;
+use std::fs;
+
+#[derive(Debug, Clone, Default)]
+/// The order in which notes appear in output which combines multiple notes, such as
+/// [Exporter::concatenate_to][crate::Exporter::concatenate_to].
+///
+/// Notes which compare equal are always ordered by their path, so the resulting order is stable.
+pub enum NoteOrder {
+    /// Sort notes by their path within the vault (the default).
+    #[default]
+    PathAsc,
+    /// Sort notes by the value of the frontmatter key `key`, descending when `desc` is true.
+    ///
+    /// Numbers are compared numerically and strings lexically, so dates should be written in a
+    /// sortable format such as `2022-01-02`. Notes without this key are placed last.
+    FrontmatterKey { key: String, desc: bool },
+    /// Sort notes by the time they were last modified, newest first when `desc` is true.
+    Mtime { desc: bool },
+    /// Sort notes using a custom comparison function.
+    Custom(fn(&Context, &Context) -> Ordering),
+}
+
+impl NoteOrder {
+    /// Compare the notes described by the contexts `a` and `b` according to this order.
+    pub fn compare(&self, a: &Context, b: &Context) -> Ordering {
+        let ordering = match self {
+            NoteOrder::PathAsc => Ordering::Equal,
+            NoteOrder::FrontmatterKey { key, desc } => {
+                let key = Value::String(key.to_string());
+                match (a.frontmatter.get(&key), b.frontmatter.get(&key)) {
+                    (Some(a), Some(b)) => {
+                        let ordering = compare_values(a, b).unwrap_or(Ordering::Equal);
+                        match desc {
+                            true => ordering.reverse(),
+                            false => ordering,
+                        }
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }
+            NoteOrder::Mtime { desc } => {
+                let mtime = |context: &Context| {
+                    fs::metadata(context.current_file())
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                };
+                let ordering = mtime(a).cmp(&mtime(b));
+                match desc {
+                    true => ordering.reverse(),
+                    false => ordering,
+                }
+            }
+            NoteOrder::Custom(func) => func(a, b),
+        };
+        ordering.then_with(|| a.current_file().cmp(b.current_file()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn note(path: &str, frontmatter: &str) -> Context {
+        let mut context = Context::new(PathBuf::from(path), PathBuf::from(path));
+        context.frontmatter = serde_yaml::from_str(frontmatter).unwrap();
+        context
+    }
+
+    #[test]
+    fn frontmatter_key_order() {
+        let order = NoteOrder::FrontmatterKey {
+            key: "date".to_string(),
+            desc: true,
+        };
+        let mut notes = [
+            note("a.md", "date: 2022-01-01"),
+            note("b.md", "title: no date"),
+            note("c.md", "date: 2022-03-01"),
+            note("d.md", "date: 2022-01-01"),
+        ];
+        notes.sort_by(|a, b| order.compare(a, b));
+        let paths: Vec<&str> = notes
+            .iter()
+            .map(|note| note.current_file().to_str().unwrap())
+            .collect();
+        assert_eq!(paths, vec!["c.md", "a.md", "d.md", "b.md"]);
+    }
+}
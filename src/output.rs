@@ -0,0 +1,173 @@
+use crate::summary::Warning;
+use crate::{frontmatter_value_to_string, Exporter, Frontmatter, MarkdownEvents};
+use pulldown_cmark::escape::escape_html;
+use pulldown_cmark::html::push_html;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::path::Path;
+
+const DEFAULT_HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+</head>
+<body>
+{{content}}
+</body>
+</html>
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Available formats to write exported notes in.
+pub enum OutputFormat {
+    /// Write notes as regular markdown (the default).
+    Markdown,
+    /// Render notes to HTML documents.
+    Html,
+}
+
+impl<'a> Exporter<'a> {
+    // Render the given markdown events of the note at `path` to an HTML document using the
+    // configured template, warning about placeholders which have no value.
+    pub(crate) fn render_html(
+        &self,
+        markdown: MarkdownEvents,
+        frontmatter: &Frontmatter,
+        path: &Path,
+    ) -> String {
+        let (output, missing) =
+            render_mdevents_to_html(markdown, frontmatter, path, &self.html_template);
+        for placeholder in missing {
+            self.warn(
+                Warning::new("No value for placeholder in HTML template")
+                    .detail("Placeholder", placeholder)
+                    .source(path),
+            );
+        }
+        output
+    }
+}
+
+/// Render the given markdown events to an HTML document.
+///
+/// The note's title is taken from the `title` frontmatter key, falling back to the filename of
+/// `path` without its extension. Placeholders in the template which have no value are replaced
+/// with an empty string and returned alongside the document.
+pub fn render_mdevents_to_html(
+    markdown: MarkdownEvents,
+    frontmatter: &Frontmatter,
+    path: &Path,
+    template: &Option<String>,
+) -> (String, Vec<String>) {
+    lazy_static! {
+        static ref PLACEHOLDER_RE: Regex = Regex::new(r"\{\{\s*(?P<name>[^{}]*?)\s*\}\}").unwrap();
+    }
+    let mut content = String::new();
+    push_html(&mut content, markdown.into_iter());
+
+    let title = match frontmatter.get(&Value::String("title".to_string())) {
+        Some(Value::String(title)) => title.to_string(),
+        _ => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    let mut escaped_title = String::new();
+    escape_html(&mut escaped_title, &title).expect("formatting to string not expected to fail");
+
+    // Placeholders are replaced in a single pass, so placeholders in the content are left as-is.
+    let mut missing = vec![];
+    let output = PLACEHOLDER_RE
+        .replace_all(
+            template.as_deref().unwrap_or(DEFAULT_HTML_TEMPLATE),
+            |captures: &Captures| {
+                let name = &captures["name"];
+                let value = match name {
+                    "title" => Some(escaped_title.clone()),
+                    "content" => Some(content.clone()),
+                    name => name
+                        .strip_prefix("frontmatter.")
+                        .and_then(|key| frontmatter.get(&Value::String(key.to_string())))
+                        .map(|value| {
+                            let mut escaped = String::new();
+                            escape_html(&mut escaped, &frontmatter_value_to_string(value))
+                                .expect("formatting to string not expected to fail");
+                            escaped
+                        }),
+                };
+                value.unwrap_or_else(|| {
+                    missing.push(name.to_string());
+                    String::new()
+                })
+            },
+        )
+        .into_owned();
+    (output, missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use pulldown_cmark::{CowStr, Event, Tag};
+
+    #[test]
+    fn title_falls_back_to_filename() {
+        let events = vec![
+            Event::Start(Tag::Paragraph),
+            Event::Text(CowStr::from("Hello")),
+            Event::End(Tag::Paragraph),
+        ];
+        assert_eq!(
+            render_mdevents_to_html(
+                events,
+                &Frontmatter::new(),
+                Path::new("My <Note>.md"),
+                &Some("{{title}}|{{content}}".to_string()),
+            ),
+            ("My &lt;Note&gt;|<p>Hello</p>\n".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn title_from_frontmatter() {
+        let mut frontmatter = Frontmatter::new();
+        frontmatter.insert(
+            Value::String("title".to_string()),
+            Value::String("A title".to_string()),
+        );
+        assert_eq!(
+            render_mdevents_to_html(
+                vec![],
+                &frontmatter,
+                Path::new("note.md"),
+                &Some("{{title}}".to_string()),
+            ),
+            ("A title".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn frontmatter_placeholders() {
+        let frontmatter: Frontmatter =
+            serde_yaml::from_str("author: Jane & John\ntags: [a, b]").unwrap();
+        assert_eq!(
+            render_mdevents_to_html(
+                vec![],
+                &frontmatter,
+                Path::new("note.md"),
+                &Some(
+                    "{{frontmatter.author}}|{{ frontmatter.tags }}|{{frontmatter.date}}|{{x}}"
+                        .to_string()
+                ),
+            ),
+            (
+                "Jane &amp; John|a, b||".to_string(),
+                vec!["frontmatter.date".to_string(), "x".to_string()]
+            )
+        );
+    }
+}
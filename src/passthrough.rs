@@ -0,0 +1,70 @@
+use crate::frontmatter::frontmatter_from_str;
+use crate::{parser_options, Exporter, Frontmatter, FrontmatterStrategy, MarkdownEvents};
+use pulldown_cmark::{CowStr, Event, Parser};
+use std::fs;
+use std::path::Path;
+
+impl<'a> Exporter<'a> {
+    // Return the original contents of the note at `src` when exporting it would produce the same
+    // note, given its final frontmatter, frontmatter strategy and events. Returns None when the
+    // note was changed in any way, or can't be read again.
+    pub(crate) fn unmodified_source(
+        &self,
+        src: &Path,
+        frontmatter: &Frontmatter,
+        frontmatter_strategy: FrontmatterStrategy,
+        events: &MarkdownEvents,
+    ) -> Option<String> {
+        let content = fs::read_to_string(src).ok()?;
+        let (original_frontmatter, body, has_frontmatter) = match matter::matter(&content) {
+            Some((frontmatter, body)) => (frontmatter_from_str(&frontmatter).ok()?, body, true),
+            None => (Frontmatter::new(), content.clone(), false),
+        };
+        if &original_frontmatter != frontmatter {
+            return None;
+        }
+        let write_frontmatter = match frontmatter_strategy {
+            FrontmatterStrategy::Always => true,
+            FrontmatterStrategy::Never => false,
+            FrontmatterStrategy::Auto => !frontmatter.is_empty(),
+        };
+        if write_frontmatter != has_frontmatter {
+            return None;
+        }
+        let original_events: MarkdownEvents = Parser::new_ext(&body, parser_options()).collect();
+        match merge_text(&original_events) == merge_text(events) {
+            true => Some(content),
+            false => None,
+        }
+    }
+
+    // Return the original text of the frontmatter of the note at `src` when it's equal to
+    // `frontmatter`, so it can be written as it was (see Exporter::keep_frontmatter_comments).
+    pub(crate) fn unmodified_frontmatter(
+        &self,
+        src: &Path,
+        frontmatter: &Frontmatter,
+    ) -> Option<String> {
+        let content = fs::read_to_string(src).ok()?;
+        let (original, _) = matter::matter(&content)?;
+        match &frontmatter_from_str(&original).ok()? == frontmatter {
+            true => Some(original),
+            false => None,
+        }
+    }
+}
+
+// Combine consecutive text events, which the parser may emit for a single run of text depending
+// on its contents.
+fn merge_text<'b>(events: &[Event<'b>]) -> Vec<Event<'b>> {
+    let mut merged: Vec<Event> = Vec::with_capacity(events.len());
+    for event in events {
+        match (merged.last_mut(), event) {
+            (Some(Event::Text(previous)), Event::Text(text)) => {
+                *previous = CowStr::from(format!("{}{}", previous, text));
+            }
+            _ => merged.push(event.clone()),
+        }
+    }
+    merged
+}
@@ -1,7 +1,30 @@
 //! A collection of officially maintained [postprocessors][crate::Postprocessor].
 
-use super::{Context, MarkdownEvents, PostprocessorResult};
-use pulldown_cmark::Event;
+use super::{
+    event_to_owned, parser_options, path_to_link, Context, MarkdownEvents, PostprocessorResult,
+};
+use crate::tags::{frontmatter_tags, tag_matches};
+use pathdiff::diff_paths;
+use percent_encoding::percent_decode_str;
+use pulldown_cmark::escape::escape_html;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, LinkType, Parser, Tag};
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+use slug::slugify;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+lazy_static! {
+    static ref HTML_COMMENT_RE: Regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+    static ref CALLOUT_RE: Regex =
+        Regex::new(r"^\[!(?P<kind>[^\]\s]+)\][+-]?\s*(?P<title>.*)$").unwrap();
+    static ref LIST_FROM_TAG_RE: Regex =
+        Regex::new(r"(?i)^\s*LIST\s+FROM\s+#(?P<tag>[\w/-]+)\s*$").unwrap();
+    static ref HIGHLIGHT_RE: Regex = Regex::new(r"==(?P<text>[^=\s](?:[^=]*[^=\s])?)==").unwrap();
+}
+
+/// The reading speed assumed by [reading_stats] to estimate reading time, in words per minute.
+pub const WORDS_PER_MINUTE: usize = 200;
 
 /// This postprocessor converts all soft line breaks to hard line breaks. Enabling this mimics
 /// Obsidian's _'Strict line breaks'_ setting.
@@ -18,3 +41,873 @@ pub fn softbreaks_to_hardbreaks(
         .collect();
     (context, events, PostprocessorResult::Continue)
 }
+
+/// This postprocessor converts links into numbered references, which is useful for output meant
+/// to be printed.
+///
+/// The text of each link is kept, followed by a superscript reference number. A numbered list of
+/// the link targets is appended to the end of the note. Numbers are assigned in order of first
+/// appearance within the note, and links pointing to the same target share the same number.
+pub fn links_to_references(
+    context: Context,
+    events: MarkdownEvents,
+) -> (Context, MarkdownEvents, PostprocessorResult) {
+    let mut targets: Vec<String> = vec![];
+    let mut output = Vec::with_capacity(events.len());
+
+    for event in events.into_iter() {
+        match event {
+            Event::Start(Tag::Link(..)) => {}
+            Event::End(Tag::Link(_, target, _)) => {
+                let target = target.to_string();
+                let number = match targets.iter().position(|t| t == &target) {
+                    Some(idx) => idx + 1,
+                    None => {
+                        targets.push(target);
+                        targets.len()
+                    }
+                };
+                output.push(Event::Html(CowStr::from(format!("<sup>{}</sup>", number))));
+            }
+            _ => output.push(event),
+        }
+    }
+
+    if !targets.is_empty() {
+        output.push(Event::Start(Tag::List(Some(1))));
+        for target in targets {
+            output.push(Event::Start(Tag::Item));
+            output.push(Event::Text(CowStr::from(
+                percent_decode_str(&target).decode_utf8_lossy().into_owned(),
+            )));
+            output.push(Event::End(Tag::Item));
+        }
+        output.push(Event::End(Tag::List(Some(1))));
+    }
+    (context, output, PostprocessorResult::Continue)
+}
+
+/// This postprocessor removes HTML comments (`<!-- ... -->`) from notes.
+///
+/// Comments within code blocks and inline code are left alone, as these are not HTML. When
+/// `preserve_conditional` is true, conditional comments (`<!--[if IE]> ... <![endif]-->`) are
+/// kept.
+pub fn strip_html_comments(
+    preserve_conditional: bool,
+) -> impl Fn(Context, MarkdownEvents) -> (Context, MarkdownEvents, PostprocessorResult) + Send + Sync
+{
+    move |context, events| {
+        let mut output = Vec::with_capacity(events.len());
+        // A comment spanning multiple lines arrives as one event per line, so consecutive HTML
+        // events are combined before looking for comments.
+        let mut html = String::new();
+        let flush = |html: &mut String, output: &mut MarkdownEvents| {
+            if html.is_empty() {
+                return;
+            }
+            let stripped = HTML_COMMENT_RE.replace_all(html, |captures: &regex::Captures| {
+                match preserve_conditional && captures[0].starts_with("<!--[if") {
+                    true => captures[0].to_string(),
+                    false => String::new(),
+                }
+            });
+            if !stripped.trim().is_empty() {
+                output.push(Event::Html(CowStr::from(stripped.into_owned())));
+            }
+            html.clear();
+        };
+
+        for event in events.into_iter() {
+            match event {
+                Event::Html(text) => html.push_str(&text),
+                _ => {
+                    flush(&mut html, &mut output);
+                    output.push(event);
+                }
+            }
+        }
+        flush(&mut html, &mut output);
+        (context, output, PostprocessorResult::Continue)
+    }
+}
+
+/// Determines how [normalize_code_fences] treats the info string of fenced code blocks.
+///
+/// The info string is split into the language (the first word) and the remainder, which for
+/// ```` ```js {linenos} ```` would be `js` and `{linenos}` respectively.
+#[derive(Debug, Clone, Copy)]
+pub enum InfoStringRule {
+    /// Leave the info string as-is.
+    Keep,
+    /// Remove everything after the language.
+    Strip,
+    /// Replace the info string with the result of calling this function with the language and
+    /// the remainder of the info string.
+    Transform(fn(&str, &str) -> String),
+}
+
+/// This postprocessor normalizes the info strings of fenced code blocks according to `rule`.
+///
+/// When `admonitions_to_callouts` is true, code blocks of the [admonition plugin] (such as
+/// ```` ```ad-note ````) are converted into regular Obsidian callouts (`> [!note]`). A `title:`
+/// line at the start of the block is used as the title of the callout. Note that wikilinks and
+/// embeds within these blocks are not resolved, as they're part of a code block at the time notes
+/// are parsed.
+///
+/// [admonition plugin]: https://github.com/valentine195/obsidian-admonition
+pub fn normalize_code_fences(
+    rule: InfoStringRule,
+    admonitions_to_callouts: bool,
+) -> impl Fn(Context, MarkdownEvents) -> (Context, MarkdownEvents, PostprocessorResult) + Send + Sync
+{
+    move |context, events| {
+        let mut output = Vec::with_capacity(events.len());
+        let mut admonition: Option<(String, String)> = None;
+
+        for event in events.into_iter() {
+            if let Some((kind, text)) = &mut admonition {
+                match event {
+                    Event::Text(content) => text.push_str(&content),
+                    Event::End(Tag::CodeBlock(_)) => {
+                        output.append(&mut admonition_to_callout(kind, text));
+                        admonition = None;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                    let (language, _) = split_info_string(&info);
+                    match language.strip_prefix("ad-") {
+                        Some(kind) if admonitions_to_callouts => {
+                            admonition = Some((kind.to_string(), String::new()));
+                        }
+                        _ => output.push(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(
+                            normalize_info_string(&info, rule),
+                        )))),
+                    }
+                }
+                Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => output.push(Event::End(
+                    Tag::CodeBlock(CodeBlockKind::Fenced(normalize_info_string(&info, rule))),
+                )),
+                _ => output.push(event),
+            }
+        }
+        (context, output, PostprocessorResult::Continue)
+    }
+}
+
+fn split_info_string(info: &str) -> (&str, &str) {
+    let info = info.trim();
+    match info.find(|c: char| c.is_whitespace() || c == '{') {
+        Some(idx) => (&info[..idx], info[idx..].trim()),
+        None => (info, ""),
+    }
+}
+
+fn normalize_info_string<'a>(info: &str, rule: InfoStringRule) -> CowStr<'a> {
+    let (language, rest) = split_info_string(info);
+    CowStr::from(match rule {
+        InfoStringRule::Keep => info.to_string(),
+        InfoStringRule::Strip => language.to_string(),
+        InfoStringRule::Transform(func) => func(language, rest),
+    })
+}
+
+fn admonition_to_callout<'a>(kind: &str, text: &str) -> MarkdownEvents<'a> {
+    let (title, body) = match text.strip_prefix("title:") {
+        Some(rest) => {
+            let (title, body) = rest.split_once('\n').unwrap_or((rest, ""));
+            (Some(title.trim()), body)
+        }
+        None => (None, text),
+    };
+    let marker = match title {
+        Some(title) => format!("[!{}] {}", kind, title),
+        None => format!("[!{}]", kind),
+    };
+
+    // The callout marker is emitted as HTML, because a regular text event starting with `[`
+    // would be escaped when rendered back to markdown.
+    let mut events = vec![
+        Event::Start(Tag::BlockQuote),
+        Event::Start(Tag::Paragraph),
+        Event::Html(CowStr::from(marker)),
+        Event::End(Tag::Paragraph),
+    ];
+    events.extend(Parser::new_ext(body, parser_options()).map(event_to_owned));
+    events.push(Event::End(Tag::BlockQuote));
+    events
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Determines the syntax [convert_callouts] turns callouts into.
+pub enum CalloutStyle {
+    /// Admonitions as used by Docusaurus and similar tools (`:::tip Title` ... `:::`).
+    Admonition,
+    /// `<div class="callout callout-tip">` elements, for use with
+    /// [OutputFormat::Html][crate::OutputFormat::Html].
+    HtmlClass,
+}
+
+/// This postprocessor converts Obsidian callouts (`> [!tip] Title`) into the syntax given by
+/// `style`.
+///
+/// The type of each callout is looked up in `type_map` (by its lowercased name), so that for
+/// example `tip` can be mapped to the `info` type of the target. Types which aren't in `type_map`
+/// are used as-is, lowercased. With [CalloutStyle::HtmlClass] the resulting type is used as part
+/// of the CSS class. Callouts nested within callouts are converted as well (with
+/// [CalloutStyle::Admonition], the outer admonitions get additional colons), while the folding
+/// markers of foldable callouts (`[!tip]-`) are dropped.
+pub fn convert_callouts(
+    style: CalloutStyle,
+    type_map: HashMap<String, String>,
+) -> impl Fn(Context, MarkdownEvents) -> (Context, MarkdownEvents, PostprocessorResult) + Send + Sync
+{
+    move |context, events| {
+        let (events, _) = convert_callout_events(events, style, &type_map);
+        (context, events, PostprocessorResult::Continue)
+    }
+}
+
+// Convert the callouts within `events`, returning the converted events along with the deepest
+// nesting level of the callouts found.
+fn convert_callout_events<'a>(
+    events: MarkdownEvents<'a>,
+    style: CalloutStyle,
+    type_map: &HashMap<String, String>,
+) -> (MarkdownEvents<'a>, usize) {
+    let mut output = Vec::with_capacity(events.len());
+    let mut nesting = 0;
+    let mut events = events.into_iter();
+    while let Some(event) = events.next() {
+        if !matches!(event, Event::Start(Tag::BlockQuote)) {
+            output.push(event);
+            continue;
+        }
+        let inner = take_block_quote(&mut events);
+        match split_callout(inner) {
+            Ok((kind, title, body)) => {
+                let kind = kind.to_lowercase();
+                let kind = type_map.get(&kind).cloned().unwrap_or(kind);
+                let (body, body_nesting) = convert_callout_events(body, style, type_map);
+                output.append(&mut render_callout(
+                    &kind,
+                    &title,
+                    body,
+                    body_nesting,
+                    style,
+                ));
+                nesting = nesting.max(body_nesting + 1);
+            }
+            Err(inner) => {
+                let (mut inner, inner_nesting) = convert_callout_events(inner, style, type_map);
+                output.push(Event::Start(Tag::BlockQuote));
+                output.append(&mut inner);
+                output.push(Event::End(Tag::BlockQuote));
+                nesting = nesting.max(inner_nesting);
+            }
+        }
+    }
+    (output, nesting)
+}
+
+// Take the events inside a block quote from `events`, whose start has just been consumed. Its end
+// is consumed as well, but not returned. Block quotes nested within it are taken along.
+fn take_block_quote<'a>(events: &mut impl Iterator<Item = Event<'a>>) -> MarkdownEvents<'a> {
+    let mut depth = 1;
+    let mut inner = vec![];
+    for event in events {
+        match event {
+            Event::Start(Tag::BlockQuote) => depth += 1,
+            Event::End(Tag::BlockQuote) => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            break;
+        }
+        inner.push(event);
+    }
+    inner
+}
+
+// Split the events inside a block quote into the type, title and body of a callout. When the
+// block quote isn't a callout, the events are returned unchanged.
+fn split_callout(
+    events: MarkdownEvents,
+) -> Result<(String, String, MarkdownEvents), MarkdownEvents> {
+    if !matches!(events.first(), Some(Event::Start(Tag::Paragraph)))
+        || !matches!(events.get(1), Some(Event::Text(_) | Event::Html(_)))
+    {
+        return Err(events);
+    }
+    let line_end = events
+        .iter()
+        .position(|event| {
+            matches!(
+                event,
+                Event::SoftBreak | Event::HardBreak | Event::End(Tag::Paragraph)
+            )
+        })
+        .expect("paragraph should always be closed");
+    let line: String = events[1..line_end]
+        .iter()
+        .filter_map(|event| match event {
+            Event::Text(text) | Event::Code(text) | Event::Html(text) => Some(text.as_ref()),
+            _ => None,
+        })
+        .collect();
+    let (kind, title) = match CALLOUT_RE.captures(line.trim()) {
+        Some(captures) => (
+            captures["kind"].to_string(),
+            captures["title"].trim().to_string(),
+        ),
+        None => return Err(events),
+    };
+
+    let mut body = vec![];
+    if !matches!(events[line_end], Event::End(Tag::Paragraph)) {
+        body.push(Event::Start(Tag::Paragraph));
+    }
+    body.extend(events.into_iter().skip(line_end + 1));
+    Ok((kind, title, body))
+}
+
+/// This postprocessor converts Obsidian callouts into [GitHub alerts].
+///
+/// GitHub only supports the `NOTE`, `TIP`, `IMPORTANT`, `WARNING` and `CAUTION` alert types.
+/// Callout types which are aliases of these in Obsidian (such as `hint`, `attention` or `danger`)
+/// are mapped to the matching alert type, all other types become `NOTE`. Because alerts can't have
+/// a title, the title of a callout is placed in bold at the start of the alert instead.
+///
+/// [GitHub alerts]: https://docs.github.com/en/get-started/writing-on-github/getting-started-with-writing-and-formatting-on-github/basic-writing-and-formatting-syntax#alerts
+pub fn callouts_to_github_alerts(
+    context: Context,
+    events: MarkdownEvents,
+) -> (Context, MarkdownEvents, PostprocessorResult) {
+    let events = retype_callouts(events, &|kind| {
+        let alert = match kind.to_lowercase().as_str() {
+            "tip" | "hint" => "TIP",
+            "important" => "IMPORTANT",
+            "warning" | "attention" => "WARNING",
+            "caution" | "danger" | "error" | "bug" | "failure" | "fail" | "missing" => "CAUTION",
+            _ => "NOTE",
+        };
+        Some((alert.to_string(), false))
+    });
+    (context, events, PostprocessorResult::Continue)
+}
+
+/// This postprocessor converts [GitHub alerts] (`> [!NOTE]`) into Obsidian callouts, the
+/// inverse of [callouts_to_github_alerts].
+///
+/// The types of GitHub alerts are all supported by Obsidian, so only their case is changed. Other
+/// callouts are left as-is.
+///
+/// [GitHub alerts]: https://docs.github.com/en/get-started/writing-on-github/getting-started-with-writing-and-formatting-on-github/basic-writing-and-formatting-syntax#alerts
+pub fn github_alerts_to_callouts(
+    context: Context,
+    events: MarkdownEvents,
+) -> (Context, MarkdownEvents, PostprocessorResult) {
+    let events = retype_callouts(events, &|kind| match kind {
+        "NOTE" | "TIP" | "IMPORTANT" | "WARNING" | "CAUTION" => Some((kind.to_lowercase(), true)),
+        _ => None,
+    });
+    (context, events, PostprocessorResult::Continue)
+}
+
+// Change the type of the callouts within `events` to the type returned by `retype`, which also
+// determines whether the title of the callout is kept on the marker line (true) or moved into the
+// body (false). Callouts for which `retype` returns None are left as-is.
+fn retype_callouts<'a>(
+    events: MarkdownEvents<'a>,
+    retype: &dyn Fn(&str) -> Option<(String, bool)>,
+) -> MarkdownEvents<'a> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut events = events.into_iter();
+    while let Some(event) = events.next() {
+        if !matches!(event, Event::Start(Tag::BlockQuote)) {
+            output.push(event);
+            continue;
+        }
+        let inner = take_block_quote(&mut events);
+
+        output.push(Event::Start(Tag::BlockQuote));
+        let original = inner.clone();
+        match split_callout(inner) {
+            Ok((kind, title, body)) => match retype(&kind) {
+                Some((kind, title_on_marker)) => {
+                    let mut marker = format!("[!{}]", kind);
+                    let mut body = retype_callouts(body, retype);
+                    if !title.is_empty() && title_on_marker {
+                        marker.push(' ');
+                        marker.push_str(&title);
+                    } else if !title.is_empty() {
+                        body.splice(
+                            0..0,
+                            vec![
+                                Event::Start(Tag::Paragraph),
+                                Event::Start(Tag::Strong),
+                                Event::Text(CowStr::from(title)),
+                                Event::End(Tag::Strong),
+                                Event::End(Tag::Paragraph),
+                            ],
+                        );
+                    }
+                    // The marker is emitted as HTML to prevent it from being escaped, and placed
+                    // on the first line of the first paragraph as GitHub requires.
+                    output.push(Event::Start(Tag::Paragraph));
+                    output.push(Event::Html(CowStr::from(marker)));
+                    match body.first() {
+                        Some(Event::Start(Tag::Paragraph)) => {
+                            output.push(Event::SoftBreak);
+                            output.extend(body.into_iter().skip(1));
+                        }
+                        _ => {
+                            output.push(Event::End(Tag::Paragraph));
+                            output.append(&mut body);
+                        }
+                    }
+                }
+                None => output.append(&mut retype_callouts(original, retype)),
+            },
+            Err(inner) => output.append(&mut retype_callouts(inner, retype)),
+        }
+        output.push(Event::End(Tag::BlockQuote));
+    }
+    output
+}
+
+fn render_callout<'a>(
+    kind: &str,
+    title: &str,
+    mut body: MarkdownEvents<'a>,
+    body_nesting: usize,
+    style: CalloutStyle,
+) -> MarkdownEvents<'a> {
+    let mut events = vec![];
+    match style {
+        CalloutStyle::Admonition => {
+            // Admonitions containing other admonitions need a longer fence than those inside them.
+            let fence = ":".repeat(3 + body_nesting);
+            let opening = match title.is_empty() {
+                true => format!("{}{}", fence, kind),
+                false => format!("{}{} {}", fence, kind, title),
+            };
+            // Like callout markers, these are emitted as HTML to prevent them from being escaped.
+            events.push(Event::Start(Tag::Paragraph));
+            events.push(Event::Html(CowStr::from(opening)));
+            events.push(Event::End(Tag::Paragraph));
+            events.append(&mut body);
+            events.push(Event::Start(Tag::Paragraph));
+            events.push(Event::Html(CowStr::from(fence)));
+            events.push(Event::End(Tag::Paragraph));
+        }
+        CalloutStyle::HtmlClass => {
+            let mut class = String::new();
+            escape_html(&mut class, kind).expect("formatting to string not expected to fail");
+            events.push(Event::Html(CowStr::from(format!(
+                "<div class=\"callout callout-{}\">\n",
+                class
+            ))));
+            if !title.is_empty() {
+                let mut escaped_title = String::new();
+                escape_html(&mut escaped_title, title)
+                    .expect("formatting to string not expected to fail");
+                events.push(Event::Html(CowStr::from(format!(
+                    "<p class=\"callout-title\">{}</p>\n",
+                    escaped_title
+                ))));
+            }
+            events.append(&mut body);
+            events.push(Event::Html(CowStr::Borrowed("</div>\n")));
+        }
+    }
+    events
+}
+
+/// This postprocessor replaces the `query` code blocks of Obsidian search and the `dataview` code
+/// blocks of the [Dataview] plugin, which can only be evaluated within Obsidian.
+///
+/// Dataview queries of the form `LIST FROM #tag` are rendered as a list of links to the notes which
+/// have that tag (or a tag nested under it) in their frontmatter, sorted by path. Links point to
+/// the notes as they're named in the vault, so this doesn't take notes renamed by other
+/// postprocessors or [OutputFormat::Html][crate::OutputFormat::Html] into account. All other
+/// queries are replaced with a paragraph containing `placeholder`, or removed entirely when
+/// `placeholder` is empty.
+///
+/// [Dataview]: https://blacksmithgu.github.io/obsidian-dataview/
+pub fn handle_query_blocks(
+    placeholder: String,
+) -> impl Fn(Context, MarkdownEvents) -> (Context, MarkdownEvents, PostprocessorResult) + Send + Sync
+{
+    move |context, events| {
+        let mut output = Vec::with_capacity(events.len());
+        let mut events = events.into_iter();
+        while let Some(event) = events.next() {
+            let info = match &event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => info,
+                _ => {
+                    output.push(event);
+                    continue;
+                }
+            };
+            let language = split_info_string(info).0.to_lowercase();
+            if language != "query" && language != "dataview" {
+                output.push(event);
+                continue;
+            }
+            let mut query = String::new();
+            for event in events.by_ref() {
+                match event {
+                    Event::End(Tag::CodeBlock(_)) => break,
+                    Event::Text(text) => query.push_str(&text),
+                    _ => {}
+                }
+            }
+            match LIST_FROM_TAG_RE.captures(&query) {
+                Some(captures) if language == "dataview" && context.vault_notes().is_some() => {
+                    output.append(&mut tagged_notes_list(&context, &captures["tag"]));
+                }
+                _ if placeholder.is_empty() => {}
+                _ => {
+                    output.push(Event::Start(Tag::Paragraph));
+                    output.push(Event::Text(CowStr::from(placeholder.clone())));
+                    output.push(Event::End(Tag::Paragraph));
+                }
+            }
+        }
+        (context, output, PostprocessorResult::Continue)
+    }
+}
+
+// Render a list of links to the notes in the vault which are tagged with `tag`.
+fn tagged_notes_list<'a>(context: &Context, tag: &str) -> MarkdownEvents<'a> {
+    let mut notes: Vec<&PathBuf> = context
+        .vault_notes()
+        .into_iter()
+        .flatten()
+        .filter(|(_, frontmatter)| {
+            frontmatter_tags(frontmatter)
+                .iter()
+                .any(|note_tag| tag_matches(note_tag, tag))
+        })
+        .map(|(path, _)| path)
+        .collect();
+    if notes.is_empty() {
+        return vec![];
+    }
+    notes.sort();
+
+    let note_dir = context
+        .current_file()
+        .parent()
+        .expect("obsidian content files should always have a parent");
+    let mut events = vec![Event::Start(Tag::List(None))];
+    for note in notes {
+        let target = diff_paths(note, note_dir).unwrap_or_else(|| note.to_path_buf());
+        let link = Tag::Link(
+            LinkType::Inline,
+            CowStr::from(path_to_link(&target)),
+            CowStr::Borrowed(""),
+        );
+        events.push(Event::Start(Tag::Item));
+        events.push(Event::Start(link.clone()));
+        events.push(Event::Text(CowStr::from(
+            note.file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        )));
+        events.push(Event::End(link));
+        events.push(Event::End(Tag::Item));
+    }
+    events.push(Event::End(Tag::List(None)));
+    events
+}
+
+/// This postprocessor normalizes the frontmatter values of the given `keys` into lists of
+/// lowercase terms, as expected by taxonomies in [Hugo] (such as `tags` and `categories`).
+///
+/// Strings are split on commas, so `tags: Rust, CLI` becomes `tags: [rust, cli]`. Terms are trimmed
+/// and duplicates are removed, keeping the first occurrence. Keys which aren't present in the
+/// frontmatter of a note are left out.
+///
+/// [Hugo]: https://gohugo.io/content-management/taxonomies/
+pub fn normalize_taxonomies(
+    keys: Vec<String>,
+) -> impl Fn(Context, MarkdownEvents) -> (Context, MarkdownEvents, PostprocessorResult) + Send + Sync
+{
+    move |mut context, events| {
+        for key in &keys {
+            let key = Value::String(key.to_string());
+            let terms = match context.frontmatter.get(&key) {
+                Some(value) => taxonomy_terms(value),
+                None => continue,
+            };
+            let mut unique: Vec<Value> = Vec::with_capacity(terms.len());
+            for term in terms {
+                let term = Value::String(term);
+                if !unique.contains(&term) {
+                    unique.push(term);
+                }
+            }
+            context.frontmatter.insert(key, Value::Sequence(unique));
+        }
+        (context, events, PostprocessorResult::Continue)
+    }
+}
+
+/// This postprocessor turns nested tags under the given `prefixes` into a tree of categories,
+/// stored under the frontmatter key `key`.
+///
+/// Each level of a tag becomes a level of nested mappings, so with a prefix of `area`, the tags
+/// `area/health/fitness` and `area/work` result in:
+///
+/// ```yaml
+/// categories:
+///   area:
+///     health:
+///       fitness: {}
+///     work: {}
+/// ```
+///
+/// Both tags in frontmatter and inline tags are used (see [Context::tags]). Categories are merged
+/// into an existing mapping under `key`, and notes without any matching tags are left as they are.
+pub fn tag_hierarchies(
+    prefixes: Vec<String>,
+    key: String,
+) -> impl Fn(Context, MarkdownEvents) -> (Context, MarkdownEvents, PostprocessorResult) + Send + Sync
+{
+    move |mut context, events| {
+        let tags: Vec<String> = context
+            .tags()
+            .into_iter()
+            .filter(|tag| prefixes.iter().any(|prefix| tag_matches(tag, prefix)))
+            .collect();
+        if tags.is_empty() {
+            return (context, events, PostprocessorResult::Continue);
+        }
+        let key = Value::String(key.to_string());
+        let mut categories = match context.frontmatter.remove(&key) {
+            Some(Value::Mapping(categories)) => categories,
+            _ => Mapping::new(),
+        };
+        for tag in tags {
+            let mut level = &mut categories;
+            for name in tag.split('/').filter(|name| !name.is_empty()) {
+                let entry = level
+                    .entry(Value::String(name.to_string()))
+                    .or_insert_with(|| Value::Mapping(Mapping::new()));
+                if !entry.is_mapping() {
+                    *entry = Value::Mapping(Mapping::new());
+                }
+                level = entry.as_mapping_mut().expect("entry is a mapping");
+            }
+        }
+        context.frontmatter.insert(key, Value::Mapping(categories));
+        (context, events, PostprocessorResult::Continue)
+    }
+}
+
+// Return the (lowercased) terms of a taxonomy given as a comma-separated string or a list.
+fn taxonomy_terms(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(terms) => terms
+            .split(',')
+            .map(|term| term.trim().to_lowercase())
+            .filter(|term| !term.is_empty())
+            .collect(),
+        Value::Number(term) => vec![term.to_string()],
+        Value::Bool(term) => vec![term.to_string()],
+        Value::Sequence(values) => values.iter().flat_map(taxonomy_terms).collect(),
+        Value::Null | Value::Mapping(_) => vec![],
+    }
+}
+
+/// This postprocessor converts Obsidian's `==highlighted text==` syntax into `<mark>` HTML
+/// elements.
+///
+/// Renderers which derive heading anchors from the raw heading text would include the `<mark>`
+/// tags in these, breaking links to a highlighted heading. Headings which contain highlights are
+/// therefore given an explicit anchor (`<a id="..."></a>`), computed from the plain text of the
+/// heading in the same way as the section part of links to it.
+///
+/// Highlights must start and end within the same run of text, so `==**bold**==` is left as-is.
+pub fn highlights_to_html(
+    context: Context,
+    events: MarkdownEvents,
+) -> (Context, MarkdownEvents, PostprocessorResult) {
+    let mut output = Vec::with_capacity(events.len());
+    let mut heading: Option<MarkdownEvents> = None;
+    let mut in_code_block = false;
+    // Text may arrive as several consecutive events, which are combined so that highlights split
+    // across them are still found.
+    let mut text = String::new();
+
+    for event in events.into_iter() {
+        if let Event::Text(content) = &event {
+            if !in_code_block {
+                text.push_str(content);
+                continue;
+            }
+        }
+        let target = heading.as_mut().unwrap_or(&mut output);
+        if !text.is_empty() {
+            target.extend(highlight_events(&text));
+            text.clear();
+        }
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                target.push(event);
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                in_code_block = false;
+                target.push(event);
+            }
+            Event::Start(Tag::Heading(..)) => heading = Some(vec![event]),
+            Event::End(Tag::Heading(..)) => {
+                let mut events = heading.take().unwrap_or_default();
+                events.push(event);
+                output.extend(anchor_highlighted_heading(events));
+            }
+            _ => target.push(event),
+        }
+    }
+    output.extend(highlight_events(&text));
+    (context, output, PostprocessorResult::Continue)
+}
+
+fn highlight_events<'a>(text: &str) -> MarkdownEvents<'a> {
+    let mut events = vec![];
+    let mut last = 0;
+    for captures in HIGHLIGHT_RE.captures_iter(text) {
+        let range = captures.get(0).unwrap().range();
+        if range.start > last {
+            events.push(Event::Text(CowStr::from(
+                text[last..range.start].to_string(),
+            )));
+        }
+        events.push(Event::Html(CowStr::Borrowed("<mark>")));
+        events.push(Event::Text(CowStr::from(captures["text"].to_string())));
+        events.push(Event::Html(CowStr::Borrowed("</mark>")));
+        last = range.end;
+    }
+    if last < text.len() {
+        events.push(Event::Text(CowStr::from(text[last..].to_string())));
+    }
+    events
+}
+
+// Insert an anchor derived from the plain text of a heading into the heading, if it contains any
+// highlights.
+fn anchor_highlighted_heading(mut events: MarkdownEvents) -> MarkdownEvents {
+    if !events
+        .iter()
+        .any(|event| matches!(event, Event::Html(html) if html.as_ref() == "<mark>"))
+    {
+        return events;
+    }
+    let plain_text: String = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+            _ => None,
+        })
+        .collect();
+    let anchor = format!("<a id=\"{}\"></a>", slugify(plain_text.trim()));
+    events.insert(1, Event::Html(CowStr::from(anchor)));
+    events
+}
+
+/// This postprocessor escapes `<`, `{` and `}` in text, which MDX would otherwise interpret as
+/// the start of a JSX element or expression.
+///
+/// Only text outside of code is escaped, so prose such as `a < b` or `${price}` compiles while
+/// code spans and code blocks are left untouched. HTML (and JSX) written in the note isn't text
+/// and is left as-is, as are characters which were already escaped in the note.
+pub fn escape_mdx(
+    context: Context,
+    events: MarkdownEvents,
+) -> (Context, MarkdownEvents, PostprocessorResult) {
+    let mut output = Vec::with_capacity(events.len());
+    let mut in_code_block = false;
+    for event in events.into_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            Event::Text(text) if !in_code_block && text.contains(['<', '{', '}']) => {
+                output.extend(escape_mdx_text(&text));
+                continue;
+            }
+            _ => {}
+        }
+        output.push(event);
+    }
+    (context, output, PostprocessorResult::Continue)
+}
+
+// Split `text` into text events and escaped `<`, `{` and `}` characters. The escapes are emitted
+// as HTML, as the markdown renderer would escape the backslash of an escape in a text event.
+fn escape_mdx_text<'a>(text: &str) -> MarkdownEvents<'a> {
+    let mut events = vec![];
+    let mut last = 0;
+    for (idx, char) in text.match_indices(['<', '{', '}']) {
+        if idx > last {
+            events.push(Event::Text(CowStr::from(text[last..idx].to_string())));
+        }
+        events.push(Event::Html(CowStr::from(format!("\\{}", char))));
+        last = idx + char.len();
+    }
+    if last < text.len() {
+        events.push(Event::Text(CowStr::from(text[last..].to_string())));
+    }
+    events
+}
+
+/// This postprocessor adds a `word_count` and `reading_time` (in minutes) to the frontmatter of
+/// notes.
+///
+/// When `exclude_embeds` is true, content which was inserted into a note by embedding other notes
+/// is not counted (see [Context::content_range_excluding_embeds]). Because this relies on the
+/// position of events within a note, it should be added before any postprocessors which insert or
+/// remove events.
+pub fn reading_stats(
+    exclude_embeds: bool,
+) -> impl Fn(Context, MarkdownEvents) -> (Context, MarkdownEvents, PostprocessorResult) + Send + Sync
+{
+    move |mut context, events| {
+        let word_count = match exclude_embeds {
+            true => context
+                .content_range_excluding_embeds(&events)
+                .into_iter()
+                .map(|range| count_words(&events[range]))
+                .sum(),
+            false => count_words(&events),
+        };
+        let reading_time = word_count.div_ceil(WORDS_PER_MINUTE);
+
+        context.frontmatter.insert(
+            Value::String("word_count".to_string()),
+            Value::Number(word_count.into()),
+        );
+        context.frontmatter.insert(
+            Value::String("reading_time".to_string()),
+            Value::Number(reading_time.into()),
+        );
+        (context, events, PostprocessorResult::Continue)
+    }
+}
+
+/// Count the number of words in the text and inline code of the given events.
+pub fn count_words(events: &[Event]) -> usize {
+    events
+        .iter()
+        .map(|event| match event {
+            Event::Text(text) | Event::Code(text) => text.split_whitespace().count(),
+            _ => 0,
+        })
+        .sum()
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// The accumulated time spent in a single stage of an export.
+pub struct Timing {
+    /// The total time spent in this stage, summed across all threads.
+    pub total: Duration,
+    /// The number of times this stage was entered.
+    pub calls: usize,
+}
+
+#[derive(Debug, Default)]
+/// Profile holds cumulative timings for the stages of an export, as collected when profiling is
+/// enabled through [Exporter::profile][crate::Exporter::profile].
+///
+/// Because notes are exported in parallel, the total time of all stages combined may exceed the
+/// wall-clock duration of the export.
+pub struct Profile {
+    timings: Mutex<HashMap<String, Timing>>,
+}
+
+impl Profile {
+    /// Create a new, empty profile.
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    /// Add `elapsed` to the accumulated time of `stage`.
+    pub fn record(&self, stage: &str, elapsed: Duration) {
+        let mut timings = self.timings.lock().expect("profile lock poisoned");
+        let timing = timings.entry(stage.to_string()).or_default();
+        timing.total += elapsed;
+        timing.calls += 1;
+    }
+
+    /// Run `func`, adding the time it takes to the accumulated time of `stage`.
+    pub fn time<T>(&self, stage: &str, func: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = func();
+        self.record(stage, start.elapsed());
+        result
+    }
+
+    /// Return the accumulated timing of `stage`, if it was entered at all.
+    pub fn get(&self, stage: &str) -> Option<Timing> {
+        self.timings
+            .lock()
+            .expect("profile lock poisoned")
+            .get(stage)
+            .copied()
+    }
+
+    /// Return the timings of all stages, sorted by total time (longest first).
+    pub fn timings(&self) -> Vec<(String, Timing)> {
+        let mut timings: Vec<(String, Timing)> = self
+            .timings
+            .lock()
+            .expect("profile lock poisoned")
+            .iter()
+            .map(|(stage, timing)| (stage.clone(), *timing))
+            .collect();
+        timings.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(&b.0)));
+        timings
+    }
+
+    /// Render the timings in the "folded stacks" format understood by flamegraph tools such as
+    /// [inferno](https://github.com/jonhoo/inferno), with one `stage microseconds` line per stage.
+    ///
+    /// Stages are nested using `;` (`parse;embeds` is part of `parse`). The time reported for a
+    /// stage excludes the time spent in the stages nested directly under it.
+    pub fn to_folded(&self) -> String {
+        let timings = self.timings();
+        timings
+            .iter()
+            .map(|(stage, timing)| {
+                let prefix = format!("{};", stage);
+                let nested: Duration = timings
+                    .iter()
+                    .filter_map(|(other, timing)| {
+                        other
+                            .strip_prefix(&prefix)
+                            .filter(|rest| !rest.contains(';'))
+                            .map(|_| timing.total)
+                    })
+                    .sum();
+                format!(
+                    "{} {}\n",
+                    stage,
+                    timing.total.saturating_sub(nested).as_micros()
+                )
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let timings = self.timings();
+        let width = timings
+            .iter()
+            .map(|(stage, _)| stage.len())
+            .max()
+            .unwrap_or(0)
+            .max("stage".len());
+        writeln!(
+            f,
+            "{:<width$}  {:>12}  {:>8}",
+            "stage", "total (ms)", "calls"
+        )?;
+        for (stage, timing) in timings {
+            writeln!(
+                f,
+                "{:<width$}  {:>12.3}  {:>8}",
+                stage,
+                timing.total.as_secs_f64() * 1000.0,
+                timing.calls,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn timings_accumulate_and_sort() {
+        let profile = Profile::new();
+        profile.record("parse", Duration::from_millis(2));
+        profile.record("write", Duration::from_millis(5));
+        profile.record("parse", Duration::from_millis(4));
+
+        assert_eq!(
+            profile.timings(),
+            vec![
+                (
+                    "parse".to_string(),
+                    Timing {
+                        total: Duration::from_millis(6),
+                        calls: 2
+                    }
+                ),
+                (
+                    "write".to_string(),
+                    Timing {
+                        total: Duration::from_millis(5),
+                        calls: 1
+                    }
+                ),
+            ]
+        );
+        assert_eq!(profile.get("missing"), None);
+    }
+
+    #[test]
+    fn folded_output_excludes_nested_stages() {
+        let profile = Profile::new();
+        profile.record("parse", Duration::from_micros(100));
+        profile.record("parse;embeds", Duration::from_micros(60));
+        profile.record(
+            "parse;embeds;embed postprocessor #1",
+            Duration::from_micros(10),
+        );
+
+        assert_eq!(
+            profile.to_folded(),
+            "parse 40\nparse;embeds 50\nparse;embeds;embed postprocessor #1 10\n"
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The kind of files a [Progress] update is about.
+pub enum ProgressPhase {
+    /// Notes, which are parsed and rendered.
+    Notes,
+    /// Attachments (and other files which aren't notes), which are copied.
+    Attachments,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The progress of an export, as passed to the [ProgressCallback] set through
+/// [Exporter::on_progress][crate::Exporter::on_progress].
+pub struct Progress {
+    /// The kind of files this update is about.
+    pub phase: ProgressPhase,
+    /// The number of files of this kind which have been processed so far.
+    pub done: usize,
+    /// The total number of files of this kind which will be processed.
+    pub total: usize,
+}
+
+/// A function which is called as an export makes progress.
+///
+/// Notes and attachments are exported in parallel, so this may be called from several threads at
+/// once, and updates may arrive slightly out of order.
+pub type ProgressCallback = dyn Fn(Progress) + Send + Sync;
+
+// Counts the files processed during an export and reports these to a ProgressCallback.
+pub(crate) struct ProgressTracker<'a> {
+    callback: &'a ProgressCallback,
+    notes_total: usize,
+    notes_done: AtomicUsize,
+    attachments_total: AtomicUsize,
+    attachments_done: AtomicUsize,
+}
+
+impl<'a> ProgressTracker<'a> {
+    // Start tracking an export of the given number of notes and attachments, reporting that none
+    // of them have been processed yet.
+    pub(crate) fn new(
+        callback: &'a ProgressCallback,
+        notes_total: usize,
+        attachments_total: usize,
+    ) -> ProgressTracker<'a> {
+        let tracker = ProgressTracker {
+            callback,
+            notes_total,
+            notes_done: AtomicUsize::new(0),
+            attachments_total: AtomicUsize::new(attachments_total),
+            attachments_done: AtomicUsize::new(0),
+        };
+        tracker.report(ProgressPhase::Notes, 0);
+        tracker.report(ProgressPhase::Attachments, 0);
+        tracker
+    }
+
+    // Record that another file of the given kind has been processed.
+    pub(crate) fn advance(&self, phase: ProgressPhase) {
+        let counter = match phase {
+            ProgressPhase::Notes => &self.notes_done,
+            ProgressPhase::Attachments => &self.attachments_done,
+        };
+        let done = counter.fetch_add(1, Ordering::SeqCst) + 1;
+        self.report(phase, done);
+    }
+
+    // Add attachments which turned out to be part of the export after it started, such as the
+    // dependencies of the notes set through Exporter::export_files.
+    pub(crate) fn add_attachments(&self, count: usize) {
+        self.attachments_total.fetch_add(count, Ordering::SeqCst);
+    }
+
+    fn report(&self, phase: ProgressPhase, done: usize) {
+        let total = match phase {
+            ProgressPhase::Notes => self.notes_total,
+            ProgressPhase::Attachments => self.attachments_total.load(Ordering::SeqCst),
+        };
+        (self.callback)(Progress { phase, done, total });
+    }
+}
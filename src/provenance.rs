@@ -0,0 +1,104 @@
+use crate::summary::Warning;
+use crate::{Exporter, Frontmatter};
+use serde_yaml::Value;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl<'a> Exporter<'a> {
+    // Build the frontmatter fields which inject_provenance adds to every note of this run.
+    pub(crate) fn provenance_frontmatter(
+        &self,
+        commit_key: &Option<String>,
+        exported_at_key: &Option<String>,
+    ) -> Frontmatter {
+        let mut frontmatter = Frontmatter::new();
+        if let Some(key) = commit_key {
+            match git_commit(&self.root) {
+                Some(commit) => {
+                    frontmatter.insert(Value::String(key.to_string()), Value::String(commit));
+                }
+                None => self.warn(
+                    Warning::new(format_args!(
+                        "Unable to determine the git commit of the vault, omitting '{}' from frontmatter",
+                        key
+                    ))
+                    .source(&self.root),
+                ),
+            }
+        }
+        if let Some(key) = exported_at_key {
+            frontmatter.insert(
+                Value::String(key.to_string()),
+                Value::String(format_timestamp(SystemTime::now())),
+            );
+        }
+        frontmatter
+    }
+}
+
+// Return the hash of the commit checked out in the git repository containing `path`, or None
+// when it isn't part of a git repository (or git isn't available).
+fn git_commit(path: &Path) -> Option<String> {
+    let dir = match path.is_file() {
+        true => path.parent()?,
+        false => path,
+    };
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8(output.stdout).ok()?;
+    Some(commit.trim().to_string())
+}
+
+// Format `time` as an RFC 3339 timestamp in UTC, such as `2022-01-31T12:00:00Z`.
+fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // Convert days since the epoch to a civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn timestamps_are_formatted_as_rfc3339() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400 + 3_723)),
+            "2000-02-29T01:02:03Z"
+        );
+    }
+}
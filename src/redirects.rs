@@ -0,0 +1,91 @@
+use crate::frontmatter::note_aliases;
+use crate::json::json_string;
+use crate::{path_to_link, Context, Exporter, Result, WriteError, PERCENTENCODE_CHARS};
+use percent_encoding::utf8_percent_encode;
+use snafu::ResultExt;
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use std::path::Path;
+
+impl<'a> Exporter<'a> {
+    // Remember the names under which the note described by `context` could previously be linked
+    // to, for inclusion in the redirect map.
+    pub(crate) fn record_redirects(&self, context: &Context) {
+        let relative_path = context
+            .destination
+            .strip_prefix(&self.destination)
+            .unwrap_or(&context.destination);
+        let url = path_to_link(relative_path);
+
+        let mut names = vec![context
+            .current_file()
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()];
+        names.extend(note_aliases(&context.frontmatter));
+
+        let mut redirects = self.redirects.lock().expect("redirects lock poisoned");
+        for name in names {
+            redirects.push((name.trim().to_string(), url.clone()));
+        }
+    }
+
+    // Return the redirects recorded during the export, mapping each name to a single URL.
+    fn redirect_map(&self) -> BTreeMap<String, String> {
+        let mut redirects = self
+            .redirects
+            .lock()
+            .expect("redirects lock poisoned")
+            .clone();
+        // Sort by URL as well, so the note a name maps to is deterministic when several notes
+        // share an alias.
+        redirects.sort();
+        let mut map = BTreeMap::new();
+        for (name, url) in redirects {
+            if !name.is_empty() {
+                map.entry(name).or_insert(url);
+            }
+        }
+        map
+    }
+
+    // Write the redirects recorded during the export to the file `path`, relative to the
+    // destination.
+    pub(crate) fn write_redirect_map(&self, path: &Path) -> Result<()> {
+        let entries: Vec<String> = self
+            .redirect_map()
+            .iter()
+            .map(|(name, url)| format!("  {}: {}", json_string(name), json_string(url)))
+            .collect();
+        let output = match entries.is_empty() {
+            true => "{}\n".to_string(),
+            false => format!("{{\n{}\n}}\n", entries.join(",\n")),
+        };
+        self.write_redirects_file(path, &output)
+    }
+
+    // Write the redirects recorded during the export to the file `path`, relative to the
+    // destination, in the `_redirects` format used by Netlify and Cloudflare Pages.
+    pub(crate) fn write_netlify_redirects(&self, path: &Path) -> Result<()> {
+        let output: String = self
+            .redirect_map()
+            .iter()
+            .map(|(name, url)| {
+                format!(
+                    "/{} /{} 301\n",
+                    utf8_percent_encode(name, PERCENTENCODE_CHARS),
+                    url
+                )
+            })
+            .collect();
+        self.write_redirects_file(path, &output)
+    }
+
+    fn write_redirects_file(&self, path: &Path, output: &str) -> Result<()> {
+        let dest = self.destination.join(path);
+        self.create_output(&dest)?
+            .write_all(output.as_bytes())
+            .context(WriteError { path: &dest })
+    }
+}
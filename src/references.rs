@@ -1,9 +1,52 @@
 use regex::Regex;
 use std::fmt;
 
+/// The prefix of a section which marks a reference to a frontmatter field (`![[Note#meta:field]]`).
+pub const FRONTMATTER_FIELD_PREFIX: &str = "meta:";
+
 lazy_static! {
     static ref OBSIDIAN_NOTE_LINK_RE: Regex =
         Regex::new(r"^(?P<file>[^#|]+)??(#(?P<section>.+?))??(\|(?P<label>.+?))??$").unwrap();
+    static ref HTML_REFERENCE_RE: Regex = Regex::new(
+        r#"(?i)(?P<prefix><(?:img|a)\b[^>]*?\s(?:src|href)\s*=\s*)(?:"(?P<dq>[^"]*)"|'(?P<sq>[^']*)')"#
+    )
+    .unwrap();
+    static ref WIKILINK_RE: Regex = Regex::new(r"!?\[\[(?P<text>[^\[\]]+)\]\]").unwrap();
+}
+
+/// Replace `[[note]]` and `![[embed]]` references in `text` with the text they would be displayed
+/// as. This is used where links can't be placed, such as the alt text and title of images.
+pub fn wikilinks_to_text(text: &str) -> String {
+    WIKILINK_RE
+        .replace_all(text, |captures: &regex::Captures| {
+            let reference = ObsidianNoteReference::from_str(&captures["text"]);
+            match (reference.file, reference.section, reference.label) {
+                (None, None, None) => captures[0].to_string(),
+                _ => reference.display(),
+            }
+        })
+        .into_owned()
+}
+
+/// Rewrite the `src` and `href` attributes of `<img>` and `<a>` tags found in `html`.
+///
+/// `rewrite` is called with the value of each attribute. When it returns `Some`, the attribute's
+/// value is replaced with the returned value, otherwise it's left untouched.
+pub fn rewrite_html_references<F>(html: &str, rewrite: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    HTML_REFERENCE_RE
+        .replace_all(html, |captures: &regex::Captures| {
+            let (quote, value) = match (captures.name("dq"), captures.name("sq")) {
+                (Some(value), _) => ('"', value.as_str()),
+                (_, Some(value)) => ('\'', value.as_str()),
+                _ => unreachable!("html reference regex matched without a value"),
+            };
+            let value = rewrite(value).unwrap_or_else(|| value.to_string());
+            format!("{}{}{}{}", &captures["prefix"], quote, value, quote)
+        })
+        .into_owned()
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -73,9 +116,17 @@ impl<'a> ObsidianNoteReference<'a> {
         let captures = OBSIDIAN_NOTE_LINK_RE
             .captures(text)
             .expect("note link regex didn't match - bad input?");
-        let file = captures.name("file").map(|v| v.as_str());
-        let label = captures.name("label").map(|v| v.as_str());
-        let section = captures.name("section").map(|v| v.as_str());
+        // Whitespace surrounding any of the parts (`[[ Note # Heading | Label ]]`) is not
+        // significant, so it's trimmed before lookup.
+        let part = |name| {
+            captures
+                .name(name)
+                .map(|v| v.as_str().trim())
+                .filter(|v| !v.is_empty())
+        };
+        let file = part("file");
+        let label = part("label");
+        let section = part("section");
 
         ObsidianNoteReference {
             file,
@@ -84,6 +135,13 @@ impl<'a> ObsidianNoteReference<'a> {
         }
     }
 
+    /// Return the name of the frontmatter field being referenced, if this is a reference of the
+    /// form `Note#meta:field`.
+    pub fn frontmatter_field(&self) -> Option<&'a str> {
+        self.section
+            .and_then(|section| section.strip_prefix(FRONTMATTER_FIELD_PREFIX))
+    }
+
     pub fn display(&self) -> String {
         format!("{}", self)
     }
@@ -153,6 +211,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn wikilinks_become_text() {
+        assert_eq!(
+            wikilinks_to_text("[[Note]] and [[Other#Heading|label]], ![[Embed]]"),
+            "Note and label, Embed"
+        );
+        assert_eq!(
+            wikilinks_to_text("[[ ]] [not a link]"),
+            "[[ ]] [not a link]"
+        );
+    }
+
+    #[test]
+    fn parse_padded_note_refs() {
+        assert_eq!(
+            ObsidianNoteReference::from_str(" Just a note "),
+            ObsidianNoteReference::from_str("Just a note"),
+        );
+        assert_eq!(
+            ObsidianNoteReference::from_str(" Note # with heading | A label "),
+            ObsidianNoteReference {
+                file: Some("Note"),
+                label: Some("A label"),
+                section: Some("with heading"),
+            }
+        );
+        assert_eq!(
+            ObsidianNoteReference::from_str(" #Heading"),
+            ObsidianNoteReference {
+                file: None,
+                label: None,
+                section: Some("Heading"),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_frontmatter_field_refs() {
+        assert_eq!(
+            ObsidianNoteReference::from_str("Note#meta:author").frontmatter_field(),
+            Some("author")
+        );
+        assert_eq!(
+            ObsidianNoteReference::from_str("Note#author").frontmatter_field(),
+            None
+        );
+        assert_eq!(
+            ObsidianNoteReference::from_str("Note").frontmatter_field(),
+            None
+        );
+    }
+
+    #[test]
+    fn rewrite_html_img_and_a_references() {
+        let rewrite = |value: &str| match value {
+            "x.png" => Some("y.png".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            rewrite_html_references(r#"<img alt="x" src="x.png"> <IMG SRC='x.png'/>"#, rewrite),
+            r#"<img alt="x" src="y.png"> <IMG SRC='y.png'/>"#
+        );
+        assert_eq!(
+            rewrite_html_references(r#"<a href="x.png">link</a> <a href="z.png">"#, rewrite),
+            r#"<a href="y.png">link</a> <a href="z.png">"#
+        );
+        assert_eq!(
+            rewrite_html_references(r#"<div src="x.png"><image src="x.png">"#, rewrite),
+            r#"<div src="x.png"><image src="x.png">"#
+        );
+    }
+
     #[test]
     fn test_display_of_note_refs() {
         assert_eq!(
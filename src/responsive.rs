@@ -0,0 +1,63 @@
+use crate::references::ObsidianNoteReference;
+use crate::{Context, Exporter, MarkdownEvents};
+use pulldown_cmark::escape::escape_html;
+use pulldown_cmark::{CowStr, Event};
+use std::path::{Path, PathBuf};
+
+impl<'a> Exporter<'a> {
+    // Return the variants of the image at `path` which exist in the vault (such as `img@2x.png`
+    // for `img.png`), along with their srcset descriptors, ordered by suffix.
+    pub(crate) fn image_variants(&self, path: &Path) -> Vec<(PathBuf, &str)> {
+        if self.responsive_images.is_empty() {
+            return vec![];
+        }
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let mut suffixes: Vec<(&String, &String)> = self.responsive_images.iter().collect();
+        suffixes.sort();
+        suffixes
+            .into_iter()
+            .filter_map(|(suffix, descriptor)| {
+                let mut filename = format!("{}{}", stem, suffix);
+                if let Some(extension) = path.extension() {
+                    filename.push('.');
+                    filename.push_str(&extension.to_string_lossy());
+                }
+                let variant = path.with_file_name(filename);
+                self.vault_contents
+                    .as_ref()
+                    .unwrap()
+                    .contains(&variant)
+                    .then_some((variant, descriptor.as_str()))
+            })
+            .collect()
+    }
+
+    // Turn an embed of the image at `path` into an `<img>` tag listing the image and its variants
+    // in its srcset (see Exporter::responsive_images).
+    pub(crate) fn make_responsive_image<'c>(
+        &self,
+        reference: ObsidianNoteReference,
+        path: &Path,
+        context: &Context,
+    ) -> MarkdownEvents<'c> {
+        let mut sources = vec![format!("{} 1x", self.link_to_path(path, context))];
+        for (variant, descriptor) in self.image_variants(path) {
+            sources.push(format!(
+                "{} {}",
+                self.link_to_path(&variant, context),
+                descriptor
+            ));
+        }
+        let mut html = String::from("<img src=\"");
+        escape_html(&mut html, &self.link_to_path(path, context))
+            .expect("formatting to string not expected to fail");
+        html.push_str("\" srcset=\"");
+        escape_html(&mut html, &sources.join(", "))
+            .expect("formatting to string not expected to fail");
+        html.push_str("\" alt=\"");
+        escape_html(&mut html, &reference.display())
+            .expect("formatting to string not expected to fail");
+        html.push_str("\">");
+        vec![Event::Html(CowStr::from(html))]
+    }
+}
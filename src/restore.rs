@@ -0,0 +1,140 @@
+use crate::{is_absolute_url, is_markdown_file, normalize_path, Exporter};
+use percent_encoding::percent_decode_str;
+use regex::{Captures, Regex};
+use slug::slugify;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+impl<'a> Exporter<'a> {
+    /// Return a map of the files written by the last export to the files in the vault they were
+    /// exported from.
+    ///
+    /// Keys are paths relative to the destination and values are paths relative to the root of
+    /// the vault. This map is meant to be passed to [restore_wikilinks]. It's empty until the
+    /// exporter has run.
+    pub fn link_map(&self) -> HashMap<PathBuf, PathBuf> {
+        let vault_contents = match &self.vault_contents {
+            Some(vault_contents) => vault_contents,
+            None => return HashMap::new(),
+        };
+        vault_contents
+            .iter()
+            .filter(|file| file.starts_with(&self.start_at))
+            .map(|file| {
+                let relative_path = file
+                    .strip_prefix(self.output_root())
+                    .expect("file should always be nested under root");
+                let destination = self.output_path(file, &self.destination.join(relative_path));
+                (
+                    destination
+                        .strip_prefix(&self.destination)
+                        .unwrap_or(&destination)
+                        .to_path_buf(),
+                    file.strip_prefix(&self.root).unwrap_or(file).to_path_buf(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Turn the links of an exported note back into Obsidian's wikilink syntax.
+///
+/// This is the (best-effort) inverse of the link resolution done during an export, meant for
+/// checking the fidelity of an export through round-trip tests. `note` is the path of the exported
+/// note relative to the destination, and `link_map` maps exported files to the files in the vault
+/// they came from, as returned by [Exporter::link_map].
+///
+/// Links and image embeds which point to a file in `link_map` are restored to `[[Note]]`,
+/// `[[Note#Heading]]`, `[[Note|label]]` and `![[image.png]]` form. Notes are referred to by their
+/// name, unless several files in the vault share that name, in which case their full path is used.
+/// Other links are left untouched. Embedded notes have been merged into the note during the export
+/// and can't be restored.
+pub fn restore_wikilinks(
+    content: &str,
+    note: &Path,
+    link_map: &HashMap<PathBuf, PathBuf>,
+) -> String {
+    lazy_static! {
+        static ref MARKDOWN_LINK_RE: Regex =
+            Regex::new(r"(?P<embed>!?)\[(?P<label>[^\]]*)\]\((?P<target>[^)\s]+)\)").unwrap();
+    }
+    let note_dir = note.parent().unwrap_or_else(|| Path::new(""));
+    MARKDOWN_LINK_RE
+        .replace_all(content, |captures: &Captures| {
+            restore_wikilink(
+                !captures["embed"].is_empty(),
+                &captures["label"],
+                &captures["target"],
+                note_dir,
+                link_map,
+            )
+            .unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+fn restore_wikilink(
+    embed: bool,
+    label: &str,
+    target: &str,
+    note_dir: &Path,
+    link_map: &HashMap<PathBuf, PathBuf>,
+) -> Option<String> {
+    if is_absolute_url(target) {
+        return None;
+    }
+    let (path, fragment) = match target.find('#') {
+        Some(index) => (&target[..index], &target[index + 1..]),
+        None => (target, ""),
+    };
+    let path = percent_decode_str(path).decode_utf8_lossy();
+    let vault_path = link_map.get(&normalize_path(&note_dir.join(path.as_ref())))?;
+    let reference = wikilink_reference(vault_path, link_map);
+    if embed {
+        return Some(format!("![[{}]]", reference));
+    }
+
+    // Headings are slugified in links, so the heading itself is recovered from the default label
+    // of links to a section (`Note > Heading`) where possible.
+    let section = match fragment {
+        "" => None,
+        fragment => Some(
+            label
+                .strip_prefix(&format!("{} > ", reference))
+                .filter(|heading| slugify(heading) == fragment)
+                .unwrap_or(fragment),
+        ),
+    };
+    let (reference, default_label) = match section {
+        Some(section) => (
+            format!("{}#{}", reference, section),
+            format!("{} > {}", reference, section),
+        ),
+        None => (reference.clone(), reference),
+    };
+    match label == default_label {
+        true => Some(format!("[[{}]]", reference)),
+        false => Some(format!("[[{}|{}]]", reference, label)),
+    }
+}
+
+// Return how the file at `vault_path` is referred to in a wikilink: its name (without `.md` for
+// notes), or its full path when that name is shared with another file.
+fn wikilink_reference(vault_path: &Path, link_map: &HashMap<PathBuf, PathBuf>) -> String {
+    let name = vault_path.file_name();
+    let path = match link_map
+        .values()
+        .any(|other| other != vault_path && other.file_name() == name)
+    {
+        true => vault_path.to_path_buf(),
+        false => PathBuf::from(name.unwrap_or_default()),
+    };
+    let path = match is_markdown_file(&path) {
+        true => path.with_extension(""),
+        false => path,
+    };
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
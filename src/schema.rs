@@ -0,0 +1,112 @@
+use crate::json::frontmatter_to_json;
+use crate::summary::Warning;
+use crate::{ExportError, Exporter, Frontmatter, ReadError, Result, SchemaDecodeError};
+use jsonschema::Validator;
+use snafu::ResultExt;
+use std::fs;
+use std::path::Path;
+
+impl<'a> Exporter<'a> {
+    // Read and compile the JSON schema at `path`.
+    pub(crate) fn load_frontmatter_schema(path: &Path) -> Result<Validator> {
+        let content = fs::read_to_string(path).context(ReadError { path })?;
+        let schema = serde_json::from_str(&content).context(SchemaDecodeError { path })?;
+        jsonschema::validator_for(&schema).map_err(|err| ExportError::InvalidSchema {
+            path: path.to_path_buf(),
+            message: err.to_string(),
+        })
+    }
+
+    // Check the frontmatter of the note at `src` against the schema set through
+    // Exporter::frontmatter_schema, failing on violations when `strict` and warning otherwise.
+    pub(crate) fn check_frontmatter_schema(
+        &self,
+        src: &Path,
+        frontmatter: &Frontmatter,
+        strict: bool,
+    ) -> Result<()> {
+        let schema = match &self.schema {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+        let violations = schema_violations(schema, frontmatter);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        if strict {
+            return Err(ExportError::FrontmatterSchemaViolation {
+                path: src.to_path_buf(),
+                violations,
+            });
+        }
+        let warning = violations.iter().fold(
+            Warning::new("Frontmatter does not match the schema"),
+            |warning, violation| warning.detail("Violation", violation),
+        );
+        self.warn(warning.source(src));
+        Ok(())
+    }
+}
+
+// Validate `frontmatter` against `schema`, returning a description of each violation. Violations
+// below the top level of the frontmatter are prefixed with their location as a JSON pointer (such
+// as `/tags/1`).
+//
+// The frontmatter is validated as it would be written out as JSON, so keys which aren't strings
+// are converted to strings and numbers which JSON can't represent (such as `.nan`) become `null`.
+pub(crate) fn schema_violations(schema: &Validator, frontmatter: &Frontmatter) -> Vec<String> {
+    let instance: serde_json::Value = serde_json::from_str(&frontmatter_to_json(frontmatter))
+        .expect("frontmatter_to_json should always produce valid JSON");
+    schema
+        .iter_errors(&instance)
+        .map(|err| match err.instance_path().as_str() {
+            "" => err.to_string(),
+            location => format!("'{}': {}", location, err),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontmatter::frontmatter_from_str;
+    use serde_json::json;
+
+    #[test]
+    fn violations() {
+        let schema = jsonschema::validator_for(&json!({
+            "type": "object",
+            "required": ["title", "tags"],
+            "properties": {
+                "title": {"type": "string", "minLength": 1},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "status": {"enum": ["draft", "published"]},
+                "weight": {"type": "integer", "minimum": 0},
+                "date": {"anyOf": [{"type": "string"}, {"type": "null"}]}
+            },
+            "additionalProperties": false
+        }))
+        .unwrap();
+        let valid =
+            frontmatter_from_str("title: Note\ntags: [a, b]\nstatus: draft\nweight: 2\ndate: ~")
+                .unwrap();
+        assert_eq!(schema_violations(&schema, &valid), Vec::<String>::new());
+
+        let invalid =
+            frontmatter_from_str("tags: [a, 1]\nstatus: done\nweight: -1\ndate: 3\nextra: true")
+                .unwrap();
+        let mut violations = schema_violations(&schema, &invalid);
+        violations.sort();
+        assert_eq!(
+            violations,
+            vec![
+                "\"title\" is a required property",
+                "'/date': 3 is not valid under any of the schemas listed in the 'anyOf' keyword",
+                "'/status': \"done\" is not one of \"draft\" or \"published\"",
+                "'/tags/1': 1 is not of type \"string\"",
+                "'/weight': -1 is less than the minimum of 0",
+                "Additional properties are not allowed ('extra' was unexpected)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use crate::json::json_string;
+use crate::{path_to_link, Context, Exporter, MarkdownEvents, Result, WriteError};
+use pulldown_cmark::{Event, Tag};
+use serde_yaml::Value;
+use snafu::ResultExt;
+use std::io::prelude::*;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The layout of the records in a search index written through
+/// [Exporter::search_index_output][crate::Exporter::search_index_output].
+pub enum SearchIndexFormat {
+    /// Records for [Fuse.js](https://www.fusejs.io/), with `url`, `title`, `tags` (a list) and
+    /// `content` keys.
+    Fuse,
+    /// Records for [Lunr](https://lunrjs.com/), which only indexes strings. These have the same
+    /// keys as [SearchIndexFormat::Fuse], but `tags` is a single space-separated string and an
+    /// `id` key (equal to the URL) is added to use as the `ref` of the index.
+    Lunr,
+}
+
+// A note as it appears in the search index.
+#[derive(Debug, Clone)]
+pub(crate) struct SearchRecord {
+    url: String,
+    title: String,
+    tags: Vec<String>,
+    content: String,
+}
+
+impl<'a> Exporter<'a> {
+    // Add the note described by `context` and `events` to the search index.
+    pub(crate) fn record_search_entry(&self, context: &Context, events: &MarkdownEvents) {
+        let relative_path = context
+            .destination
+            .strip_prefix(&self.destination)
+            .unwrap_or(&context.destination);
+        let title = match context.frontmatter.get(&Value::String("title".to_string())) {
+            Some(Value::String(title)) => title.to_string(),
+            _ => context
+                .current_file()
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        };
+        let record = SearchRecord {
+            url: path_to_link(relative_path),
+            title,
+            tags: context.tags(),
+            content: plain_text(events),
+        };
+        self.search_index
+            .lock()
+            .expect("search index lock poisoned")
+            .push(record);
+    }
+
+    // Write the search index built during the export to the file `path`, relative to the
+    // destination.
+    pub(crate) fn write_search_index(&self, path: &Path, format: SearchIndexFormat) -> Result<()> {
+        let mut records = self
+            .search_index
+            .lock()
+            .expect("search index lock poisoned")
+            .clone();
+        records.sort_by(|a, b| a.url.cmp(&b.url));
+
+        let records: Vec<String> = records
+            .iter()
+            .map(|record| {
+                let tags = match format {
+                    SearchIndexFormat::Fuse => format!(
+                        "[{}]",
+                        record
+                            .tags
+                            .iter()
+                            .map(|tag| json_string(tag))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    SearchIndexFormat::Lunr => json_string(&record.tags.join(" ")),
+                };
+                let id = match format {
+                    SearchIndexFormat::Fuse => String::new(),
+                    SearchIndexFormat::Lunr => format!("\"id\": {}, ", json_string(&record.url)),
+                };
+                format!(
+                    "  {{{}\"url\": {}, \"title\": {}, \"tags\": {}, \"content\": {}}}",
+                    id,
+                    json_string(&record.url),
+                    json_string(&record.title),
+                    tags,
+                    json_string(&record.content),
+                )
+            })
+            .collect();
+        let output = match records.is_empty() {
+            true => "[]\n".to_string(),
+            false => format!("[\n{}\n]\n", records.join(",\n")),
+        };
+        let dest = self.destination.join(path);
+        self.create_output(&dest)?
+            .write_all(output.as_bytes())
+            .context(WriteError { path: &dest })
+    }
+}
+
+// Extract the text of the given events, with blocks separated by (and whitespace collapsed into)
+// single spaces. Raw HTML is left out.
+fn plain_text(events: &MarkdownEvents) -> String {
+    let mut text = String::new();
+    for event in events {
+        match event {
+            Event::Text(content) | Event::Code(content) => text.push_str(content),
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(Tag::Paragraph)
+            | Event::End(Tag::Heading(..))
+            | Event::End(Tag::Item)
+            | Event::End(Tag::TableCell)
+            | Event::End(Tag::CodeBlock(_)) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,240 @@
+use crate::log::LogLevel;
+use crate::Exporter;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The reason a file was left out of an export.
+pub enum SkipReason {
+    /// The note isn't valid UTF-8 and [EncodingErrorStrategy::Skip][crate::EncodingErrorStrategy::Skip]
+    /// is in effect.
+    InvalidEncoding,
+    /// The note doesn't have any of the tags set through
+    /// [Exporter::include_tags][crate::Exporter::include_tags].
+    ExcludedByTags,
+    /// A postprocessor or attachment processor returned
+    /// [PostprocessorResult::StopAndSkipNote][crate::PostprocessorResult::StopAndSkipNote].
+    Postprocessor,
+    /// A postprocessor (or attachment processor) panicked while processing the file and
+    /// [Exporter::catch_postprocessor_panics][crate::Exporter::catch_postprocessor_panics] is
+    /// enabled.
+    PostprocessorPanicked,
+    /// The note is empty and [Exporter::skip_empty_notes][crate::Exporter::skip_empty_notes] is
+    /// enabled.
+    Empty,
+    /// The note is identical to the note at its destination, so it wasn't rewritten (see
+    /// [Exporter::previous_export][crate::Exporter::previous_export]).
+    Unchanged,
+    /// The attachment is larger than the limit set through
+    /// [Exporter::max_attachment_size][crate::Exporter::max_attachment_size].
+    TooLarge,
+    /// The note contains a directive comment (such as `%% export: false %%`) which excludes it
+    /// from the export (see [Exporter::directive_comment][crate::Exporter::directive_comment]).
+    Directive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How warnings are printed to stderr, set through
+/// [Exporter::warning_format][crate::Exporter::warning_format].
+pub enum WarningFormat {
+    /// A human readable message, with details on the lines below it (the default).
+    Plain,
+    /// [Workflow commands](https://docs.github.com/en/actions/using-workflow-commands-for-github-actions#setting-a-warning-message)
+    /// (`::warning file=...,line=...::message`), which GitHub Actions shows as annotations on the
+    /// affected files.
+    GithubActions,
+}
+
+/// A function which is called with each warning instead of printing it to stderr, set through
+/// [Exporter::on_warning][crate::Exporter::on_warning].
+pub type WarningCallback = dyn Fn(&str) + Send + Sync;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// The outcome of an export, as returned by [Exporter::run_with_summary].
+pub struct RunSummary {
+    /// The number of notes which were written to the destination.
+    pub notes_exported: usize,
+    /// The files which were left out of the export, along with the reason why.
+    pub skipped: Vec<(PathBuf, SkipReason)>,
+    /// The number of attachments which were copied to the destination.
+    pub attachments_copied: usize,
+    /// The number of warnings printed during the export, such as for links to notes which don't
+    /// exist.
+    pub warnings: usize,
+    /// How long the export took.
+    pub duration: Duration,
+}
+
+// A warning about a problem found during an export: a message, followed by `Key: 'value'` details
+// on the lines below it.
+pub(crate) struct Warning {
+    message: String,
+    details: Vec<(&'static str, String)>,
+    file: Option<PathBuf>,
+    reference: Option<String>,
+}
+
+impl Warning {
+    pub(crate) fn new(message: impl fmt::Display) -> Warning {
+        Warning {
+            message: message.to_string(),
+            details: vec![],
+            file: None,
+            reference: None,
+        }
+    }
+
+    pub(crate) fn detail(mut self, key: &'static str, value: impl fmt::Display) -> Warning {
+        self.details.push((key, value.to_string()));
+        self
+    }
+
+    // The file the warning is about, which GitHub annotations are attached to.
+    pub(crate) fn source(mut self, path: &Path) -> Warning {
+        self.file = Some(path.to_path_buf());
+        self.detail("Source", path.display())
+    }
+
+    // The reference the warning is about. GitHub annotations point at the first line of the
+    // source file containing it.
+    pub(crate) fn reference(mut self, reference: impl fmt::Display) -> Warning {
+        let reference = reference.to_string();
+        self.reference = Some(reference.clone());
+        self.detail("Reference", reference)
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Warning: {}", self.message)?;
+        for (key, value) in &self.details {
+            writeln!(f, "\t{}: '{}'", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Exporter<'a> {
+    // Print a warning, counting it towards the summary of the current run.
+    pub(crate) fn warn(&self, warning: Warning) {
+        let message = warning.to_string();
+        if self.warnings_to_stderr {
+            let line = match self.warning_format {
+                WarningFormat::Plain => message.clone(),
+                WarningFormat::GithubActions => self.github_annotation(&warning),
+            };
+            match self.warning_callback {
+                Some(callback) => callback(&line),
+                None => eprintln!("{}", line),
+            }
+        }
+        self.log(LogLevel::Warning, &message);
+        self.summary.lock().expect("summary lock poisoned").warnings += 1;
+    }
+
+    // Turn a warning into a GitHub Actions workflow command. The line is looked up in the note
+    // contents kept by read_note, so files which aren't notes are annotated without one.
+    fn github_annotation(&self, warning: &Warning) -> String {
+        let mut properties = vec![];
+        if let Some(file) = &warning.file {
+            properties.push(format!("file={}", escape_property(&file.to_string_lossy())));
+            let line = warning.reference.as_ref().and_then(|reference| {
+                self.note_sources
+                    .lock()
+                    .expect("note sources lock poisoned")
+                    .get(file)?
+                    .lines()
+                    .position(|line| line.contains(reference.as_str()))
+            });
+            if let Some(line) = line {
+                properties.push(format!("line={}", line + 1));
+            }
+        }
+        // The file is already given by the annotation itself.
+        let file = warning.file.as_ref().map(|file| file.display().to_string());
+        let details: Vec<String> = warning
+            .details
+            .iter()
+            .filter(|(key, value)| !(*key == "Source" && Some(value) == file.as_ref()))
+            .map(|(key, value)| format!("{}: '{}'", key, value))
+            .collect();
+        let mut text = warning.message.clone();
+        if !details.is_empty() {
+            text.push_str(&format!(" ({})", details.join(", ")));
+        }
+        match properties.is_empty() {
+            true => format!("::warning::{}", escape_data(&text)),
+            false => format!("::warning {}::{}", properties.join(","), escape_data(&text)),
+        }
+    }
+
+    // Keep the contents of a note around for github_annotation, if warnings are printed as
+    // GitHub annotations.
+    pub(crate) fn record_note_source(&self, path: &Path, content: &str) {
+        if self.warnings_to_stderr && self.warning_format == WarningFormat::GithubActions {
+            self.note_sources
+                .lock()
+                .expect("note sources lock poisoned")
+                .insert(path.to_path_buf(), content.to_string());
+        }
+    }
+
+    pub(crate) fn record_exported_note(&self, src: &Path, dest: &Path) {
+        self.log(
+            LogLevel::Info,
+            &format!(
+                "Info: Exported note\n\tSource: '{}'\n\tDestination: '{}'",
+                src.display(),
+                dest.display()
+            ),
+        );
+        self.summary
+            .lock()
+            .expect("summary lock poisoned")
+            .notes_exported += 1;
+    }
+
+    pub(crate) fn record_copied_attachment(&self, src: &Path, dest: &Path) {
+        self.log(
+            LogLevel::Info,
+            &format!(
+                "Info: Copied attachment\n\tSource: '{}'\n\tDestination: '{}'",
+                src.display(),
+                dest.display()
+            ),
+        );
+        self.summary
+            .lock()
+            .expect("summary lock poisoned")
+            .attachments_copied += 1;
+    }
+
+    pub(crate) fn record_skipped(&self, path: &Path, reason: SkipReason) {
+        self.log(
+            LogLevel::Info,
+            &format!(
+                "Info: Skipped file\n\tReason: {:?}\n\tSource: '{}'",
+                reason,
+                path.display()
+            ),
+        );
+        self.summary
+            .lock()
+            .expect("summary lock poisoned")
+            .skipped
+            .push((path.to_path_buf(), reason));
+    }
+}
+
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
@@ -0,0 +1,109 @@
+use crate::Frontmatter;
+use pulldown_cmark::{Event, Tag};
+use regex::Regex;
+use serde_yaml::Value;
+
+lazy_static! {
+    static ref INLINE_TAG_RE: Regex = Regex::new(r"(?:^|\s)#([\w/-]+)").unwrap();
+}
+
+/// Normalize a tag by removing a leading `#` and converting it to lowercase, as tags in Obsidian
+/// are case-insensitive.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// Return true if `tag` is equal to `filter`, or is nested under it (`project/alpha` is nested
+/// under `project`).
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    let filter = normalize_tag(filter);
+    let tag = normalize_tag(tag);
+    tag == filter || tag.starts_with(&format!("{}/", filter))
+}
+
+/// Return the (normalized) tags defined under the `tags` or `tag` keys of the given frontmatter.
+///
+/// Tags may be given either as a list or as a single string of comma and/or space-separated tags.
+pub fn frontmatter_tags(frontmatter: &Frontmatter) -> Vec<String> {
+    let mut tags = vec![];
+    for key in &["tags", "tag"] {
+        match frontmatter.get(&Value::String(key.to_string())) {
+            Some(Value::String(value)) => tags.extend(
+                value
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|tag| !tag.is_empty())
+                    .map(normalize_tag),
+            ),
+            Some(Value::Sequence(values)) => {
+                tags.extend(values.iter().filter_map(|value| match value {
+                    Value::String(tag) => Some(normalize_tag(tag)),
+                    Value::Number(tag) => Some(tag.to_string()),
+                    _ => None,
+                }))
+            }
+            _ => {}
+        }
+    }
+    tags
+}
+
+/// Return the (normalized) inline `#tags` found in the text of the given events.
+///
+/// Text inside code blocks is ignored, as are purely numeric tags such as `#1`, which Obsidian
+/// doesn't consider to be tags either.
+pub fn inline_tags(events: &[Event]) -> Vec<String> {
+    let mut tags = vec![];
+    let mut in_code_block = false;
+    for event in events {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            Event::Text(text) if !in_code_block => {
+                tags.extend(
+                    INLINE_TAG_RE
+                        .captures_iter(text)
+                        .map(|captures| captures[1].to_string())
+                        .filter(|tag| !tag.chars().all(|c| c.is_numeric()))
+                        .map(|tag| normalize_tag(&tag)),
+                );
+            }
+            _ => {}
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use pulldown_cmark::{CodeBlockKind, CowStr};
+
+    #[test]
+    fn nested_tags_match_parent() {
+        assert!(tag_matches("project", "project"));
+        assert!(tag_matches("Project/Alpha", "#project"));
+        assert!(!tag_matches("projects", "project"));
+        assert!(!tag_matches("project", "project/alpha"));
+    }
+
+    #[test]
+    fn tags_from_frontmatter() {
+        let frontmatter: Frontmatter = serde_yaml::from_str("tags: '#One, two three'").unwrap();
+        assert_eq!(frontmatter_tags(&frontmatter), vec!["one", "two", "three"]);
+
+        let frontmatter: Frontmatter = serde_yaml::from_str("tag: [A/b, 2022]").unwrap();
+        assert_eq!(frontmatter_tags(&frontmatter), vec!["a/b", "2022"]);
+    }
+
+    #[test]
+    fn tags_from_text() {
+        let events = vec![
+            Event::Text(CowStr::from("#start text #Nested/tag and#not #1 ")),
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from("")))),
+            Event::Text(CowStr::from("#code")),
+            Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from("")))),
+        ];
+        assert_eq!(inline_tags(&events), vec!["start", "nested/tag"]);
+    }
+}
@@ -0,0 +1,33 @@
+use std::borrow::Cow;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// Convert `text` to Unicode Normalization Form C, composing decomposed characters such as an
+/// `e` followed by a combining acute accent (as stored by macOS in filenames) into their
+/// precomposed form (`é`).
+pub fn to_nfc(text: &str) -> Cow<'_, str> {
+    match is_nfc_quick(text.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(text),
+        IsNormalized::No | IsNormalized::Maybe => Cow::Owned(text.nfc().collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn composes_decomposed_text() {
+        assert_eq!(to_nfc("plain"), Cow::Borrowed("plain"));
+        assert_eq!(to_nfc("cafe\u{301}"), "caf\u{e9}");
+        assert_eq!(to_nfc("e\u{323}\u{302}"), "\u{1ec7}");
+        assert_eq!(to_nfc("\u{1112}\u{1161}\u{11ab}"), "\u{d55c}");
+        assert_eq!(to_nfc("x\u{301}"), "x\u{301}");
+        assert_eq!(to_nfc("a\u{316}\u{301}"), "\u{e1}\u{316}");
+        assert_eq!(
+            to_nfc("\u{30ab}\u{3099}\u{30a4}\u{30c8}\u{3099}"),
+            "\u{30ac}\u{30a4}\u{30c9}"
+        );
+        assert_eq!(to_nfc("\u{30cf}\u{309a}\u{30b9}"), "\u{30d1}\u{30b9}");
+    }
+}
@@ -24,6 +24,25 @@ pub struct WalkOptions<'a> {
     ///
     /// This is enabled by default.
     pub honor_gitignore: bool,
+    /// Whether to include the `.obsidian` directory, which holds the configuration of the vault.
+    ///
+    /// When enabled, its files are copied like any other attachment. This is disabled by default,
+    /// in which case the directory is excluded even when hidden files are exported.
+    pub include_obsidian_config: bool,
+    /// The maximum depth to descend into directories, where a depth of 1 includes only the files
+    /// directly inside the vault root.
+    ///
+    /// When exporting from a subdirectory through [`crate::Exporter::start_at`], the depth is
+    /// counted from that directory instead. By default there is no limit.
+    pub max_depth: Option<usize>,
+    /// Directories, relative to the vault root, whose markdown files are copied byte-for-byte
+    /// instead of being exported as notes (such as a `templates` directory).
+    ///
+    /// Links to these files still work, but unlike notes they keep their `.md` extension when
+    /// exporting to HTML.
+    pub verbatim_dirs: Vec<PathBuf>,
+    /// Directories, relative to the vault root, which are left out of the export entirely.
+    pub skip_dirs: Vec<PathBuf>,
     /// An optional custom filter function which is called for each directory entry to determine if
     /// it should be included or not.
     ///
@@ -41,6 +60,10 @@ impl<'a> fmt::Debug for WalkOptions<'a> {
             .field("ignore_filename", &self.ignore_filename)
             .field("ignore_hidden", &self.ignore_hidden)
             .field("honor_gitignore", &self.honor_gitignore)
+            .field("include_obsidian_config", &self.include_obsidian_config)
+            .field("max_depth", &self.max_depth)
+            .field("verbatim_dirs", &self.verbatim_dirs)
+            .field("skip_dirs", &self.skip_dirs)
             .field("filter_fn", &filter_fn_fmt)
             .finish()
     }
@@ -53,6 +76,10 @@ impl<'a> WalkOptions<'a> {
             ignore_filename: ".export-ignore",
             ignore_hidden: true,
             honor_gitignore: true,
+            include_obsidian_config: false,
+            max_depth: None,
+            verbatim_dirs: vec![],
+            skip_dirs: vec![],
             filter_fn: None,
         }
     }
@@ -67,11 +94,25 @@ impl<'a> WalkOptions<'a> {
             .require_git(true)
             .git_ignore(self.honor_gitignore)
             .git_global(self.honor_gitignore)
-            .git_exclude(self.honor_gitignore);
+            .git_exclude(self.honor_gitignore)
+            .max_depth(self.max_depth);
 
-        if let Some(filter) = self.filter_fn {
-            walker.filter_entry(filter);
-        }
+        let filter_fn = self.filter_fn;
+        let include_obsidian_config = self.include_obsidian_config;
+        // Pruning skipped directories here means the walker never descends into them.
+        let skip_dirs: Vec<PathBuf> = self.skip_dirs.iter().map(|dir| path.join(dir)).collect();
+        walker.filter_entry(move |entry| {
+            if !include_obsidian_config && is_obsidian_config(entry) {
+                return false;
+            }
+            if skip_dirs.iter().any(|dir| entry.path() == dir) {
+                return false;
+            }
+            match filter_fn {
+                Some(filter) => filter(entry),
+                None => true,
+            }
+        });
         walker.build()
     }
 }
@@ -82,6 +123,36 @@ impl<'a> Default for WalkOptions<'a> {
     }
 }
 
+// Whether `entry` is the `.obsidian` directory in which Obsidian stores the configuration of a vault.
+fn is_obsidian_config(entry: &DirEntry) -> bool {
+    entry.file_name() == ".obsidian" && entry.file_type().is_some_and(|kind| kind.is_dir())
+}
+
+// Return the errors in ignore files (such as invalid glob patterns) which are encountered when
+// walking the vault at `path` using the given [WalkOptions].
+pub(crate) fn ignore_pattern_errors(path: &Path, opts: WalkOptions) -> Vec<ignore::Error> {
+    // Errors in the ignore files of a directory are attached to the entry of that directory,
+    // rather than failing the entry.
+    opts.build_walker(path)
+        .filter_map(|entry| match entry {
+            Ok(entry) => entry.error().cloned(),
+            Err(err) => Some(err),
+        })
+        .filter(is_glob_error)
+        .collect()
+}
+
+fn is_glob_error(err: &ignore::Error) -> bool {
+    match err {
+        ignore::Error::Glob { .. } => true,
+        ignore::Error::Partial(errs) => errs.iter().any(is_glob_error),
+        ignore::Error::WithLineNumber { err, .. }
+        | ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. } => is_glob_error(err),
+        _ => false,
+    }
+}
+
 /// `vault_contents` returns all of the files in an Obsidian vault located at `path` which would be
 /// exported when using the given [WalkOptions].
 pub fn vault_contents(path: &Path, opts: WalkOptions) -> Result<Vec<PathBuf>> {
@@ -0,0 +1,115 @@
+use crate::json::{frontmatter_to_json, json_string};
+use crate::{
+    event_to_owned, parser_options, render_mdevents_to_mdtext, Context, Frontmatter,
+    MarkdownEvents, PostprocessorResult,
+};
+use pulldown_cmark::Parser;
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::path::Path;
+use wasmtime::{Engine, Instance, Module, Store};
+
+// The result codes a WASM postprocessor may return, mirroring PostprocessorResult.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WasmResult {
+    Continue,
+    StopHere,
+    StopAndSkipNote,
+    Abort,
+}
+
+// The document returned by the `process` function of a WASM postprocessor.
+#[derive(Deserialize)]
+struct WasmOutput {
+    markdown: String,
+    frontmatter: Option<serde_json::Value>,
+    result: Option<WasmResult>,
+    message: Option<String>,
+}
+
+// A postprocessor implemented by a WebAssembly module, see Exporter::add_wasm_postprocessor.
+pub(crate) struct WasmPostprocessor {
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmPostprocessor {
+    // Compile the module (in either the binary or the text format) at `path`.
+    pub(crate) fn load(path: &Path) -> Result<WasmPostprocessor, String> {
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, path).map_err(|err| format!("{:#}", err))?;
+        Ok(WasmPostprocessor { engine, module })
+    }
+
+    pub(crate) fn call<'b>(
+        &self,
+        mut context: Context,
+        events: MarkdownEvents<'b>,
+    ) -> (Context, MarkdownEvents<'b>, PostprocessorResult) {
+        let input = format!(
+            "{{\"frontmatter\":{},\"markdown\":{}}}",
+            frontmatter_to_json(&context.frontmatter),
+            json_string(&render_mdevents_to_mdtext(events.clone())),
+        );
+        let output = match self.run(input.as_bytes()) {
+            Ok(output) => output,
+            Err(message) => return (context, events, PostprocessorResult::Abort(message)),
+        };
+
+        if let Some(frontmatter) = output.frontmatter {
+            match serde_yaml::to_value(frontmatter) {
+                Ok(Value::Mapping(frontmatter)) => context.frontmatter = frontmatter,
+                Ok(Value::Null) => context.frontmatter = Frontmatter::new(),
+                _ => {
+                    let message = "WASM postprocessor returned frontmatter which isn't an object";
+                    return (context, events, PostprocessorResult::Abort(message.into()));
+                }
+            }
+        }
+        let events = Parser::new_ext(&output.markdown, parser_options())
+            .map(event_to_owned)
+            .collect();
+        let result = match output.result.unwrap_or(WasmResult::Continue) {
+            WasmResult::Continue => PostprocessorResult::Continue,
+            WasmResult::StopHere => PostprocessorResult::StopHere,
+            WasmResult::StopAndSkipNote => PostprocessorResult::StopAndSkipNote,
+            WasmResult::Abort => PostprocessorResult::Abort(output.message.unwrap_or_default()),
+        };
+        (context, events, result)
+    }
+
+    // Pass `input` to a fresh instance of the module and decode what it returns.
+    //
+    // Every note gets an instance of its own, so modules don't need to free memory they allocate
+    // and notes can be processed in parallel.
+    fn run(&self, input: &[u8]) -> Result<WasmOutput, String> {
+        let error = |err: wasmtime::Error| format!("WASM postprocessor failed: {:#}", err);
+        let mut store = Store::new(&self.engine, ());
+        let instance = Instance::new(&mut store, &self.module, &[]).map_err(error)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or("WASM postprocessor doesn't export its memory")?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "alloc")
+            .map_err(error)?;
+        let process = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, "process")
+            .map_err(error)?;
+
+        let len = input.len() as i32;
+        let ptr = alloc.call(&mut store, len).map_err(error)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input)
+            .map_err(|err| error(err.into()))?;
+        let packed = process.call(&mut store, (ptr, len)).map_err(error)? as u64;
+
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        let output = memory
+            .data(&store)
+            .get(ptr..ptr + len)
+            .ok_or("WASM postprocessor returned output outside of its memory")?;
+        serde_json::from_slice(output)
+            .map_err(|err| format!("WASM postprocessor returned invalid output: {}", err))
+    }
+}
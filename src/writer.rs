@@ -0,0 +1,42 @@
+use crate::{copy_file, create_file, Exporter, ReadError, Result, WriteError};
+use snafu::ResultExt;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// A function returning the writer which an exported file is written to, given the path of the
+/// file relative to the destination.
+///
+/// See [Exporter::set_writer_factory][crate::Exporter::set_writer_factory].
+pub type WriterFactory = dyn Fn(&Path) -> Box<dyn Write> + Send + Sync;
+
+impl<'a> Exporter<'a> {
+    // Return a writer for the exported file `dest`, which is either a newly created file or the
+    // writer returned by the writer factory.
+    pub(crate) fn create_output(&self, dest: &Path) -> Result<Box<dyn Write>> {
+        match self.writer_factory {
+            Some(factory) => Ok(factory(self.output_key(dest))),
+            None => Ok(Box::new(create_file(dest)?)),
+        }
+    }
+
+    // Copy the file `src` to the exported file `dest`.
+    pub(crate) fn copy_output(&self, src: &Path, dest: &Path) -> Result<()> {
+        if self.writer_factory.is_none() {
+            return copy_file(src, dest);
+        }
+        let contents = fs::read(src).context(ReadError { path: src })?;
+        self.create_output(dest)?
+            .write_all(&contents)
+            .context(WriteError { path: dest })
+    }
+
+    // Return the path of the exported file `dest` relative to the destination. When the
+    // destination names a single exported file, this is its filename.
+    fn output_key<'p>(&self, dest: &'p Path) -> &'p Path {
+        match dest.strip_prefix(&self.destination) {
+            Ok(path) if !path.as_os_str().is_empty() => path,
+            _ => dest.file_name().map(Path::new).unwrap_or(dest),
+        }
+    }
+}
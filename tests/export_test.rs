@@ -1,8 +1,24 @@
-use obsidian_export::{ExportError, Exporter, FrontmatterStrategy};
+// Lints added to clippy and rustc after large parts of these tests were written.
+#![allow(clippy::expect_fun_call, noop_method_call)]
+
+use obsidian_export::{
+    restore_wikilinks, BacklinkStrategy, BlockIdStyle, ChangeKind, CommentStrategy, Config,
+    ConfigError, EmbedAmbiguity, EncodingErrorStrategy, ExportError, Exporter, FrontmatterStrategy,
+    IndexConfig, IndexTitle, LinkEvent, LinkKind, LinkResolution, LinkStyle, LogLevel, NoteChange,
+    NoteOrder, OutputFormat, PostprocessorResult, Progress, ProgressPhase, SearchIndexFormat,
+    SharedAttachments, SidecarPrecedence, SkipReason, UnresolvedEmbedStyle, WalkOptions,
+};
 use pretty_assertions::assert_eq;
-use std::fs::{create_dir, read_to_string, set_permissions, File, Permissions};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fs::{
+    create_dir, create_dir_all, read, read_to_string, set_permissions, write, File, Permissions,
+};
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tempfile::TempDir;
 use walkdir::WalkDir;
 
@@ -102,6 +118,30 @@ fn test_frontmatter_always() {
     assert_eq!(expected, actual);
 }
 
+#[test]
+fn test_from_config() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let config = Config {
+        frontmatter_strategy: Some(FrontmatterStrategy::Always),
+        ignore_file: Some(".no-such-ignore-file".to_string()),
+        ..Default::default()
+    };
+    Exporter::from_config(
+        PathBuf::from("tests/testdata/input/main-samples/"),
+        tmp_dir.path().to_path_buf(),
+        &config,
+    )
+    .run()
+    .expect("exporter returned error");
+
+    assert_eq!(
+        "---\n---\n\nNote without frontmatter.\n",
+        read_to_string(tmp_dir.path().join("note-without-frontmatter.md")).unwrap()
+    );
+    // Notes listed in .export-ignore are exported, as another ignore file is used.
+    assert!(tmp_dir.path().join("excluded-note.md").exists());
+}
+
 #[test]
 fn test_exclude() {
     let tmp_dir = TempDir::new().expect("failed to make tempdir");
@@ -181,6 +221,45 @@ fn test_start_at_subdir() {
     );
 }
 
+#[test]
+fn test_start_at_as_root() {
+    for (as_root, dir) in [(true, ""), (false, "subdir/")] {
+        let tmp_dir = TempDir::new().expect("failed to make tempdir");
+        let mut exporter = Exporter::new(
+            PathBuf::from("tests/testdata/input/start-at/"),
+            tmp_dir.path().to_path_buf(),
+        );
+        exporter.start_at(PathBuf::from("tests/testdata/input/start-at/subdir"));
+        exporter.start_at_as_root(as_root);
+        exporter.run().unwrap();
+
+        let note = tmp_dir.path().join(format!("{}Note B.md", dir));
+        assert_eq!(
+            read_to_string("tests/testdata/expected/start-at/subdir/Note B.md").unwrap(),
+            read_to_string(&note).unwrap(),
+        );
+        assert!(tmp_dir.path().join(format!("{}Note C.md", dir)).exists());
+        assert!(!tmp_dir.path().join("Note A.md").exists());
+
+        let tmp_dir = TempDir::new().expect("failed to make tempdir");
+        let mut exporter = Exporter::new(
+            PathBuf::from("tests/testdata/input/start-at/"),
+            tmp_dir.path().to_path_buf(),
+        );
+        exporter.start_at(PathBuf::from("tests/testdata/input/start-at/subdir"));
+        exporter.start_at_as_root(as_root);
+        exporter.link_style(LinkStyle::AbsoluteFromRoot);
+        exporter.run().unwrap();
+
+        let note = read_to_string(tmp_dir.path().join(format!("{}Note B.md", dir))).unwrap();
+        assert!(
+            note.contains(&format!("[Note C](/{}Note%20C.md)", dir)),
+            "{}",
+            note
+        );
+    }
+}
+
 #[test]
 fn test_start_at_file_within_subdir_destination_is_dir() {
     let tmp_dir = TempDir::new().expect("failed to make tempdir");
@@ -420,6 +499,2344 @@ fn test_same_filename_different_directories() {
             .unwrap()
     };
 
-    let actual = read_to_string(tmp_dir.path().clone().join(PathBuf::from("Note.md"))).unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
     assert_eq!(expected, actual);
 }
+
+#[test]
+fn test_html_output_format() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/html-output/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.output_format(OutputFormat::Html);
+    exporter.run().expect("exporter returned error");
+
+    assert!(!tmp_dir.path().join("Note.md").exists());
+    let actual = read_to_string(tmp_dir.path().join("Note.html")).unwrap();
+    assert!(actual.contains("<title>A note in HTML</title>"));
+    assert!(actual.contains("<h1>Heading</h1>"));
+    assert!(actual.contains(r#"<a href="Other%20note.html">Other note</a>"#));
+    assert!(actual.contains(r#"<a href="Other%20note.html#some-section">a section</a>"#));
+    assert!(!actual.contains("title: A note in HTML"));
+    assert!(tmp_dir.path().join("Other note.html").exists());
+}
+
+#[test]
+fn test_html_output_format_with_template() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/html-output/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.output_format(OutputFormat::Html);
+    exporter.html_template("<main title=\"{{title}}\">{{content}}</main>".to_string());
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string(tmp_dir.path().join("Other note.html")).unwrap(),
+        "<main title=\"Other note\"><h1>Some section</h1>\n<p>Text in another note.</p>\n</main>",
+    );
+}
+
+#[test]
+fn test_html_template_file() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/html-template-file/vault/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.output_format(OutputFormat::Html);
+    exporter.html_template_file(PathBuf::from(
+        "tests/testdata/input/html-template-file/template.html",
+    ));
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    let page = read_to_string(tmp_dir.path().join("Page.html")).unwrap();
+    assert!(page.contains("<title>A Simple Page</title>"));
+    assert!(page.contains("<meta name=\"author\" content=\"Jane\">"));
+    assert!(page.contains("<main>\n<p>Some <strong>bold</strong> text.</p>\n\n</main>"));
+
+    // The note without an author gets an empty value for it, with a warning.
+    let anonymous = read_to_string(tmp_dir.path().join("Anonymous.html")).unwrap();
+    assert!(anonymous.contains("<title>Anonymous</title>"));
+    assert!(anonymous.contains("<meta name=\"author\" content=\"\">"));
+    assert_eq!(summary.warnings, 1);
+}
+
+#[test]
+fn test_metadata_comment_pattern() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/metadata-comments/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter
+        .metadata_comment_pattern(r"%%\+status:(?P<value>\w+)\+%%", "status")
+        .unwrap();
+    exporter
+        .metadata_comment_pattern(r"%%\+reviewer:(\w+)\+%%", "reviewer")
+        .unwrap();
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/expected/metadata-comments/Note.md").unwrap(),
+        read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap(),
+    );
+}
+
+#[test]
+fn test_metadata_comment_pattern_invalid_regex() {
+    let mut exporter = Exporter::new(PathBuf::from("src"), PathBuf::from("dest"));
+    match exporter.metadata_comment_pattern(r"%%(unclosed", "key") {
+        Err(ExportError::RegexError { pattern, .. }) => assert_eq!(pattern, "%%(unclosed"),
+        Err(err) => panic!("Wrong error variant: {:?}", err),
+        Ok(_) => panic!("expected an error for an invalid pattern"),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn test_validate_reports_all_problems() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let dest = tmp_dir.path().join("dest");
+    create_dir(&dest).unwrap();
+    set_permissions(&dest, Permissions::from_mode(0o555)).unwrap();
+
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/main-samples/"),
+        dest.clone(),
+    );
+    exporter.start_at(PathBuf::from(
+        "tests/testdata/input/main-samples/no-such-dir",
+    ));
+    let errors = exporter.validate().unwrap_err();
+
+    assert_eq!(errors.len(), 2, "unexpected errors: {:?}", errors);
+    assert!(errors
+        .iter()
+        .any(|err| matches!(err, ConfigError::StartAtNotFound { .. })));
+    assert!(errors
+        .iter()
+        .any(|err| matches!(err, ConfigError::DestinationNotWritable { path } if path == &dest)));
+}
+
+#[test]
+fn test_validate_destination_is_source() {
+    let exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/main-samples/"),
+        PathBuf::from("tests/testdata/input/main-samples"),
+    );
+    match exporter.validate().unwrap_err().as_slice() {
+        [ConfigError::DestinationIsSource { .. }] => {}
+        errors => panic!("unexpected errors: {:?}", errors),
+    }
+
+    let exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/main-samples/"),
+        PathBuf::from("tests/testdata/input/main-samples/../main-samples"),
+    );
+    match exporter.validate().unwrap_err().as_slice() {
+        [ConfigError::DestinationIsSource { .. }] => {}
+        errors => panic!("unexpected errors: {:?}", errors),
+    }
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let dest = tmp_dir.path().join("vault/export");
+    create_dir_all(&dest).unwrap();
+    let exporter = Exporter::new(tmp_dir.path().join("vault"), dest);
+    match exporter.validate().unwrap_err().as_slice() {
+        [ConfigError::DestinationInsideSource { .. }] => {}
+        errors => panic!("unexpected errors: {:?}", errors),
+    }
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/main-samples/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .validate()
+    .expect("expected a valid configuration");
+}
+
+#[test]
+fn test_validate_conflicting_keys_and_invalid_globs() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/invalid-ignore/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.publish_marker(".export-ignore".to_string());
+    exporter.directive_comment("export".to_string());
+    exporter.embed_skip_key("export".to_string());
+    let errors = exporter.validate().unwrap_err();
+
+    assert_eq!(errors.len(), 3, "unexpected errors: {:?}", errors);
+    assert!(errors.iter().any(|err| matches!(
+        err,
+        ConfigError::ConflictingKeys { key, .. } if key == ".export-ignore"
+    )));
+    assert!(errors.iter().any(|err| matches!(
+        err,
+        ConfigError::ConflictingKeys { key, .. } if key == "export"
+    )));
+    assert!(errors
+        .iter()
+        .any(|err| matches!(err, ConfigError::InvalidGlob { .. })));
+}
+
+#[test]
+fn test_frontmatter_field_embeds() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/frontmatter-field-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run()
+    .expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/expected/frontmatter-field-embeds/Review.md").unwrap(),
+        read_to_string(tmp_dir.path().join(PathBuf::from("Review.md"))).unwrap(),
+    );
+}
+
+#[test]
+fn test_block_embeds() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/table-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run()
+    .expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/expected/table-embeds/Summary.md").unwrap(),
+        read_to_string(tmp_dir.path().join(PathBuf::from("Summary.md"))).unwrap(),
+    );
+}
+
+#[test]
+fn test_list_item_and_missing_block_embeds() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let summary = Exporter::new(
+        PathBuf::from("tests/testdata/input/block-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run_with_summary()
+    .expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/expected/block-embeds/Embedder.md").unwrap(),
+        read_to_string(tmp_dir.path().join(PathBuf::from("Embedder.md"))).unwrap(),
+    );
+    assert_eq!(summary.warnings, 1);
+}
+
+#[test]
+fn test_embeds_by_alias() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/alias-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run()
+    .expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/expected/alias-embeds/Embedder.md").unwrap(),
+        read_to_string(tmp_dir.path().join(PathBuf::from("Embedder.md"))).unwrap(),
+    );
+}
+
+#[test]
+fn test_wrap_body() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/wrap-body/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.wrap_body(
+        "<article class=\"note\">".to_string(),
+        "</article>".to_string(),
+    );
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/expected/wrap-body/Note.md").unwrap(),
+        read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap(),
+    );
+}
+
+#[test]
+fn test_section_embeds_of_formatted_headings() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/formatted-headings/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run()
+    .expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/expected/formatted-headings/Embeds.md").unwrap(),
+        read_to_string(tmp_dir.path().join(PathBuf::from("Embeds.md"))).unwrap(),
+    );
+}
+
+#[test]
+fn test_section_range_embeds() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let summary = Exporter::new(
+        PathBuf::from("tests/testdata/input/section-ranges/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run_with_summary()
+    .expect("exporter returned error");
+
+    // Dotted heading.md embeds a heading containing `..`, which isn't a range.
+    for note in ["Range.md", "Open range.md", "Dotted heading.md"] {
+        assert_eq!(
+            read_to_string(PathBuf::from("tests/testdata/expected/section-ranges/").join(note))
+                .unwrap(),
+            read_to_string(tmp_dir.path().join(note)).unwrap(),
+            "{}",
+            note
+        );
+    }
+    // The end of the range in Open range.md doesn't exist.
+    assert_eq!(summary.warnings, 1);
+}
+
+#[test]
+fn test_skip_empty_notes() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/empty-notes/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.run().expect("exporter returned error");
+    assert!(tmp_dir.path().join("Stub.md").exists());
+    assert!(tmp_dir.path().join("Embeds ignored note.md").exists());
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/empty-notes/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.skip_empty_notes(true);
+    exporter.run().expect("exporter returned error");
+    assert!(tmp_dir.path().join("Note.md").exists());
+    assert!(!tmp_dir.path().join("Stub.md").exists());
+    assert!(!tmp_dir.path().join("Embeds ignored note.md").exists());
+}
+
+#[test]
+fn test_inject_slug() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/inject-slug/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.inject_slug("slug".to_string());
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "---\nslug: my-first-note\n---\n\nA note without frontmatter.\n",
+        read_to_string(tmp_dir.path().join(PathBuf::from("My First Note.md"))).unwrap(),
+    );
+    assert_eq!(
+        "---\nslug: custom-slug\n---\n\nA note with a custom slug.\n",
+        read_to_string(tmp_dir.path().join(PathBuf::from("Custom.md"))).unwrap(),
+    );
+}
+
+#[test]
+fn test_encoding_error_strategies() {
+    let src_dir = TempDir::new().expect("failed to make tempdir");
+    let mut file = File::create(src_dir.path().join("invalid.md")).unwrap();
+    file.write_all(b"Invalid \xff UTF-8\n").unwrap();
+    let mut file = File::create(src_dir.path().join("valid.md")).unwrap();
+    file.write_all(b"Valid UTF-8\n").unwrap();
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let err = Exporter::new(src_dir.path().to_path_buf(), tmp_dir.path().to_path_buf())
+        .run()
+        .unwrap_err();
+    match err {
+        ExportError::FileExportError { source, .. } => match *source {
+            ExportError::CharacterEncodingError { path, .. } => {
+                assert_eq!(path, src_dir.path().join("invalid.md"))
+            }
+            _ => panic!("Wrong error variant for source, got: {:?}", source),
+        },
+        err => panic!("Wrong error variant: {:?}", err),
+    }
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(src_dir.path().to_path_buf(), tmp_dir.path().to_path_buf());
+    exporter.on_encoding_error(EncodingErrorStrategy::Lossy);
+    exporter.run().expect("exporter returned error");
+    assert_eq!(
+        "Invalid \u{FFFD} UTF-8\n",
+        read_to_string(tmp_dir.path().join("invalid.md")).unwrap()
+    );
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(src_dir.path().to_path_buf(), tmp_dir.path().to_path_buf());
+    exporter.on_encoding_error(EncodingErrorStrategy::Skip);
+    exporter.run().expect("exporter returned error");
+    assert!(!tmp_dir.path().join("invalid.md").exists());
+    assert!(tmp_dir.path().join("valid.md").exists());
+}
+
+#[test]
+fn test_encoding_warnings_emitted_once() {
+    let src_dir = TempDir::new().expect("failed to make tempdir");
+    let mut file = File::create(src_dir.path().join("invalid.md")).unwrap();
+    file.write_all(b"Invalid \xff UTF-8\n").unwrap();
+    let mut file = File::create(src_dir.path().join("embeds.md")).unwrap();
+    file.write_all(b"![[invalid]]\n\n![[invalid]]\n").unwrap();
+
+    for strategy in [EncodingErrorStrategy::Lossy, EncodingErrorStrategy::Skip] {
+        let tmp_dir = TempDir::new().expect("failed to make tempdir");
+        let mut exporter =
+            Exporter::new(src_dir.path().to_path_buf(), tmp_dir.path().to_path_buf());
+        exporter.on_encoding_error(strategy);
+        let summary = exporter
+            .run_with_summary()
+            .expect("exporter returned error");
+        assert_eq!(summary.warnings, 1, "{:?}", strategy);
+    }
+}
+
+#[test]
+fn test_include_tags() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/include-tags/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.include_tags(vec!["project".to_string()]);
+    exporter.run().expect("exporter returned error");
+
+    assert!(tmp_dir.path().join("frontmatter-tag.md").exists());
+    assert!(tmp_dir.path().join("inline-tag.md").exists());
+    assert!(!tmp_dir.path().join("untagged.md").exists());
+    assert!(!tmp_dir.path().join("other-tag.md").exists());
+    assert!(!tmp_dir.path().join("embeds-tagged-note.md").exists());
+}
+
+#[test]
+fn test_html_references() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/html-references/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run()
+    .expect("exporter returned error");
+
+    assert!(tmp_dir.path().join("assets/image.png").exists());
+    for file in &["Embedder.md", "notes/Note.md"] {
+        let expected =
+            read_to_string(PathBuf::from("tests/testdata/expected/html-references/").join(file))
+                .unwrap_or_else(|_| panic!("failed to read expected {}", file));
+        let actual = read_to_string(tmp_dir.path().join(file))
+            .unwrap_or_else(|_| panic!("failed to read actual {}", file));
+        assert_eq!(expected, actual, "{}", file);
+    }
+}
+
+#[test]
+fn test_render_bases() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/bases/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.render_bases(true);
+    exporter.run().expect("exporter returned error");
+
+    assert!(!tmp_dir.path().join("Books.base").exists());
+    let expected = read_to_string("tests/testdata/expected/bases/Books.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join("Books.md")).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_render_bases_lists_exported_notes_only() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/bases/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.render_bases(true);
+    exporter.include_tags(vec!["classic".to_string()]);
+    exporter.run().expect("exporter returned error");
+
+    let actual = read_to_string(tmp_dir.path().join("Books.md")).unwrap();
+    assert!(actual.contains("[Dune](books/Dune.md)"));
+    assert!(!actual.contains("The Dispossessed"));
+}
+
+#[test]
+fn test_bases_copied_by_default() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/bases/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run()
+    .expect("exporter returned error");
+
+    let expected = read_to_string("tests/testdata/input/bases/Books.base").unwrap();
+    let actual = read_to_string(tmp_dir.path().join("Books.base")).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_concatenate_to() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/concatenate/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.concatenate_to(PathBuf::from("book.md"));
+    exporter.note_order(NoteOrder::FrontmatterKey {
+        key: "chapter".to_string(),
+        desc: false,
+    });
+    exporter.run().expect("exporter returned error");
+
+    let expected = read_to_string("tests/testdata/expected/concatenate/book.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join("book.md")).unwrap();
+    assert_eq!(expected, actual);
+    assert!(tmp_dir.path().join("chapters/image.png").exists());
+    assert!(!tmp_dir.path().join("chapters/Introduction.md").exists());
+}
+
+#[test]
+fn test_also_concatenate() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/concatenate/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.also_concatenate(
+        PathBuf::from("book.md"),
+        NoteOrder::FrontmatterKey {
+            key: "chapter".to_string(),
+            desc: false,
+        },
+    );
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    // The combined file is the same as when only concatenating, with links between notes
+    // pointing to anchors.
+    let expected = read_to_string("tests/testdata/expected/concatenate/book.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join("book.md")).unwrap();
+    assert_eq!(expected, actual);
+
+    // The individual notes link to each other's files.
+    assert_eq!(
+        "---\nchapter: 1\n---\n\n## Background\n\nSee the [Conclusion](Conclusion.md).\n",
+        read_to_string(tmp_dir.path().join("chapters/Introduction.md")).unwrap(),
+    );
+    assert_eq!(
+        concat!(
+            "---\nchapter: 2\n---\n\n",
+            "The end. Back to [the background](Introduction.md#background).\n\n",
+            "![image.png](image.png)\n"
+        ),
+        read_to_string(tmp_dir.path().join("chapters/Conclusion.md")).unwrap(),
+    );
+    assert!(tmp_dir.path().join("chapters/image.png").exists());
+    assert_eq!(summary.notes_exported, 2);
+}
+
+#[test]
+fn test_concatenate_links_to_anchors() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/concatenate-links/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.start_at(PathBuf::from("tests/testdata/input/concatenate-links/book"));
+    exporter.concatenate_to(PathBuf::from("book.md"));
+    exporter.note_order(NoteOrder::FrontmatterKey {
+        key: "chapter".to_string(),
+        desc: false,
+    });
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    let expected = read_to_string("tests/testdata/expected/concatenate-links/book.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join("book.md")).unwrap();
+    assert_eq!(expected, actual);
+    // Both the link to a note outside of the concatenated notes and the link to a missing note
+    // are reported.
+    assert_eq!(summary.warnings, 2);
+}
+
+#[test]
+fn test_concatenate_prefixed_anchors() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/concatenate-anchors/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.concatenate_to(PathBuf::from("book.md"));
+    exporter.note_order(NoteOrder::FrontmatterKey {
+        key: "chapter".to_string(),
+        desc: false,
+    });
+    exporter.prefix_concatenated_anchors(true);
+    exporter.run().expect("exporter returned error");
+
+    let expected = read_to_string("tests/testdata/expected/concatenate-anchors/book.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join("book.md")).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_embed_attachment_by_name() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let summary = Exporter::new(
+        PathBuf::from("tests/testdata/input/attachment-basename/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run_with_summary()
+    .expect("exporter returned error");
+
+    assert_eq!(
+        "See the diagram:\n\n![diagram.png](../assets/sub/deep/diagram.png)\n",
+        read_to_string(tmp_dir.path().join("notes/Note.md")).unwrap(),
+    );
+    assert!(tmp_dir.path().join("assets/sub/deep/diagram.png").exists());
+    assert_eq!(summary.warnings, 0);
+}
+
+#[test]
+fn test_embed_ambiguous_attachment() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let summary = Exporter::new(
+        PathBuf::from("tests/testdata/input/ambiguous-attachments/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run_with_summary()
+    .expect("exporter returned error");
+
+    // Both a/logo.png and b/c/logo.png match, the one with the shortest path is used.
+    assert_eq!(
+        "![logo.png](a/logo.png)\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap(),
+    );
+    assert_eq!(summary.warnings, 1);
+
+    // Collecting the embeds for the metadata sidecar doesn't warn about them a second time.
+    let summary = Exporter::new(
+        PathBuf::from("tests/testdata/input/ambiguous-attachments/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .metadata_sidecar(true)
+    .run_with_summary()
+    .expect("exporter returned error");
+    assert_eq!(summary.warnings, 1);
+}
+
+#[test]
+fn test_embed_ambiguity() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/embed-ambiguity/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.run().expect("exporter returned error");
+    let actual = read_to_string(tmp_dir.path().join("Note.md")).unwrap();
+    assert_eq!("Diagram note.\n\n![Diagram.png](Diagram.png)\n", actual);
+
+    exporter.embed_ambiguity(EmbedAmbiguity::PreferAttachment);
+    exporter.run().expect("exporter returned error");
+    let actual = read_to_string(tmp_dir.path().join("Note.md")).unwrap();
+    assert_eq!(
+        "![Diagram](Diagram.png)\n\n![Diagram.png](Diagram.png)\n",
+        actual
+    );
+}
+
+#[test]
+fn test_note_order_frontmatter_key_descending() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/note-order/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.concatenate_to(PathBuf::from("all.md"));
+    exporter.note_order(NoteOrder::FrontmatterKey {
+        key: "date".to_string(),
+        desc: true,
+    });
+    exporter.run().expect("exporter returned error");
+
+    let actual = read_to_string(tmp_dir.path().join("all.md")).unwrap();
+    let positions: Vec<usize> = ["Newest.", "Middle.", "Oldest.", "Undated."]
+        .iter()
+        .map(|text| actual.find(text).unwrap())
+        .collect();
+    let mut sorted = positions.clone();
+    sorted.sort_unstable();
+    assert_eq!(positions, sorted);
+}
+
+#[test]
+fn test_padded_wikilinks() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/padded-links/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run()
+    .expect("exporter returned error");
+
+    let actual = read_to_string(tmp_dir.path().join("Note.md")).unwrap();
+    assert_eq!(
+        "[Other note](Other%20note.md)\n\n[Other note](Other%20note.md)\n\n[label](Other%20note.md)\n",
+        actual
+    );
+}
+
+#[test]
+fn test_previous_export_frontmatter_only_change() {
+    let src_dir = TempDir::new().expect("failed to make tempdir");
+    let dest_dir = TempDir::new().expect("failed to make tempdir");
+    let note = src_dir.path().join("Note.md");
+    std::fs::write(&note, "---\nstatus: draft\n---\n\nSome text.\n").unwrap();
+    std::fs::write(src_dir.path().join("Other.md"), "Other text.\n").unwrap();
+
+    let mut exporter = Exporter::new(src_dir.path().to_path_buf(), dest_dir.path().to_path_buf());
+    exporter.previous_export(dest_dir.path().to_path_buf());
+    exporter.run().expect("exporter returned error");
+    assert!(exporter
+        .changes()
+        .iter()
+        .all(|change| change.kind == ChangeKind::Added));
+
+    std::fs::write(&note, "---\nstatus: published\n---\n\nSome text.\n").unwrap();
+    exporter.run().expect("exporter returned error");
+    assert_eq!(
+        exporter.changes(),
+        vec![
+            NoteChange {
+                path: PathBuf::from("Note.md"),
+                kind: ChangeKind::Frontmatter,
+            },
+            NoteChange {
+                path: PathBuf::from("Other.md"),
+                kind: ChangeKind::Unchanged,
+            },
+        ]
+    );
+    assert_eq!(
+        "---\nstatus: published\n---\n\nSome text.\n",
+        read_to_string(dest_dir.path().join("Note.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_inline_svg() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/inline-svg/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.inline_svg(true);
+    exporter.sanitize_svg(true);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "Before\n\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"10\" height=\"10\">\n  <circle cx=\"5\" cy=\"5\" r=\"4\"/>\n</svg>\n\nAfter\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+    assert!(!tmp_dir.path().join("circle.svg").exists());
+    assert!(tmp_dir.path().join("square.svg").exists());
+}
+
+#[test]
+fn test_sidecar_frontmatter() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/sidecar-frontmatter/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.sidecar_frontmatter("yaml".to_string());
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "---\ntitle: Inline title\nauthor: Jane\n---\n\nBody text.\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+    assert!(!tmp_dir.path().join("Note.md.yaml").exists());
+
+    exporter.sidecar_precedence(SidecarPrecedence::Sidecar);
+    exporter.run().expect("exporter returned error");
+    assert_eq!(
+        "---\ntitle: Sidecar title\nauthor: Jane\n---\n\nBody text.\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_link_text_from_title() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/link-titles/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.link_text_from_title(true);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "[A Nicer Title](some-file-name.md)\n\n[Alias](some-file-name.md)\n\n[untitled](untitled.md)\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_max_depth() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/max-depth/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.walk_options(WalkOptions {
+        max_depth: Some(1),
+        ..Default::default()
+    });
+    exporter.run().expect("exporter returned error");
+    assert!(tmp_dir.path().join("Top.md").exists());
+    assert!(!tmp_dir.path().join("sub").exists());
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/max-depth/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.start_at(PathBuf::from("tests/testdata/input/max-depth/sub"));
+    exporter.walk_options(WalkOptions {
+        max_depth: Some(1),
+        ..Default::default()
+    });
+    exporter.run().expect("exporter returned error");
+    assert!(tmp_dir.path().join("Sub.md").exists());
+    assert!(!tmp_dir.path().join("deeper").exists());
+}
+
+#[test]
+fn test_include_obsidian_config() {
+    let vault = TempDir::new().expect("failed to make tempdir");
+    create_dir(vault.path().join(".obsidian")).unwrap();
+    write(vault.path().join(".obsidian/app.json"), "{}").unwrap();
+    write(vault.path().join(".hidden.md"), "Hidden note").unwrap();
+    write(vault.path().join("Note.md"), "Note").unwrap();
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(vault.path().to_path_buf(), tmp_dir.path().to_path_buf());
+    exporter.walk_options(WalkOptions {
+        ignore_hidden: false,
+        ..Default::default()
+    });
+    exporter.run().expect("exporter returned error");
+    assert!(tmp_dir.path().join("Note.md").exists());
+    assert!(tmp_dir.path().join(".hidden.md").exists());
+    assert!(!tmp_dir.path().join(".obsidian").exists());
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(vault.path().to_path_buf(), tmp_dir.path().to_path_buf());
+    exporter.walk_options(WalkOptions {
+        ignore_hidden: false,
+        include_obsidian_config: true,
+        ..Default::default()
+    });
+    exporter.run().expect("exporter returned error");
+    assert_eq!(
+        read_to_string(tmp_dir.path().join(".obsidian/app.json")).unwrap(),
+        "{}"
+    );
+}
+
+#[test]
+fn test_redirect_map() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/redirect-map/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.redirect_map_output(PathBuf::from("redirects.json"));
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        concat!(
+            "{\n",
+            "  \"Another Name\": \"sub/My%20Note.md\",\n",
+            "  \"My Note\": \"sub/My%20Note.md\",\n",
+            "  \"Old Name\": \"sub/My%20Note.md\",\n",
+            "  \"Other\": \"Other.md\"\n",
+            "}\n"
+        ),
+        read_to_string(tmp_dir.path().join("redirects.json")).unwrap()
+    );
+}
+
+#[test]
+fn test_netlify_redirects() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/redirect-map/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.netlify_redirects_output(PathBuf::from("_redirects"));
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        concat!(
+            "/Another%20Name /sub/My%20Note.md 301\n",
+            "/My%20Note /sub/My%20Note.md 301\n",
+            "/Old%20Name /sub/My%20Note.md 301\n",
+            "/Other /Other.md 301\n",
+        ),
+        read_to_string(tmp_dir.path().join("_redirects")).unwrap()
+    );
+    assert!(!tmp_dir.path().join("redirects.json").exists());
+}
+
+#[test]
+fn test_comment_strategies() {
+    for (strategy, expected) in [
+        (CommentStrategy::Keep, "keep.md"),
+        (CommentStrategy::Strip, "strip.md"),
+        (CommentStrategy::ToHtmlComment, "html-comment.md"),
+    ] {
+        let tmp_dir = TempDir::new().expect("failed to make tempdir");
+        let mut exporter = Exporter::new(
+            PathBuf::from("tests/testdata/input/comments/"),
+            tmp_dir.path().to_path_buf(),
+        );
+        exporter.comments(strategy);
+        exporter.run().expect("exporter returned error");
+
+        assert_eq!(
+            read_to_string(PathBuf::from("tests/testdata/expected/comments/").join(expected))
+                .unwrap(),
+            read_to_string(tmp_dir.path().join("Note.md")).unwrap(),
+            "unexpected output with {:?}",
+            strategy
+        );
+    }
+}
+
+#[test]
+fn test_metadata_sidecar() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/metadata-sidecar/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.metadata_sidecar(true);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        concat!(
+            "{\n",
+            "  \"url\": \"Note.md\",\n",
+            "  \"tags\": [\"project\", \"inline\"],\n",
+            "  \"word_count\": 13,\n",
+            "  \"links\": [\"Other.md\", \"Other.md#section\"],\n",
+            "  \"embeds\": [\"Embedded.md\", \"image.png\"]\n",
+            "}\n"
+        ),
+        read_to_string(tmp_dir.path().join("Note.md.json")).unwrap()
+    );
+    assert!(tmp_dir.path().join("Other.md.json").exists());
+    assert!(!tmp_dir.path().join("image.png.json").exists());
+}
+
+#[test]
+fn test_search_index() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/search-index/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.search_index_output(PathBuf::from("search.json"), SearchIndexFormat::Fuse);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        concat!(
+            "[\n",
+            "  {\"url\": \"Guide.md\", \"title\": \"Getting started\", \"tags\": [\"guide\", \"config\"], ",
+            "\"content\": \"Setup Install the tool with cargo install. Configure it #config\"},\n",
+            "  {\"url\": \"sub/Plain%20Note.md\", \"title\": \"Plain Note\", \"tags\": [], ",
+            "\"content\": \"Just \\\"quoted\\\" text.\"}\n",
+            "]\n"
+        ),
+        read_to_string(tmp_dir.path().join("search.json")).unwrap()
+    );
+
+    exporter.search_index_output(PathBuf::from("search.json"), SearchIndexFormat::Lunr);
+    exporter.run().expect("exporter returned error");
+    let index = read_to_string(tmp_dir.path().join("search.json")).unwrap();
+    assert!(index.contains(
+        "{\"id\": \"Guide.md\", \"url\": \"Guide.md\", \"title\": \"Getting started\", \"tags\": \"guide config\""
+    ));
+}
+
+#[test]
+fn test_repeated_embeds_copy_attachment_once() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/repeated-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.profile(true);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "![white.png](white.png)\n\n![white.png](white.png)\n\n![white.png](white.png)\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+    let copies = exporter.profile_report().unwrap().get("copy").unwrap();
+    assert_eq!(copies.calls, 1);
+    let files: Vec<_> = WalkDir::new(tmp_dir.path())
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .collect();
+    assert_eq!(files.len(), 2);
+}
+
+#[test]
+fn test_backmatter() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/backmatter/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.backmatter(true);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "---\ntitle: Note\nauthor: Jane\n---\n\nBody text.\n\n---\n\nAfter a rule.\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_normalize_unicode() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/unicode-normalization/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.normalize_unicode(true);
+    exporter.run().expect("exporter returned error");
+
+    // `café.md` is stored decomposed (NFD) and linked to in composed form (NFC), while `naïve.md`
+    // is stored composed and linked to in decomposed form.
+    assert_eq!(
+        "[caf\u{e9}](cafe%CC%81.md) and [na\u{ef}ve](na%C3%AFve.md)\n",
+        read_to_string(tmp_dir.path().join("Links.md")).unwrap()
+    );
+    assert!(tmp_dir.path().join("cafe\u{301}.md").exists());
+
+    // The same goes for the voiced kana of `ガイド.md` (decomposed) and `パス.md` (composed), which
+    // are linked to in the other form.
+    assert_eq!(
+        concat!(
+            "[\u{30ac}\u{30a4}\u{30c9}](%E3%82%AB%E3%82%99%E3%82%A4%E3%83%88%E3%82%99.md) and ",
+            "[\u{30d1}\u{30b9}](%E3%83%91%E3%82%B9.md)\n"
+        ),
+        read_to_string(tmp_dir.path().join("Kana.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_reuse_exporter_with_new_destination() {
+    let first_dir = TempDir::new().expect("failed to make tempdir");
+    let second_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/main-samples/"),
+        first_dir.path().to_path_buf(),
+    );
+    exporter.profile(true);
+    exporter.reuse_index(true);
+    exporter.run().expect("exporter returned error");
+    let profile = exporter.profile_report().unwrap();
+    assert_eq!(profile.get("walk").unwrap().calls, 1);
+    assert_eq!(profile.get("index").unwrap().calls, 1);
+
+    exporter.set_destination(second_dir.path().to_path_buf());
+    exporter.run().expect("exporter returned error");
+    let profile = exporter.profile_report().unwrap();
+    assert_eq!(profile.get("walk"), None);
+    assert_eq!(profile.get("index"), None);
+
+    for entry in WalkDir::new("tests/testdata/expected/main-samples/")
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.metadata().unwrap().is_file() {
+            continue;
+        };
+        let filename = entry.file_name().to_string_lossy().into_owned();
+        let expected = read_to_string(entry.path()).unwrap();
+        assert_eq!(
+            expected,
+            read_to_string(first_dir.path().join(&filename)).unwrap()
+        );
+        assert_eq!(
+            expected,
+            read_to_string(second_dir.path().join(&filename)).unwrap()
+        );
+    }
+
+    // Changing a setting which affects the vault contents rebuilds the index.
+    exporter.walk_options(WalkOptions::default());
+    exporter.run().expect("exporter returned error");
+    assert_eq!(
+        exporter
+            .profile_report()
+            .unwrap()
+            .get("index")
+            .unwrap()
+            .calls,
+        1
+    );
+}
+
+#[test]
+fn test_rerun_picks_up_vault_changes() {
+    let vault = TempDir::new().expect("failed to make tempdir");
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    write(vault.path().join("Note.md"), "See [[Other]].\n").unwrap();
+    let mut exporter = Exporter::new(vault.path().to_path_buf(), tmp_dir.path().to_path_buf());
+    exporter.run().expect("exporter returned error");
+    assert_eq!(
+        "See *Other*.\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+
+    write(vault.path().join("Other.md"), "Added later.\n").unwrap();
+    exporter.run().expect("exporter returned error");
+    assert_eq!(
+        "See [Other](Other.md).\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+    assert!(tmp_dir.path().join("Other.md").exists());
+}
+
+#[test]
+fn test_hash_attachment_names() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/repeated-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.hash_attachment_names(8);
+    exporter.run().expect("exporter returned error");
+
+    assert!(!tmp_dir.path().join("white.png").exists());
+    let attachment = std::fs::read_dir(tmp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .find(|name| name.ends_with(".png"))
+        .expect("attachment should be exported");
+    let hash = attachment
+        .strip_prefix("white.")
+        .and_then(|name| name.strip_suffix(".png"))
+        .unwrap();
+    assert_eq!(hash.len(), 8);
+    assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+    let note = read_to_string(tmp_dir.path().join("Note.md")).unwrap();
+    assert!(note.starts_with(&format!("![white.png]({})\n", attachment)));
+}
+
+#[test]
+fn test_wikilinks_in_image_alt_text() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/image-alt-wikilinks/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run()
+    .expect("exporter returned error");
+
+    assert_eq!(
+        "![Other diagram](img.png)\n\n![Alias x](img.png \"title Other\")\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_run_with_summary() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/empty-notes/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.skip_empty_notes(true);
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    assert_eq!(summary.notes_exported, 1);
+    assert_eq!(summary.attachments_copied, 0);
+    let mut skipped = summary.skipped.clone();
+    skipped.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        skipped,
+        vec![
+            (
+                PathBuf::from("tests/testdata/input/empty-notes/Embeds ignored note.md"),
+                SkipReason::Empty
+            ),
+            (
+                PathBuf::from("tests/testdata/input/empty-notes/Stub.md"),
+                SkipReason::Empty
+            ),
+        ]
+    );
+    // The embed of the ignored note can't be resolved.
+    assert_eq!(summary.warnings, 1);
+
+    let summary = Exporter::new(
+        PathBuf::from("tests/testdata/input/repeated-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run_with_summary()
+    .expect("exporter returned error");
+    assert_eq!(summary.notes_exported, 1);
+    assert_eq!(summary.attachments_copied, 1);
+    assert!(summary.skipped.is_empty());
+}
+
+#[test]
+fn test_attachments_copied_once() {
+    let updates = Arc::new(Mutex::new(vec![]));
+    let recorded = updates.clone();
+    let callback = move |progress: Progress| recorded.lock().unwrap().push(progress);
+    // Both notes embed white.png, which should still only be written once.
+    let copies = Arc::new(AtomicUsize::new(0));
+    let counted = copies.clone();
+    let count_copies = move |_: &Path, _: &mut Vec<u8>| {
+        counted.fetch_add(1, Ordering::SeqCst);
+        PostprocessorResult::Continue
+    };
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/shared-attachments/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.on_progress(&callback);
+    exporter.add_attachment_processor(&count_copies);
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    assert_eq!(copies.load(Ordering::SeqCst), 1);
+    assert_eq!(summary.notes_exported, 2);
+    assert_eq!(summary.attachments_copied, 1);
+    assert!(tmp_dir.path().join("white.png").exists());
+    let mut done: Vec<usize> = updates
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|progress| progress.phase == ProgressPhase::Attachments)
+        .map(|progress| progress.done)
+        .collect();
+    done.sort_unstable();
+    assert_eq!(done, vec![0, 1]);
+
+    // The same goes for attachments exported along with several notes given to export_files.
+    copies.store(0, Ordering::SeqCst);
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    exporter.set_destination(tmp_dir.path().to_path_buf());
+    exporter.export_files(vec![PathBuf::from("First.md"), PathBuf::from("Second.md")]);
+    exporter.run().expect("exporter returned error");
+    assert_eq!(copies.load(Ordering::SeqCst), 1);
+    assert!(tmp_dir.path().join("white.png").exists());
+}
+
+#[test]
+fn test_log_file() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let log_dir = TempDir::new().expect("failed to make tempdir");
+    let log_file = log_dir.path().join("export.log");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/unresolved-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.log_file(log_file.clone(), LogLevel::Warning);
+    exporter.warnings_to_stderr(false);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "Warning: Unable to find embedded note\tReference: 'Nonexistent Note'\tSource: 'tests/testdata/input/unresolved-embeds/Note.md'\n",
+        read_to_string(&log_file).unwrap()
+    );
+
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/unresolved-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.log_file(log_file.clone(), LogLevel::Info);
+    exporter.warnings_to_stderr(false);
+    exporter.run().expect("exporter returned error");
+
+    let log = read_to_string(&log_file).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("Warning: Unable to find embedded note"));
+    assert!(lines[1].starts_with(
+        "Info: Exported note\tSource: 'tests/testdata/input/unresolved-embeds/Note.md'"
+    ));
+}
+
+#[test]
+fn test_on_warning() {
+    let warnings = Arc::new(Mutex::new(vec![]));
+    let recorded = warnings.clone();
+    let callback = move |warning: &str| recorded.lock().unwrap().push(warning.to_string());
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/unresolved-embeds/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.on_warning(&callback);
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    assert_eq!(summary.warnings, 1);
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        vec!["Warning: Unable to find embedded note\n\tReference: 'Nonexistent Note'\n\tSource: 'tests/testdata/input/unresolved-embeds/Note.md'\n"]
+    );
+}
+
+#[test]
+fn test_frontmatter_attachment_keys() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/frontmatter-attachments/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.frontmatter_attachment_keys(vec![
+        "cover".to_string(),
+        "banner".to_string(),
+        "source".to_string(),
+    ]);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "---\ncover: \"../assets/hero.jpg\"\nbanner: images/banner.png\nsource: \"https://example.com/image.png\"\n---\n\nText.\n",
+        read_to_string(tmp_dir.path().join("notes/Note.md")).unwrap()
+    );
+    assert!(tmp_dir.path().join("assets/hero.jpg").exists());
+    assert!(tmp_dir.path().join("notes/images/banner.png").exists());
+}
+
+#[test]
+fn test_rename_frontmatter_keys() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/rename-frontmatter-keys/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    let mut renames = HashMap::new();
+    renames.insert("tags".to_string(), "keywords".to_string());
+    exporter.rename_frontmatter_keys(renames);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "---\ntitle: Renamed\nkeywords:\n  - rust\n  - notes\ndate: 2024-01-01\n---\n\nBody.\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_verbatim_and_skip_dirs() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/verbatim-dirs/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.walk_options(WalkOptions {
+        verbatim_dirs: vec![PathBuf::from("templates")],
+        skip_dirs: vec![PathBuf::from("drafts")],
+        // Skipped directories are pruned before the filter is consulted, so it never sees them.
+        filter_fn: Some(&|entry| {
+            assert!(
+                !entry.path().ends_with("drafts"),
+                "walked into skipped directory"
+            );
+            true
+        }),
+        ..Default::default()
+    });
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/input/verbatim-dirs/templates/Template.md").unwrap(),
+        read_to_string(tmp_dir.path().join("templates/Template.md")).unwrap()
+    );
+    assert_eq!(
+        "See [Template](templates/Template.md).\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+    assert!(!tmp_dir.path().join("drafts").exists());
+}
+
+#[test]
+fn test_sanitize_filename() {
+    // Built at runtime, as these names can't be checked out on every platform.
+    let vault = TempDir::new().expect("failed to make tempdir");
+    write(
+        vault.path().join("Q: What? | Answer.md"),
+        "The answer is in [[See: also]].\n",
+    )
+    .unwrap();
+    write(
+        vault.path().join("See: also.md"),
+        "Linked from [[Index]].\n",
+    )
+    .unwrap();
+    write(
+        vault.path().join("Index.md"),
+        "Start with [[See: also#Heading]].\n",
+    )
+    .unwrap();
+    write(vault.path().join("a:b.md"), "Same\n").unwrap();
+    write(vault.path().join("a-b.md"), "Same\n").unwrap();
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(vault.path().to_path_buf(), tmp_dir.path().to_path_buf());
+    exporter.sanitize_filename(":?|", Some('-'));
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    assert_eq!(
+        "The answer is in [See: also](See-%20also.md).\n",
+        read_to_string(tmp_dir.path().join("Q- What- - Answer.md")).unwrap()
+    );
+    assert_eq!(
+        "Start with [See: also > Heading](See-%20also.md#heading).\n",
+        read_to_string(tmp_dir.path().join("Index.md")).unwrap()
+    );
+    assert!(tmp_dir.path().join("See- also.md").exists());
+    assert!(!tmp_dir.path().join("See: also.md").exists());
+    assert_eq!(1, summary.warnings);
+}
+
+#[test]
+fn test_inject_provenance() {
+    let vault = TempDir::new().expect("failed to make tempdir");
+    write(vault.path().join("Note.md"), "Some text.\n").unwrap();
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(vault.path())
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .expect("failed to run git");
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8(output.stdout).unwrap()
+    };
+    git(&["init", "--quiet"]);
+    git(&["add", "Note.md"]);
+    git(&["commit", "--quiet", "--message", "Add note"]);
+    let head = git(&["rev-parse", "HEAD"]);
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(vault.path().to_path_buf(), tmp_dir.path().to_path_buf());
+    exporter.inject_provenance(Some("commit".to_string()), Some("exported_at".to_string()));
+    exporter.run().expect("exporter returned error");
+
+    let output = read_to_string(tmp_dir.path().join("Note.md")).unwrap();
+    let frontmatter: Mapping =
+        serde_yaml::from_str(output.split("---").nth(1).expect("missing frontmatter")).unwrap();
+    assert_eq!(
+        Some(&Value::String(head.trim().to_string())),
+        frontmatter.get(&Value::String("commit".to_string()))
+    );
+    match frontmatter.get(&Value::String("exported_at".to_string())) {
+        Some(Value::String(timestamp)) => assert!(timestamp.ends_with('Z'), "{}", timestamp),
+        value => panic!("unexpected exported_at: {:?}", value),
+    }
+}
+
+#[test]
+fn test_inject_provenance_outside_git() {
+    let vault = TempDir::new().expect("failed to make tempdir");
+    write(vault.path().join("Note.md"), "Some text.\n").unwrap();
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(vault.path().to_path_buf(), tmp_dir.path().to_path_buf());
+    exporter.inject_provenance(Some("commit".to_string()), None);
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    assert_eq!(
+        "Some text.\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+    assert_eq!(1, summary.warnings);
+}
+
+#[test]
+fn test_max_attachment_size() {
+    let vault = TempDir::new().expect("failed to make tempdir");
+    write(vault.path().join("small.png"), vec![0; 100]).unwrap();
+    write(vault.path().join("large.png"), vec![0; 2000]).unwrap();
+    write(
+        vault.path().join("Note.md"),
+        "![[small.png]]\n\n![[large.png]]\n",
+    )
+    .unwrap();
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(vault.path().to_path_buf(), tmp_dir.path().to_path_buf());
+    exporter.max_attachment_size(Some(1000));
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    assert!(tmp_dir.path().join("small.png").exists());
+    assert!(!tmp_dir.path().join("large.png").exists());
+    assert_eq!(
+        vec![(vault.path().join("large.png"), SkipReason::TooLarge)],
+        summary.skipped
+    );
+    assert_eq!(1, summary.warnings);
+}
+
+#[test]
+fn test_restore_wikilinks() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/restore-wikilinks/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.run().expect("exporter returned error");
+    let link_map = exporter.link_map();
+
+    for note in &["Source.md", "sub/Deep.md"] {
+        let exported = read_to_string(tmp_dir.path().join(note)).unwrap();
+        assert_eq!(
+            read_to_string(PathBuf::from("tests/testdata/input/restore-wikilinks/").join(note))
+                .unwrap(),
+            restore_wikilinks(&exported, &PathBuf::from(note), &link_map),
+        );
+    }
+}
+
+#[test]
+fn test_skip_attachments() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/notes-only/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.skip_attachments(true);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "A note with an image:\n\n![image.png](attachments/image.png)\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap()
+    );
+    let non_markdown_files: Vec<_> = WalkDir::new(tmp_dir.path())
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| entry.path().extension() != Some("md".as_ref()))
+        .collect();
+    assert!(non_markdown_files.is_empty(), "{:?}", non_markdown_files);
+}
+
+#[test]
+fn test_frontmatter_strategy_override() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/frontmatter-override/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.frontmatter_strategy(FrontmatterStrategy::Auto);
+    exporter.run().expect("exporter returned error");
+
+    // Without the override key, Always.md has empty frontmatter which Auto would leave out.
+    assert_eq!(
+        "---\n---\n\nAlways.\n",
+        read_to_string(tmp_dir.path().join("Always.md")).unwrap()
+    );
+    assert_eq!(
+        "Never.\n",
+        read_to_string(tmp_dir.path().join("Never.md")).unwrap()
+    );
+    assert_eq!(
+        "---\ntitle: Auto\n---\n\nAuto.\n",
+        read_to_string(tmp_dir.path().join("Auto.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_inject_backlinks() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/backlinks/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.inject_backlinks(BacklinkStrategy::Footer);
+    // Draft.md links to Target.md as well, but isn't listed as it's not exported.
+    exporter.directive_comment("export".to_string());
+    exporter.run().expect("exporter returned error");
+
+    assert!(!tmp_dir.path().join("Draft.md").exists());
+    assert_eq!(
+        concat!(
+            "Target note.\n",
+            "\n",
+            "## Backlinks\n",
+            "\n",
+            "* [First](First.md)\n",
+            "* [Second](sub/Second.md)\n",
+        ),
+        read_to_string(tmp_dir.path().join("Target.md")).unwrap()
+    );
+    assert!(!read_to_string(tmp_dir.path().join("First.md"))
+        .unwrap()
+        .contains("Backlinks"));
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    exporter.set_destination(tmp_dir.path().to_path_buf());
+    exporter.inject_backlinks(BacklinkStrategy::FrontmatterKey("backlinks".to_string()));
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "---\nbacklinks:\n  - First.md\n  - sub/Second.md\n---\n\nTarget note.\n",
+        read_to_string(tmp_dir.path().join("Target.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_block_ids() {
+    for (style, expected) in [
+        (BlockIdStyle::Keep, "Keep.md"),
+        (BlockIdStyle::Strip, "Strip.md"),
+        (BlockIdStyle::Anchor, "Anchor.md"),
+    ] {
+        let tmp_dir = TempDir::new().expect("failed to make tempdir");
+        let mut exporter = Exporter::new(
+            PathBuf::from("tests/testdata/input/block-ids/"),
+            tmp_dir.path().to_path_buf(),
+        );
+        exporter.block_ids(style);
+        exporter.run().expect("exporter returned error");
+
+        assert_eq!(
+            read_to_string(PathBuf::from("tests/testdata/expected/block-ids/").join(expected))
+                .unwrap(),
+            read_to_string(tmp_dir.path().join("Note.md")).unwrap(),
+            "{:?}",
+            style
+        );
+    }
+}
+
+#[test]
+fn test_unresolved_embed_style() {
+    let cases = vec![
+        // The paragraph the embed was in is left empty.
+        (UnresolvedEmbedStyle::Remove, "Before\n\n\n\nAfter\n"),
+        (
+            UnresolvedEmbedStyle::Leave,
+            "Before\n\n![[Nonexistent Note]]\n\nAfter\n",
+        ),
+        (
+            UnresolvedEmbedStyle::Placeholder("Missing: {target}".to_string()),
+            "Before\n\nMissing: Nonexistent Note\n\nAfter\n",
+        ),
+        (
+            UnresolvedEmbedStyle::Link,
+            "Before\n\n[Nonexistent Note](Nonexistent%20Note.md)\n\nAfter\n",
+        ),
+    ];
+    for (style, expected) in cases {
+        let tmp_dir = TempDir::new().expect("failed to make tempdir");
+        let mut exporter = Exporter::new(
+            PathBuf::from("tests/testdata/input/unresolved-embeds/"),
+            tmp_dir.path().to_path_buf(),
+        );
+        exporter.unresolved_embed_style(style.clone());
+        let summary = exporter
+            .run_with_summary()
+            .expect("exporter returned error");
+
+        assert_eq!(
+            expected,
+            read_to_string(tmp_dir.path().join("Note.md")).unwrap(),
+            "{:?}",
+            style
+        );
+        assert_eq!(1, summary.warnings);
+    }
+}
+
+#[test]
+fn test_progress_callback() {
+    let updates = Arc::new(Mutex::new(vec![]));
+    let recorded = updates.clone();
+    let callback = move |progress: Progress| recorded.lock().unwrap().push(progress);
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/restore-wikilinks/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.on_progress(&callback);
+    exporter.run().expect("exporter returned error");
+
+    let updates = updates.lock().unwrap();
+    for (phase, total) in [(ProgressPhase::Notes, 3), (ProgressPhase::Attachments, 1)] {
+        let updates: Vec<&Progress> = updates
+            .iter()
+            .filter(|progress| progress.phase == phase)
+            .collect();
+        assert!(updates.iter().all(|progress| progress.total == total));
+        // One update before any files are processed, and one after each file. Updates from
+        // different threads may arrive out of order.
+        let mut done: Vec<usize> = updates.iter().map(|progress| progress.done).collect();
+        done.sort_unstable();
+        assert_eq!((0..=total).collect::<Vec<_>>(), done);
+    }
+}
+
+#[test]
+fn test_export_files() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/export-files/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.export_files(vec![
+        PathBuf::from("Changed.md"),
+        PathBuf::from("tests/testdata/input/export-files/Also changed.md"),
+    ]);
+    exporter.run().expect("exporter returned error");
+
+    let mut exported: Vec<PathBuf> = WalkDir::new(tmp_dir.path())
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| {
+            entry
+                .path()
+                .strip_prefix(tmp_dir.path())
+                .unwrap()
+                .to_path_buf()
+        })
+        .collect();
+    exported.sort();
+    assert_eq!(
+        exported,
+        vec![
+            PathBuf::from("Also changed.md"),
+            PathBuf::from("Changed.md"),
+            PathBuf::from("images/white.png"),
+        ]
+    );
+    assert_eq!(
+        "Embeds an image:\n\n![white.png](images/white.png)\n\nLinks to [Unlisted](Unlisted.md).\n",
+        read_to_string(tmp_dir.path().join("Changed.md")).unwrap()
+    );
+    assert_eq!(
+        "Embeds a note:\n\nNot listed, only embedded.\n",
+        read_to_string(tmp_dir.path().join("Also changed.md")).unwrap()
+    );
+}
+
+#[test]
+fn test_github_annotations() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let output = Command::new(env!("CARGO_BIN_EXE_obsidian-export"))
+        .arg("tests/testdata/input/link-events/")
+        .arg(tmp_dir.path())
+        .arg("--github-annotations")
+        .output()
+        .expect("failed to run obsidian-export");
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr.trim_end(),
+        "::warning file=tests/testdata/input/link-events/Source.md,line=1::Unable to find referenced note (Reference: 'Missing note')"
+    );
+}
+
+#[test]
+fn test_link_callback() {
+    let events = Arc::new(Mutex::new(vec![]));
+    let recorded = events.clone();
+    let callback = move |event: &LinkEvent| recorded.lock().unwrap().push(event.clone());
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/link-events/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.on_link(&callback);
+    exporter.run().expect("exporter returned error");
+
+    let source = PathBuf::from("tests/testdata/input/link-events/Source.md");
+    let target = PathBuf::from("tests/testdata/input/link-events/Target.md");
+    let event = |kind, file: &str, section: Option<&str>, resolved: Option<&PathBuf>| LinkEvent {
+        kind,
+        source: source.clone(),
+        target: Some(file.to_string()),
+        section: section.map(str::to_string),
+        resolved: resolved.cloned(),
+    };
+    let mut events = events.lock().unwrap().clone();
+    events.sort_by_key(|event| {
+        (
+            event.target.clone(),
+            event.section.clone(),
+            event.kind == LinkKind::Embed,
+        )
+    });
+    assert_eq!(
+        events,
+        vec![
+            event(LinkKind::Link, "Missing note", None, None),
+            event(LinkKind::Link, "Target", None, Some(&target)),
+            event(LinkKind::Embed, "Target", None, Some(&target)),
+            event(LinkKind::Link, "Target", Some("Section"), Some(&target)),
+        ]
+    );
+}
+
+#[test]
+fn test_link_resolution_relative_first() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/link-resolution/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.link_resolution(LinkResolution::RelativeFirst);
+    exporter.run().expect("exporter returned error");
+
+    // Both directories contain a Note.md, but links resolve to the one next to the linking note.
+    assert_eq!(
+        "Link to [Note](Note.md).\n\nNote in a.\n",
+        read_to_string(tmp_dir.path().join("a/Index.md")).unwrap(),
+    );
+    assert_eq!(
+        "Link to [Note](Note.md).\n\nNote in b.\n",
+        read_to_string(tmp_dir.path().join("b/Index.md")).unwrap(),
+    );
+}
+
+#[test]
+fn test_publish_marker() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/publish-marker/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.publish_marker(".publish".to_string());
+    exporter.run().expect("exporter returned error");
+
+    let mut exported: Vec<PathBuf> = WalkDir::new(tmp_dir.path())
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| {
+            entry
+                .path()
+                .strip_prefix(tmp_dir.path())
+                .unwrap()
+                .to_path_buf()
+        })
+        .collect();
+    exported.sort();
+    assert_eq!(
+        vec![
+            PathBuf::from("public/Note.md"),
+            PathBuf::from("public/nested/Nested.md"),
+        ],
+        exported,
+    );
+}
+
+#[test]
+fn test_frontmatter_schema() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/frontmatter-schema/vault/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.frontmatter_schema(
+        PathBuf::from("tests/testdata/input/frontmatter-schema/schema.json"),
+        false,
+    );
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+    // The note without a title is reported, but still exported.
+    assert_eq!(summary.warnings, 1);
+    assert_eq!(summary.notes_exported, 2);
+
+    exporter.frontmatter_schema(
+        PathBuf::from("tests/testdata/input/frontmatter-schema/schema.json"),
+        true,
+    );
+    // Notes which fail a strict schema aren't recorded anywhere, not even partially.
+    exporter.metadata_sidecar(true);
+    match exporter.run().unwrap_err() {
+        ExportError::FileExportError { path: _, source } => match *source {
+            ExportError::FrontmatterSchemaViolation { path, violations } => {
+                assert!(!tmp_dir.path().join("Untitled.md.json").exists());
+                assert_eq!(
+                    PathBuf::from("tests/testdata/input/frontmatter-schema/vault/Untitled.md"),
+                    path
+                );
+                assert_eq!(vec!["\"title\" is a required property"], violations);
+            }
+            _ => panic!("Wrong error variant for source, got: {:?}", source),
+        },
+        err => panic!("Wrong error variant: {:?}", err),
+    }
+
+    exporter.frontmatter_schema(
+        PathBuf::from("tests/testdata/input/frontmatter-schema/invalid-schema.json"),
+        false,
+    );
+    match exporter.run().unwrap_err() {
+        ExportError::InvalidSchema { path, message: _ } => {
+            assert_eq!(
+                PathBuf::from("tests/testdata/input/frontmatter-schema/invalid-schema.json"),
+                path
+            );
+        }
+        err => panic!("Wrong error variant: {:?}", err),
+    }
+}
+
+#[test]
+fn test_link_style_absolute_from_root() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/restore-wikilinks/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.link_style(LinkStyle::AbsoluteFromRoot);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "Deep note linking back to [Source](/Source.md).\n",
+        read_to_string(tmp_dir.path().join("sub/Deep.md")).unwrap(),
+    );
+
+    exporter.link_base("/docs/".to_string());
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "Deep note linking back to [Source](/docs/Source.md).\n",
+        read_to_string(tmp_dir.path().join("sub/Deep.md")).unwrap(),
+    );
+    let source = read_to_string(tmp_dir.path().join("Source.md")).unwrap();
+    assert!(source.contains("[Deep](/docs/sub/Deep.md)"));
+    assert!(source.contains("![image.png](/docs/image.png)"));
+}
+
+#[test]
+fn test_embed_skip_key() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/embed-skip/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.embed_skip_key("no_embed".to_string());
+    exporter.run().expect("exporter returned error");
+
+    let parent = read_to_string(tmp_dir.path().join("Parent.md")).unwrap();
+    assert!(!parent.contains("Reference content."));
+    assert!(parent.contains("Inlined content."));
+    // The flagged note is still exported on its own.
+    assert_eq!(
+        "---\nno_embed: true\n---\n\nReference content.\n",
+        read_to_string(tmp_dir.path().join("Reference.md")).unwrap(),
+    );
+
+    exporter.embed_skip_comment(true);
+    exporter.run().expect("exporter returned error");
+    let parent = read_to_string(tmp_dir.path().join("Parent.md")).unwrap();
+    assert!(parent.contains("<!-- Embed of 'Reference' omitted -->"));
+    assert!(!parent.contains("Reference content."));
+}
+
+#[test]
+fn test_thematic_breaks_after_frontmatter() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    Exporter::new(
+        PathBuf::from("tests/testdata/input/yaml-documents/"),
+        tmp_dir.path().to_path_buf(),
+    )
+    .run()
+    .expect("exporter returned error");
+
+    // Only the first block is frontmatter, later `---` lines remain thematic breaks, including
+    // those around a second YAML document directly following the frontmatter.
+    for note in ["Note.md", "Stream.md"] {
+        let expected =
+            read_to_string(PathBuf::from("tests/testdata/expected/yaml-documents/").join(note))
+                .unwrap();
+        let actual = read_to_string(tmp_dir.path().join(note)).unwrap();
+        assert_eq!(expected, actual);
+    }
+}
+
+#[test]
+fn test_generate_directory_indexes() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/directory-indexes/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.skip_empty_notes(true);
+    exporter.generate_directory_indexes(IndexConfig {
+        filename: "index.md".to_string(),
+        title_from: IndexTitle::FrontmatterKey("title".to_string()),
+    });
+    exporter.run().expect("exporter returned error");
+
+    // The skipped empty note isn't listed.
+    assert_eq!(
+        "# docs\n\n* [nested](nested/index.md)\n* [A Guide](Guide.md)\n* [Introduction](Introduction.md)\n",
+        read_to_string(tmp_dir.path().join("docs/index.md")).unwrap(),
+    );
+    assert_eq!(
+        "# nested\n\n* [Deep](Deep.md)\n",
+        read_to_string(tmp_dir.path().join("docs/nested/index.md")).unwrap(),
+    );
+    let root_index = read_to_string(tmp_dir.path().join("index.md")).unwrap();
+    assert!(root_index
+        .contains("* [docs](docs/index.md)\n* [existing](existing/index.md)\n* [Top](Top.md)\n"));
+    // An index note from the vault takes precedence over a generated one.
+    assert_eq!(
+        "Hand-written index.\n",
+        read_to_string(tmp_dir.path().join("existing/index.md")).unwrap(),
+    );
+}
+
+#[test]
+fn test_directive_comment() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/directive-comments/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.directive_comment("export".to_string());
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    assert!(!tmp_dir.path().join("Draft.md").exists());
+    assert_eq!(
+        vec![(
+            PathBuf::from("tests/testdata/input/directive-comments/Draft.md"),
+            SkipReason::Directive
+        )],
+        summary.skipped
+    );
+    // Directives are removed from the body, and may set frontmatter.
+    assert_eq!(
+        "---\ntitle: Published note\n---\n\nPublished content.\n",
+        read_to_string(tmp_dir.path().join("Published.md")).unwrap(),
+    );
+}
+
+#[test]
+fn test_passthrough_unmodified() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/passthrough/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.passthrough_unmodified(true);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        read("tests/testdata/input/passthrough/Lists.md").unwrap(),
+        read(tmp_dir.path().join("Lists.md")).unwrap(),
+    );
+    // Notes which contain links are still rewritten.
+    assert_eq!(
+        "* A link to [Lists](Lists.md)\n",
+        read_to_string(tmp_dir.path().join("Linked.md")).unwrap(),
+    );
+
+    exporter.passthrough_unmodified(false);
+    exporter.run().expect("exporter returned error");
+    assert_ne!(
+        read("tests/testdata/input/passthrough/Lists.md").unwrap(),
+        read(tmp_dir.path().join("Lists.md")).unwrap(),
+    );
+}
+
+#[test]
+fn test_keep_frontmatter_comments() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/frontmatter-comments/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.keep_frontmatter_comments(true);
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/input/frontmatter-comments/Note.md").unwrap(),
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap(),
+    );
+
+    // Once the frontmatter is modified, it's written out again without its comments.
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/frontmatter-comments/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.keep_frontmatter_comments(true);
+    exporter.add_postprocessor(&|mut context, events| {
+        context
+            .frontmatter
+            .insert(Value::String("draft".to_string()), Value::Bool(false));
+        (context, events, PostprocessorResult::Continue)
+    });
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        "---\ntitle: Commented\ntags:\n  - a\n  - b\ndraft: false\n---\n\nBody.\n",
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap(),
+    );
+}
+
+#[test]
+fn test_colocate_attachments() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/colocate-attachments/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.colocate_attachments(
+        "{note}/attachments".to_string(),
+        SharedAttachments::Centralize,
+    );
+    exporter.run().expect("exporter returned error");
+
+    let note = read_to_string(tmp_dir.path().join("notes/Trip.md")).unwrap();
+    assert!(note.contains("![photo.png](Trip/attachments/photo.png)"));
+    assert!(note.contains("![map.png](../assets/map.png)"));
+    assert!(tmp_dir
+        .path()
+        .join("notes/Trip/attachments/photo.png")
+        .exists());
+    assert!(!tmp_dir.path().join("assets/photo.png").exists());
+    assert!(tmp_dir.path().join("assets/map.png").exists());
+    assert!(tmp_dir.path().join("assets/unused.png").exists());
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/colocate-attachments/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.colocate_attachments(
+        "{note}/attachments".to_string(),
+        SharedAttachments::Duplicate,
+    );
+    exporter.run().expect("exporter returned error");
+
+    let note = read_to_string(tmp_dir.path().join("notes/Trip.md")).unwrap();
+    assert!(note.contains("![map.png](Trip/attachments/map.png)"));
+    let other = read_to_string(tmp_dir.path().join("Other.md")).unwrap();
+    assert!(other.contains("![map.png](Other/attachments/map.png)"));
+    assert!(tmp_dir
+        .path()
+        .join("notes/Trip/attachments/map.png")
+        .exists());
+    assert!(tmp_dir.path().join("Other/attachments/map.png").exists());
+    assert!(!tmp_dir.path().join("assets/map.png").exists());
+}
+
+#[test]
+fn test_colocate_colliding_attachments() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/colocate-collisions/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.colocate_attachments(
+        "{note}/attachments".to_string(),
+        SharedAttachments::Duplicate,
+    );
+    let summary = exporter
+        .run_with_summary()
+        .expect("exporter returned error");
+
+    // Both attachments would end up at Note/attachments/img.png, so they're exported to their
+    // usual location instead, with a warning.
+    assert_eq!(summary.warnings, 1);
+    let note = read_to_string(tmp_dir.path().join("Note.md")).unwrap();
+    assert!(note.contains("![a/img.png](a/img.png)"));
+    assert!(note.contains("![b/img.png](b/img.png)"));
+    assert_eq!(
+        "a\n",
+        read_to_string(tmp_dir.path().join("a/img.png")).unwrap()
+    );
+    assert_eq!(
+        "b\n",
+        read_to_string(tmp_dir.path().join("b/img.png")).unwrap()
+    );
+    assert!(!tmp_dir.path().join("Note/attachments/img.png").exists());
+}
+
+// Collects everything written to it into `files`, under `path`.
+struct MemoryWriter {
+    path: PathBuf,
+    files: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.files
+            .lock()
+            .unwrap()
+            .entry(self.path.clone())
+            .or_default()
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_writer_factory() {
+    let files = Arc::new(Mutex::new(HashMap::new()));
+    let written = files.clone();
+    let factory = move |path: &Path| -> Box<dyn Write> {
+        Box::new(MemoryWriter {
+            path: path.to_path_buf(),
+            files: written.clone(),
+        })
+    };
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let destination = tmp_dir.path().join("export");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/shared-attachments/"),
+        destination.clone(),
+    );
+    exporter.set_writer_factory(&factory);
+    exporter.run().expect("exporter returned error");
+
+    let files = files.lock().unwrap();
+    let mut keys: Vec<&PathBuf> = files.keys().collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![
+            &PathBuf::from("First.md"),
+            &PathBuf::from("Second.md"),
+            &PathBuf::from("white.png"),
+        ]
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&files[&PathBuf::from("First.md")]),
+        "![white.png](white.png)\n"
+    );
+    assert_eq!(
+        files[&PathBuf::from("white.png")],
+        read("tests/testdata/input/shared-attachments/white.png").unwrap()
+    );
+    assert!(!destination.exists());
+}
+
+#[test]
+fn test_responsive_images() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/responsive-images/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.responsive_images(HashMap::from([("@2x".to_string(), "2x".to_string())]));
+    exporter.run().expect("exporter returned error");
+
+    assert_eq!(
+        read_to_string("tests/testdata/expected/responsive-images/Note.md").unwrap(),
+        read_to_string(tmp_dir.path().join("Note.md")).unwrap(),
+    );
+    assert!(tmp_dir.path().join("photo@2x.jpg").exists());
+}
+
+#[test]
+fn test_check() {
+    let output = Command::new(env!("CARGO_BIN_EXE_obsidian-export"))
+        .arg("--check")
+        .arg("tests/testdata/input/check/")
+        .output()
+        .expect("failed to run obsidian-export");
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        vec![
+            "tests/testdata/input/check/Note.md: broken link to 'Missing note'",
+            "tests/testdata/input/check/a/Duplicate.md: filename shared with 'tests/testdata/input/check/b/Duplicate.md'",
+            "tests/testdata/input/check/b/Duplicate.md: filename shared with 'tests/testdata/input/check/a/Duplicate.md'",
+        ]
+    );
+}
+
+#[test]
+fn test_frontmatter_as_table() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/frontmatter-table/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.frontmatter_strategy(FrontmatterStrategy::Never);
+    exporter.frontmatter_as_table(true);
+    exporter.run().expect("exporter returned error");
+
+    let note = read_to_string(tmp_dir.path().join("Note.md")).unwrap();
+    assert_eq!(
+        read_to_string("tests/testdata/expected/frontmatter-table/Note.md").unwrap(),
+        note
+    );
+    assert!(!note.lines().any(|line| line == "---"));
+}
@@ -1,10 +1,24 @@
-use obsidian_export::postprocessors::softbreaks_to_hardbreaks;
-use obsidian_export::{Context, Exporter, MarkdownEvents, PostprocessorResult};
+// Lints added to clippy and rustc after large parts of these tests were written.
+#![allow(noop_method_call)]
+
+use obsidian_export::postprocessors::{
+    callouts_to_github_alerts, convert_callouts, escape_mdx, github_alerts_to_callouts,
+    handle_query_blocks, highlights_to_html, links_to_references, normalize_code_fences,
+    normalize_taxonomies, reading_stats, softbreaks_to_hardbreaks, strip_html_comments,
+    tag_hierarchies, CalloutStyle, InfoStringRule,
+};
+use obsidian_export::{
+    Context, ExportError, Exporter, MarkdownEvents, OutputFormat, PostprocessorResult, SkipReason,
+};
 use pretty_assertions::assert_eq;
 use pulldown_cmark::{CowStr, Event};
 use serde_yaml::Value;
-use std::fs::{read_to_string, remove_file};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::fs::{read, read_to_string, remove_file};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tempfile::TempDir;
 
 /// This postprocessor replaces any instance of "foo" with "bar" in the note body.
@@ -231,3 +245,567 @@ fn test_softbreaks_to_hardbreaks() {
     .unwrap();
     assert_eq!(expected, actual);
 }
+
+#[test]
+fn test_reading_stats_excluding_embeds() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let count_all = reading_stats(false);
+    let count_own = reading_stats(true);
+    let note_path = tmp_dir.path().join(PathBuf::from("Note.md"));
+
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/reading-stats"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&count_all);
+    exporter.run().unwrap();
+    assert!(read_to_string(&note_path)
+        .unwrap()
+        .starts_with("---\nword_count: 513\nreading_time: 3\n---\n"));
+
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/reading-stats"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&count_own);
+    exporter.run().unwrap();
+    assert!(read_to_string(&note_path)
+        .unwrap()
+        .starts_with("---\nword_count: 7\nreading_time: 1\n---\n"));
+}
+
+fn info_string_to_attribute(language: &str, rest: &str) -> String {
+    match rest {
+        "" => language.to_string(),
+        rest => format!(
+            "{} title=\"{}\"",
+            language,
+            rest.trim_matches(|c| c == '{' || c == '}')
+        ),
+    }
+}
+
+#[test]
+fn test_normalize_code_fences() {
+    let cases: Vec<(InfoStringRule, bool, &str)> = vec![
+        (InfoStringRule::Keep, false, "Note_keep.md"),
+        (InfoStringRule::Strip, true, "Note_strip.md"),
+        (
+            InfoStringRule::Transform(info_string_to_attribute),
+            true,
+            "Note_transform.md",
+        ),
+    ];
+    for (rule, admonitions_to_callouts, expected_file) in cases {
+        let tmp_dir = TempDir::new().expect("failed to make tempdir");
+        let postprocessor = normalize_code_fences(rule, admonitions_to_callouts);
+        let mut exporter = Exporter::new(
+            PathBuf::from("tests/testdata/input/code-fences"),
+            tmp_dir.path().to_path_buf(),
+        );
+        exporter.add_postprocessor(&postprocessor);
+        exporter.run().unwrap();
+
+        let expected = read_to_string(
+            PathBuf::from("tests/testdata/expected/code-fences").join(expected_file),
+        )
+        .unwrap();
+        let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
+        assert_eq!(expected, actual, "unexpected output for {}", expected_file);
+    }
+}
+
+#[test]
+fn test_profile_postprocessor_timings() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/postprocessors"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.profile(true);
+    exporter.add_postprocessor(&foo_to_bar);
+    exporter.add_postprocessor(&|context, events| {
+        std::thread::sleep(Duration::from_millis(20));
+        (context, events, PostprocessorResult::Continue)
+    });
+    exporter.run().unwrap();
+
+    let profile = exporter
+        .profile_report()
+        .expect("profiling should be enabled");
+    let slow = profile.get("postprocessor #2").unwrap();
+    assert!(slow.total >= Duration::from_millis(20));
+    assert!(slow.calls > 0);
+    assert!(profile.get("walk").is_some());
+    assert!(profile.get("write").is_some());
+    assert!(profile.to_folded().contains("postprocessor #2 "));
+}
+
+#[test]
+fn test_boxed_postprocessor_with_owned_state() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/postprocessors"),
+        tmp_dir.path().to_path_buf(),
+    );
+    let notes = Arc::new(AtomicUsize::new(0));
+    let counter = notes.clone();
+    exporter.add_postprocessor_boxed(Box::new(move |context, events| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        (context, events, PostprocessorResult::Continue)
+    }));
+    exporter.run().unwrap();
+
+    assert_eq!(notes.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_boxed_postprocessor_state_is_kept_across_runs() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/postprocessors"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.threads(4);
+    // The closure mutates the state it owns without any synchronization of its own, and only
+    // publishes it through `seen` so it can be read after the export.
+    let seen = Arc::new(Mutex::new(vec![]));
+    let published = seen.clone();
+    let mut notes: Vec<PathBuf> = vec![];
+    exporter.add_postprocessor_boxed(Box::new(move |context, events| {
+        notes.push(context.current_file().to_path_buf());
+        *published.lock().unwrap() = notes.clone();
+        (context, events, PostprocessorResult::Continue)
+    }));
+    exporter.run().unwrap();
+    exporter.run().unwrap();
+
+    let mut seen = seen.lock().unwrap().clone();
+    seen.sort();
+    assert_eq!(seen.len(), 6);
+    seen.dedup();
+    assert_eq!(seen.len(), 3);
+}
+
+fn prepend_marker(_path: &Path, contents: &mut Vec<u8>) -> PostprocessorResult {
+    contents.insert(0, b'!');
+    PostprocessorResult::Continue
+}
+
+#[test]
+fn test_attachment_processors() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/main-samples"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_attachment_processor(&prepend_marker);
+    exporter.add_attachment_processor(&prepend_marker);
+    exporter.add_attachment_processor(&|path, _contents| match path
+        .extension()
+        .is_some_and(|ext| ext == "pdf")
+    {
+        true => PostprocessorResult::StopAndSkipNote,
+        false => PostprocessorResult::Continue,
+    });
+    exporter.run().unwrap();
+
+    let original = read("tests/testdata/input/main-samples/white.png").unwrap();
+    let actual = read(tmp_dir.path().join("white.png")).unwrap();
+    assert_eq!([b"!!".as_slice(), &original].concat(), actual);
+    assert!(!tmp_dir.path().join("note.pdf").exists());
+    // Notes are not passed to attachment processors.
+    let note = read_to_string(tmp_dir.path().join("foo.md")).unwrap();
+    assert!(!note.starts_with('!'));
+}
+
+#[test]
+fn test_links_to_references() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/links-to-references"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&links_to_references);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/links-to-references/Note.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_postprocessor_change_destination_extension() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/postprocessors"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&|mut context, events| {
+        let is_root_note = Value::String("is_root_note".to_string());
+        if context.frontmatter.get(&is_root_note) == Some(&Value::Bool(true)) {
+            assert_eq!(context.destination_stem(), "Note");
+            context.set_destination_extension("mdx");
+        }
+        (context, events, PostprocessorResult::Continue)
+    });
+    exporter.run().unwrap();
+
+    assert!(tmp_dir.path().join("Note.mdx").exists());
+    assert!(!tmp_dir.path().join("Note.md").exists());
+    assert!(tmp_dir.path().join("_embed.md").exists());
+}
+
+#[test]
+fn test_strip_html_comments() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/html-comments"),
+        tmp_dir.path().to_path_buf(),
+    );
+    let strip_comments = strip_html_comments(true);
+    exporter.add_postprocessor(&strip_comments);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/html-comments/Note.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
+
+fn panic_on_note(
+    context: Context,
+    events: MarkdownEvents,
+) -> (Context, MarkdownEvents, PostprocessorResult) {
+    if context.current_file().ends_with("Note.md") {
+        panic!("refusing to process Note.md");
+    }
+    (context, events, PostprocessorResult::Continue)
+}
+
+#[test]
+fn test_catch_postprocessor_panics() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/postprocessors"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&panic_on_note);
+    exporter.catch_postprocessor_panics(true);
+    let summary = exporter.run_with_summary().unwrap();
+
+    assert!(!tmp_dir.path().join("Note.md").exists());
+    assert!(tmp_dir.path().join("_embed.md").exists());
+    assert!(tmp_dir.path().join("hard_linebreaks.md").exists());
+    assert_eq!(summary.notes_exported, 2);
+    assert_eq!(
+        summary.skipped,
+        vec![(
+            PathBuf::from("tests/testdata/input/postprocessors/Note.md"),
+            SkipReason::PostprocessorPanicked
+        )]
+    );
+    assert_eq!(summary.warnings, 1);
+}
+
+#[test]
+fn test_catch_embed_and_attachment_processor_panics() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/postprocessors"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_embed_postprocessor(&|_, _| panic!("refusing to process embeds"));
+    exporter.catch_postprocessor_panics(true);
+    let summary = exporter.run_with_summary().unwrap();
+
+    let note = read_to_string(tmp_dir.path().join("Note.md")).unwrap();
+    assert!(note.contains("Sentence containing foo."));
+    assert!(!note.contains("This note is embedded"));
+    assert_eq!(summary.notes_exported, 3);
+    assert_eq!(summary.warnings, 1);
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/main-samples"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_attachment_processor(&|path, _contents| match path
+        .extension()
+        .is_some_and(|ext| ext == "png")
+    {
+        true => panic!("refusing to process images"),
+        false => PostprocessorResult::Continue,
+    });
+    exporter.catch_postprocessor_panics(true);
+    let summary = exporter.run_with_summary().unwrap();
+
+    assert!(!tmp_dir.path().join("white.png").exists());
+    assert!(tmp_dir.path().join("note.pdf").exists());
+    assert_eq!(
+        summary.skipped,
+        vec![(
+            PathBuf::from("tests/testdata/input/main-samples/white.png"),
+            SkipReason::PostprocessorPanicked
+        )]
+    );
+}
+
+fn abort_on_note(
+    context: Context,
+    events: MarkdownEvents,
+) -> (Context, MarkdownEvents, PostprocessorResult) {
+    if context.current_file().ends_with("Note.md") {
+        let result = PostprocessorResult::Abort("missing required frontmatter".to_string());
+        return (context, events, result);
+    }
+    (context, events, PostprocessorResult::Continue)
+}
+
+#[test]
+fn test_postprocessor_abort() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/postprocessors"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&abort_on_note);
+
+    match exporter.run() {
+        Err(ExportError::FileExportError { path, source }) => {
+            let note = PathBuf::from("tests/testdata/input/postprocessors/Note.md");
+            assert_eq!(path, note);
+            match *source {
+                ExportError::PostprocessorAborted { path, message } => {
+                    assert_eq!(path, note);
+                    assert_eq!(message, "missing required frontmatter");
+                }
+                err => panic!("wrong error variant: {:?}", err),
+            }
+        }
+        Err(err) => panic!("wrong error variant: {:?}", err),
+        Ok(()) => panic!("export should have been aborted"),
+    }
+    assert!(!tmp_dir.path().join("Note.md").exists());
+}
+
+fn abort_on_every_note(
+    context: Context,
+    events: MarkdownEvents,
+) -> (Context, MarkdownEvents, PostprocessorResult) {
+    let result = PostprocessorResult::Abort("aborting every note".to_string());
+    (context, events, result)
+}
+
+#[test]
+fn test_first_error_is_deterministic() {
+    for threads in [1, 4] {
+        for _ in 0..5 {
+            let tmp_dir = TempDir::new().expect("failed to make tempdir");
+            let mut exporter = Exporter::new(
+                PathBuf::from("tests/testdata/input/postprocessors"),
+                tmp_dir.path().to_path_buf(),
+            );
+            exporter.threads(threads);
+            exporter.add_postprocessor(&abort_on_every_note);
+
+            match exporter.run() {
+                Err(ExportError::FileExportError { path, .. }) => assert_eq!(
+                    path,
+                    PathBuf::from("tests/testdata/input/postprocessors/Note.md"),
+                    "threads: {}",
+                    threads
+                ),
+                Err(err) => panic!("unexpected error: {:?}", err),
+                Ok(()) => panic!("export should have been aborted"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_convert_callouts() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/callouts"),
+        tmp_dir.path().to_path_buf(),
+    );
+    let mut type_map = HashMap::new();
+    type_map.insert("tip".to_string(), "info".to_string());
+    let convert = convert_callouts(CalloutStyle::Admonition, type_map.clone());
+    exporter.add_postprocessor(&convert);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/callouts/Note.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
+    assert_eq!(expected, actual);
+
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/callouts"),
+        tmp_dir.path().to_path_buf(),
+    );
+    let convert = convert_callouts(CalloutStyle::HtmlClass, type_map);
+    exporter.output_format(OutputFormat::Html);
+    exporter.add_postprocessor(&convert);
+    exporter.run().unwrap();
+
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.html"))).unwrap();
+    assert!(actual.contains(
+        "<div class=\"callout callout-info\">\n<p class=\"callout-title\">Use this</p>\n<p>Body line</p>\n<div class=\"callout callout-warning\">\n<p>Careful.</p>\n</div>\n</div>\n<blockquote>"
+    ));
+}
+
+#[test]
+fn test_callouts_to_github_alerts() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/github-alerts/Callouts.md"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&callouts_to_github_alerts);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/github-alerts/Callouts.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Callouts.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_github_alerts_to_callouts() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/github-alerts/Alerts.md"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&github_alerts_to_callouts);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/github-alerts/Alerts.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Alerts.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_handle_query_blocks() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/query-blocks/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    let handle_queries = handle_query_blocks("This query can only be viewed in Obsidian.".into());
+    exporter.add_postprocessor(&handle_queries);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/query-blocks/Index.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Index.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_normalize_taxonomies() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/taxonomies/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    let normalize = normalize_taxonomies(vec![
+        "tags".to_string(),
+        "categories".to_string(),
+        "series".to_string(),
+    ]);
+    exporter.add_postprocessor(&normalize);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/taxonomies/Note.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_frontmatter_as_export_const() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/export-const"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.frontmatter_as_export_const(true);
+    exporter.add_postprocessor(&|mut context, events| {
+        context.set_destination_extension("mdx");
+        (context, events, PostprocessorResult::Continue)
+    });
+    exporter.run().unwrap();
+
+    assert_eq!(
+        concat!(
+            "export const frontmatter = {\"title\": \"Say \\\"hello\\\"\", \"tags\": [\"mdx\", \"js\"], ",
+            "\"draft\": false, \"weight\": 2.5, \"author\": {\"name\": \"Jane\"}};\n",
+            "\n",
+            "Body text.\n"
+        ),
+        read_to_string(tmp_dir.path().join("Note.mdx")).unwrap()
+    );
+}
+
+#[test]
+fn test_highlights_to_html() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/highlights/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&highlights_to_html);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/highlights/Note.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_escape_mdx() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/escape-mdx/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter.add_postprocessor(&escape_mdx);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/escape-mdx/Note.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_tag_hierarchies() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/tag-hierarchies/"),
+        tmp_dir.path().to_path_buf(),
+    );
+    let categories = tag_hierarchies(vec!["area".to_string()], "categories".to_string());
+    exporter.add_postprocessor(&categories);
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/tag-hierarchies/Note.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn test_wasm_postprocessor() {
+    let tmp_dir = TempDir::new().expect("failed to make tempdir");
+    let mut exporter = Exporter::new(
+        PathBuf::from("tests/testdata/input/wasm"),
+        tmp_dir.path().to_path_buf(),
+    );
+    exporter
+        .add_wasm_postprocessor(PathBuf::from("tests/testdata/wasm/uppercase.wat"))
+        .unwrap();
+    exporter.run().unwrap();
+
+    let expected = read_to_string("tests/testdata/expected/wasm/Note.md").unwrap();
+    let actual = read_to_string(tmp_dir.path().join(PathBuf::from("Note.md"))).unwrap();
+    assert_eq!(expected, actual);
+}
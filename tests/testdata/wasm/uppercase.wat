@@ -0,0 +1,70 @@
+;; A postprocessor which uppercases the ASCII letters of all values of the JSON objects it's passed,
+;; which includes the markdown of a note and the values of its frontmatter. The input is modified in
+;; place and returned as the output.
+(module
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+
+  ;; A bump allocator, growing the memory as needed.
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (if (i32.gt_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (drop (memory.grow
+          (i32.add
+            (i32.div_u
+              (i32.sub (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+              (i32.const 65536))
+            (i32.const 1))))))
+    (local.get $ptr))
+
+  ;; Strings directly following a colon are values, other strings are keys (or items of arrays),
+  ;; which are left as-is.
+  (func (export "process") (param $ptr i32) (param $len i32) (result i64)
+    (local $i i32)
+    (local $end i32)
+    (local $c i32)
+    (local $in_string i32)
+    (local $is_value i32)
+    (local $prev i32)
+    (local.set $i (local.get $ptr))
+    (local.set $end (i32.add (local.get $ptr) (local.get $len)))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $end)))
+        (local.set $c (i32.load8_u (local.get $i)))
+        (if (local.get $in_string)
+          (then
+            (if (i32.eq (local.get $c) (i32.const 92))
+              (then
+                ;; Skip the character following a backslash, as in \n.
+                (local.set $i (i32.add (local.get $i) (i32.const 1))))
+              (else
+                (if (i32.eq (local.get $c) (i32.const 34))
+                  (then
+                    (local.set $in_string (i32.const 0))
+                    (local.set $prev (i32.const 34)))
+                  (else
+                    (if (i32.and
+                          (local.get $is_value)
+                          (i32.and
+                            (i32.ge_u (local.get $c) (i32.const 97))
+                            (i32.le_u (local.get $c) (i32.const 122))))
+                      (then
+                        (i32.store8 (local.get $i) (i32.sub (local.get $c) (i32.const 32))))))))))
+          (else
+            (if (i32.eq (local.get $c) (i32.const 34))
+              (then
+                (local.set $in_string (i32.const 1))
+                (local.set $is_value (i32.eq (local.get $prev) (i32.const 58))))
+              (else
+                (if (i32.gt_u (local.get $c) (i32.const 32))
+                  (then (local.set $prev (local.get $c))))))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len))))
+)